@@ -0,0 +1,170 @@
+//! Per-frame cost of a glide, which has to stay allocation free so that
+//! high refresh rates don't add up to noticeable CPU time
+//!
+//! Frames are emitted through the virtual mouse, as a uinput report written
+//! to `/dev/null` and as Wayland requests sent to a stand-in compositor.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use inertpad::{
+    Axes, InertiaSink, InertiaState, OutputDevice, Routing, VirtualMouse, VirtualPointer,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fs,
+    io::{Read, Write},
+    os::unix::net::UnixListener,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// System allocator counting the allocations made through it
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const DRAG: f64 = 0.15;
+const SPEED_FACTOR: f64 = 0.0075;
+const VELOCITY: (f64, f64) = (3000.0, -1500.0);
+
+/// Horizontal pointer motion and vertical scrolling, so that a frame goes
+/// through both the motion and the wheel path
+const ROUTING: Routing = Routing {
+    x: InertiaSink::Pointer,
+    y: InertiaSink::Scroll,
+};
+
+/// A frame as the daemon runs it with the default options, emitted through
+/// `mouse`, stopping the glide once its delta is negligible
+fn frame(inertia: &mut InertiaState, mouse: &mut VirtualMouse) {
+    let (dx, dy) = inertia.frame_delta((SPEED_FACTOR, SPEED_FACTOR));
+    if dx.abs() < InertiaState::STOP_DELTA && dy.abs() < InertiaState::STOP_DELTA {
+        inertia.stop();
+        return;
+    }
+    inertia.decelerate((1.0 - DRAG, 1.0 - DRAG));
+    let (x, y) = inertia.round((dx, dy), true, |value| value as i32);
+    mouse.emit_motion(x, y).unwrap();
+}
+
+/// Runs a whole glide through `mouse`
+fn glide(mouse: &mut VirtualMouse) {
+    let mut inertia = InertiaState::new(VELOCITY.0, VELOCITY.1);
+    while !inertia.is_stopped() {
+        frame(&mut inertia, mouse);
+    }
+}
+
+fn virtual_mouse(device: OutputDevice) -> VirtualMouse {
+    VirtualMouse::from_device(device, &[ROUTING], Axes::Both, None, None)
+}
+
+/// Answers the handshake of `VirtualPointer::connect` on a socket of its
+/// own, then reads and drops the requests
+///
+/// Stands in for a compositor offering the virtual pointer protocol.
+fn fake_compositor() -> VirtualPointer {
+    let path = std::env::temp_dir().join(format!("inertpad-bench-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    std::env::set_var("WAYLAND_DISPLAY", &path);
+    // Detached, it reads until the pointer is dropped
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut event = |object: u32, args: &[u32]| {
+            let size = 8 + 4 * args.len() as u32;
+            stream.write_all(&object.to_ne_bytes()).unwrap();
+            stream.write_all(&(size << 16).to_ne_bytes()).unwrap();
+            for arg in args {
+                stream.write_all(&arg.to_ne_bytes()).unwrap();
+            }
+        };
+        // wl_registry.global of the manager and of the seat, as strings of
+        // (length, bytes padded to words)
+        let global = |name: u32, interface: &str, version: u32| {
+            let mut args = vec![name, interface.len() as u32 + 1];
+            let mut bytes = interface.as_bytes().to_vec();
+            bytes.resize((bytes.len() + 1).div_ceil(4) * 4, 0);
+            args.extend(
+                bytes
+                    .chunks(4)
+                    .map(|word| u32::from_ne_bytes(word.try_into().unwrap())),
+            );
+            args.push(version);
+            args
+        };
+        event(2, &global(1, "zwlr_virtual_pointer_manager_v1", 1));
+        event(2, &global(2, "wl_seat", 7));
+        // wl_callback.done of the first roundtrip, then of the second one
+        event(3, &[0]);
+        event(7, &[0]);
+        let mut buffer = [0u8; 4096];
+        while stream.read(&mut buffer).is_ok_and(|read| read > 0) {}
+    });
+    let pointer = VirtualPointer::connect().unwrap();
+    let _ = fs::remove_file(&path);
+    pointer
+}
+
+/// Asserts that a whole glide through `mouse` doesn't allocate, once a first
+/// one has grown the buffers it reuses
+fn assert_allocation_free(name: &str, mouse: &mut VirtualMouse) {
+    glide(mouse);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    glide(mouse);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    assert_eq!(
+        allocations, 0,
+        "{} glide frames allocated {} times",
+        name, allocations
+    );
+}
+
+fn frames(c: &mut Criterion) {
+    let null = fs::OpenOptions::new()
+        .write(true)
+        .open("/dev/null")
+        .unwrap();
+    let mut uinput = virtual_mouse(OutputDevice::Inherited(null));
+    let mut wayland = virtual_mouse(OutputDevice::Wayland(fake_compositor()));
+    assert_allocation_free("uinput", &mut uinput);
+    assert_allocation_free("Wayland", &mut wayland);
+
+    for (name, mouse) in [
+        ("glide frame", &mut uinput),
+        ("wayland glide frame", &mut wayland),
+    ] {
+        // Relaunched as it stops, so that every iteration is a frame
+        let mut inertia = InertiaState::new(VELOCITY.0, VELOCITY.1);
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                if inertia.is_stopped() {
+                    inertia = InertiaState::new(VELOCITY.0, VELOCITY.1);
+                }
+                frame(black_box(&mut inertia), mouse)
+            })
+        });
+    }
+    c.bench_function("whole glide", |b| b.iter(|| glide(black_box(&mut uinput))));
+}
+
+criterion_group!(benches, frames);
+criterion_main!(benches);
@@ -0,0 +1,12 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    // Honors SOURCE_DATE_EPOCH for reproducible builds
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH").unwrap_or_else(|_| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_secs())
+            .to_string()
+    });
+    println!("cargo:rustc-env=INERTPAD_BUILD_TIMESTAMP={}", timestamp);
+}
@@ -0,0 +1,100 @@
+//! Lift-off speeds learned by `--adaptive-threshold`, kept across restarts
+
+use std::{
+    collections::VecDeque,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Most recent lift-off speeds, oldest first
+///
+/// Shared by every capture run, so that reconnecting the touchpad keeps
+/// what was learned.
+#[derive(Debug)]
+pub struct SpeedHistory {
+    speeds: Mutex<VecDeque<f64>>,
+    capacity: usize,
+}
+
+impl SpeedHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            speeds: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Adds a lift-off speed, dropping the oldest one if full
+    pub fn push(&self, speed: f64) {
+        let mut speeds = self.speeds.lock().unwrap();
+        if speeds.len() == self.capacity {
+            speeds.pop_front();
+        }
+        speeds.push_back(speed);
+    }
+
+    /// Known speeds in ascending order
+    pub fn sorted(&self) -> Vec<f64> {
+        let mut speeds: Vec<_> = self.speeds.lock().unwrap().iter().copied().collect();
+        speeds.sort_by(f64::total_cmp);
+        speeds
+    }
+
+    /// Replaces the speeds with those saved at `path`, one per line,
+    /// returning how many were loaded
+    ///
+    /// A file that doesn't parse as a whole is rejected, leaving no speeds.
+    pub fn load(&self, path: &Path) -> io::Result<usize> {
+        let text = fs::read_to_string(path)?;
+        let parsed: Option<VecDeque<f64>> = text
+            .lines()
+            .map(|line| {
+                line.trim()
+                    .parse()
+                    .ok()
+                    .filter(|s: &f64| s.is_finite() && *s >= 0.0)
+            })
+            .collect();
+        let Some(mut loaded) = parsed else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a list of speeds",
+            ));
+        };
+        // Keep the newest, should the window have shrunk
+        let excess = loaded.len().saturating_sub(self.capacity);
+        loaded.drain(..excess);
+        let count = loaded.len();
+        *self.speeds.lock().unwrap() = loaded;
+        Ok(count)
+    }
+
+    /// Writes the speeds to `path`, creating its directory if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text: String = self
+            .speeds
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|speed| format!("{}\n", speed))
+            .collect();
+        // Replaced at once, so that a crash mid-write leaves the old file
+        let partial = path.with_extension("tmp");
+        fs::write(&partial, text)?;
+        fs::rename(&partial, path)
+    }
+}
+
+/// Default state file: `$XDG_STATE_HOME/inertpad/adaptive-speeds`, or
+/// under `~/.local/state` if unset
+pub fn state_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))?;
+    Some(dir.join("inertpad").join("adaptive-speeds"))
+}
@@ -0,0 +1,157 @@
+//! Capture measurements for `--benchmark-capture` and `--probe-rate`
+
+use std::time::{Duration, SystemTime};
+
+/// Upper bounds of the latency histogram buckets in microseconds
+const LATENCY_BUCKETS_US: [u64; 12] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1000, 2000, 5000];
+
+/// Upper bounds of the report interval histogram buckets in microseconds
+const INTERVAL_BUCKETS_US: [u64; 12] = [
+    1000, 2000, 4000, 6000, 8000, 10000, 12000, 14000, 17000, 20000, 25000, 50000,
+];
+
+/// Width of the longest histogram bar in characters
+const BAR_WIDTH: u64 = 40;
+
+#[derive(Debug)]
+struct Histogram {
+    /// Upper bounds of the buckets in microseconds
+    buckets: &'static [u64],
+    /// Sample counts per bucket, the last one is unbounded
+    counts: Vec<u64>,
+    total: Duration,
+    max: Duration,
+}
+
+impl Histogram {
+    fn new(buckets: &'static [u64]) -> Self {
+        Self {
+            buckets,
+            counts: vec![0; buckets.len() + 1],
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+        }
+    }
+
+    fn record(&mut self, duration: Duration) {
+        let us = duration.as_micros();
+        let bucket = self
+            .buckets
+            .iter()
+            .position(|&upper| us <= u128::from(upper))
+            .unwrap_or(self.buckets.len());
+        self.counts[bucket] += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    fn log(&self, title: &str) {
+        let count: u64 = self.counts.iter().sum();
+        if count == 0 {
+            log::info!("{}: no samples", title);
+            return;
+        }
+        log::info!(
+            "{}: {} samples, mean {:?}, max {:?}",
+            title,
+            count,
+            self.total.div_f64(count as f64),
+            self.max
+        );
+        let peak = self.counts.iter().copied().max().unwrap_or(1);
+        let mut lower = 0;
+        for (i, &n) in self.counts.iter().enumerate() {
+            let label = match self.buckets.get(i) {
+                Some(&upper) => format!("{:>5}..{:<5} us", lower, upper),
+                None => format!("{:>5}..      us", lower),
+            };
+            let bar = "#".repeat((n * BAR_WIDTH).div_ceil(peak) as usize);
+            log::info!("  {} {:>8} {}", label, n, bar);
+            lower = self.buckets.get(i).copied().unwrap_or(lower);
+        }
+    }
+}
+
+/// Latency statistics of the touchpad capture
+#[derive(Debug)]
+pub struct CaptureBenchmark {
+    handling: Histogram,
+    lift_off: Histogram,
+}
+
+impl Default for CaptureBenchmark {
+    fn default() -> Self {
+        Self {
+            handling: Histogram::new(&LATENCY_BUCKETS_US),
+            lift_off: Histogram::new(&LATENCY_BUCKETS_US),
+        }
+    }
+}
+
+impl CaptureBenchmark {
+    /// Records the time spent handling a single event after receipt
+    pub fn record_handling(&mut self, duration: Duration) {
+        self.handling.record(duration);
+    }
+
+    /// Records the time from the lift-off event to sending `StartMovement`
+    pub fn record_lift_off(&mut self, duration: Duration) {
+        self.lift_off.record(duration);
+    }
+
+    pub fn log_summary(&self) {
+        self.handling.log("Event handling time");
+        self.lift_off.log("Lift-off to StartMovement latency");
+    }
+}
+
+/// Intervals between the touchpad reports of finger contacts
+#[derive(Debug)]
+pub struct RateProbe {
+    histogram: Histogram,
+    intervals: Vec<Duration>,
+    /// Timestamp of the previous report of the current contact
+    last_report: Option<SystemTime>,
+}
+
+impl Default for RateProbe {
+    fn default() -> Self {
+        Self {
+            histogram: Histogram::new(&INTERVAL_BUCKETS_US),
+            intervals: Vec::new(),
+            last_report: None,
+        }
+    }
+}
+
+impl RateProbe {
+    /// Records a report made while a finger is in contact
+    pub fn record_report(&mut self, timestamp: SystemTime) {
+        let interval = self
+            .last_report
+            .and_then(|last| timestamp.duration_since(last).ok())
+            .filter(|interval| !interval.is_zero());
+        if let Some(interval) = interval {
+            self.histogram.record(interval);
+            self.intervals.push(interval);
+        }
+        self.last_report = Some(timestamp);
+    }
+
+    /// Ends the current contact, the gap until the next one isn't an interval
+    pub fn end_contact(&mut self) {
+        self.last_report = None;
+    }
+
+    /// Native report rate of the touchpad in Hz, from the median interval
+    pub fn report_rate(&self) -> Option<f64> {
+        let mut intervals = self.intervals.clone();
+        intervals.sort_unstable();
+        let median = intervals.get(intervals.len() / 2)?;
+        Some(median.as_secs_f64().recip())
+    }
+
+    pub fn log_summary(&self) {
+        self.histogram.log("Report interval");
+    }
+}
@@ -0,0 +1,438 @@
+//! Configuration as TOML: the config file, and the effective configuration
+//! for `--dump-config` and `--check-config`
+
+use clap::{ArgMatches, Command};
+use inertpad::Params;
+use std::{
+    ffi::{OsStr, OsString},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Options that are actions rather than configuration
+const SKIPPED: [&str; 6] = [
+    "help",
+    "version",
+    "config",
+    "list_devices",
+    "dump_config",
+    "check_config",
+];
+
+/// Key of the `[device."<name>"]` tables, shared with the `--device` option
+const DEVICE_KEY: &str = "device";
+
+/// Prefix of the environment variables options are read from
+const ENV_PREFIX: &str = "INERTPAD_";
+
+/// Alternative environment variable names and the variables they stand for
+const ENV_ALIASES: [(&str, &str); 1] = [("INERTPAD_MAX_DURATION", "INERTPAD_MAX_GLIDE_MS")];
+
+/// Width of the wrapped help comments
+const COMMENT_WIDTH: usize = 78;
+
+/// Renders every option as a TOML document, keyed by its long flag,
+/// followed by the device blocks
+///
+/// Each key is preceded by the option's help as a comment. Options that are
+/// unset and have no default are left commented out, and so is `device`
+/// when device blocks take its key.
+pub fn to_toml(mut command: Command, matches: &ArgMatches, device_blocks: &DeviceBlocks) -> String {
+    // Fills in the implied value counts and delimiters
+    command.build();
+    let mut out = String::from("# InertPad configuration\n");
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        let Some(key) = arg.get_long() else {
+            continue;
+        };
+        if SKIPPED.contains(&id) {
+            continue;
+        }
+        out.push('\n');
+        if let Some(help) = arg.get_long_help().or(arg.get_help()) {
+            write_comment(&mut out, &help.to_string());
+        }
+        let values: Option<Vec<String>> = matches
+            .get_raw(id)
+            .map(|raw| raw.map(|v| v.to_string_lossy().into_owned()).collect());
+        match values {
+            Some(values) if key != DEVICE_KEY || device_blocks.is_empty() => {
+                let _ = writeln!(out, "{} = {}", key, toml_value(arg, &values));
+            }
+            Some(values) => {
+                let _ = writeln!(out, "# {} = {}", key, toml_value(arg, &values));
+            }
+            None => {
+                let _ = writeln!(out, "# {} =", key);
+            }
+        }
+    }
+    for (name, block) in &device_blocks.0 {
+        let _ = writeln!(out, "\n[{}.{:?}]", DEVICE_KEY, name);
+        for (key, value) in block {
+            let _ = writeln!(out, "{} = {}", key, toml_literal(value));
+        }
+    }
+    out
+}
+
+/// Renders the inertia parameters as a TOML document, keyed by the long
+/// flags that set them
+///
+/// Unset optional parameters are left commented out.
+pub fn params_toml(params: &Params) -> String {
+    let number = |value: f64| Some(value.to_string());
+    let optional = |value: Option<f64>| value.map(|value| value.to_string());
+    let entries = [
+        (
+            "drag",
+            match (params.drag_x, params.drag_y) {
+                (None, None) => number(params.drag),
+                (x, y) => Some(format!(
+                    "\"{},{}\"",
+                    x.unwrap_or(params.drag),
+                    y.unwrap_or(params.drag)
+                )),
+            },
+        ),
+        ("speed-factor", number(params.speed_factor)),
+        ("speed-threshold", number(params.speed_threshold)),
+        ("response-curve", number(params.response_curve)),
+        ("min-launch-speed", number(params.min_launch_speed)),
+        ("settle-damp", number(params.settle_damp)),
+        ("soft-threshold", number(params.soft_threshold)),
+        ("speed-threshold-low", optional(params.speed_threshold_low)),
+        (
+            "drag-curve",
+            params
+                .drag_curve
+                .map(|curve| format!("\"{}:{}\"", curve.start, curve.end)),
+        ),
+        ("scroll-drag", optional(params.scroll_drag)),
+        ("scroll-speed-factor", optional(params.scroll_speed_factor)),
+        ("speed-factor-x", optional(params.speed_factor_x)),
+        ("speed-factor-y", optional(params.speed_factor_y)),
+        (
+            "scroll-speed-threshold",
+            optional(params.scroll_speed_threshold),
+        ),
+    ];
+    let mut out = String::from("# Effective InertPad parameters\n");
+    for (key, value) in entries {
+        let _ = match value {
+            Some(value) => writeln!(out, "{} = {}", key, value),
+            None => writeln!(out, "# {} =", key),
+        };
+    }
+    out
+}
+
+/// Default config file: `$XDG_CONFIG_HOME/inertpad/config.toml`, or under
+/// `~/.config` if unset
+pub fn default_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(dir.join("inertpad").join("config.toml"))
+}
+
+/// Config files named with `--config`, in order, or else with
+/// `INERTPAD_CONFIG`, which are needed before the command line can be parsed
+pub fn explicit_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            paths.extend(args.next().map(PathBuf::from));
+        } else if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+            paths.push(PathBuf::from(path));
+        }
+    }
+    if paths.is_empty() {
+        paths.extend(std::env::var_os(format!("{}CONFIG", ENV_PREFIX)).map(PathBuf::from));
+    }
+    paths
+}
+
+/// Option variables set before any config file is applied, which take
+/// precedence over every file
+pub fn preset_env_vars(command: &Command) -> Vec<OsString> {
+    command
+        .get_arguments()
+        .filter_map(|arg| arg.get_env())
+        .filter(|env| std::env::var_os(env).is_some())
+        .map(|env| env.to_owned())
+        .collect()
+}
+
+/// Passes aliased environment variables on to the variables they stand for,
+/// unless those are set too
+pub fn apply_env_aliases() {
+    for (alias, env) in ENV_ALIASES {
+        if let Some(value) = std::env::var_os(alias).filter(|_| std::env::var_os(env).is_none()) {
+            std::env::set_var(env, value);
+        }
+    }
+}
+
+/// Options a config file set, and those the environment kept
+#[derive(Debug, Default)]
+pub struct Applied {
+    /// Number of options the file set
+    pub options: usize,
+    /// Keys of the file left out, and the environment variables that
+    /// override them
+    pub overridden: Vec<(String, String)>,
+    /// `[device."<name>"]` tables of the file, applied once the device is
+    /// known
+    pub device_blocks: DeviceBlocks,
+}
+
+/// `[device."<name>"]` tables of options applying to one device only,
+/// merged by name over the config files
+#[derive(Clone, Debug, Default)]
+pub struct DeviceBlocks(Vec<(String, toml::Table)>);
+
+impl DeviceBlocks {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Merges the blocks of a later file, its keys overriding
+    pub fn merge(&mut self, other: DeviceBlocks) {
+        for (name, block) in other.0 {
+            self.insert(name, block);
+        }
+    }
+
+    fn insert(&mut self, name: String, block: toml::Table) {
+        match self
+            .0
+            .iter_mut()
+            .find(|(known, _)| same_device(known, &name))
+        {
+            Some((_, known)) => known.extend(block),
+            None => self.0.push((name, block)),
+        }
+    }
+
+    /// Block of the device with the given name, which matches
+    /// case-insensitively
+    pub fn find(&self, device: &str) -> Option<(&str, &toml::Table)> {
+        self.0
+            .iter()
+            .find(|(name, _)| same_device(name, device))
+            .map(|(name, block)| (name.as_str(), block))
+    }
+}
+
+/// Whether two device names refer to the same device, as device blocks
+/// are looked up
+pub fn same_device(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+/// Applies a TOML config file keyed by long flag, as written by
+/// `--dump-config`
+///
+/// Values are passed on as the options' environment variables, so that
+/// the command line and the variables of `preset` take precedence: command
+/// line, then environment, then the files, then the defaults. Variables
+/// set by an earlier file are replaced, so that later files override
+/// earlier ones. `_` in keys stands for `-`.
+///
+/// Device blocks are checked, but left to [`apply_device_block`].
+pub fn apply_file(
+    mut command: Command,
+    path: &Path,
+    preset: &[OsString],
+) -> Result<Applied, String> {
+    command.build();
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let table: toml::Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+    let mut applied = Applied::default();
+    for (key, value) in &table {
+        match value {
+            toml::Value::Table(blocks) if key == DEVICE_KEY => {
+                for (name, block) in blocks {
+                    let toml::Value::Table(block) = block else {
+                        return Err(format!("device.{:?} must be a table of options", name));
+                    };
+                    for (key, value) in block {
+                        option_env(&command, key, value)
+                            .map_err(|e| format!("device.{:?}: {}", name, e))?;
+                    }
+                    applied.device_blocks.insert(name.clone(), block.clone());
+                }
+            }
+            value => apply_option(&command, key, value, preset, &mut applied)?,
+        }
+    }
+    Ok(applied)
+}
+
+/// Applies the options of a device block over those of the config files,
+/// with the same precedence of the command line and `preset`
+pub fn apply_device_block(
+    mut command: Command,
+    block: &toml::Table,
+    preset: &[OsString],
+) -> Result<Applied, String> {
+    command.build();
+    let mut applied = Applied::default();
+    for (key, value) in block {
+        apply_option(&command, key, value, preset, &mut applied)?;
+    }
+    Ok(applied)
+}
+
+fn apply_option(
+    command: &Command,
+    key: &str,
+    value: &toml::Value,
+    preset: &[OsString],
+    applied: &mut Applied,
+) -> Result<(), String> {
+    let (env, value) = option_env(command, key, value)?;
+    if preset.iter().any(|preset| preset == env) {
+        let env = env.to_string_lossy().into_owned();
+        applied.overridden.push((key.to_owned(), env));
+    } else {
+        std::env::set_var(env, value);
+        applied.options += 1;
+    }
+    Ok(())
+}
+
+/// Environment variable of the option a key sets, and its value
+fn option_env<'a>(
+    command: &'a Command,
+    key: &str,
+    value: &toml::Value,
+) -> Result<(&'a OsStr, String), String> {
+    let long = key.replace('_', "-");
+    let arg = command
+        .get_arguments()
+        .filter(|arg| !SKIPPED.contains(&arg.get_id().as_str()))
+        .find(|arg| {
+            arg.get_long() == Some(long.as_str())
+                || arg
+                    .get_all_aliases()
+                    .is_some_and(|aliases| aliases.contains(&long.as_str()))
+        })
+        .ok_or_else(|| format!("unknown option {:?}", key))?;
+    let env = arg
+        .get_env()
+        .ok_or_else(|| format!("{:?} can't be set in the config file", key))?;
+    let value = env_value(arg, value).map_err(|e| format!("{}: {}", key, e))?;
+    Ok((env, value))
+}
+
+/// `INERTPAD_*` environment variables that no option reads, such as typos
+pub fn unknown_env_vars(command: &Command) -> Vec<String> {
+    let known: Vec<_> = command
+        .get_arguments()
+        .filter_map(|arg| arg.get_env())
+        .chain(ENV_ALIASES.iter().map(|(alias, _)| alias.as_ref()))
+        .collect();
+    let mut unknown: Vec<_> = std::env::vars_os()
+        .map(|(name, _)| name)
+        .filter(|name| !known.contains(&name.as_os_str()))
+        .filter_map(|name| name.into_string().ok())
+        .filter(|name| name.starts_with(ENV_PREFIX))
+        .collect();
+    unknown.sort();
+    unknown
+}
+
+fn env_value(arg: &clap::Arg, value: &toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(value) => Ok(value.clone()),
+        toml::Value::Integer(value) => Ok(value.to_string()),
+        toml::Value::Float(value) => Ok(value.to_string()),
+        toml::Value::Boolean(value) => Ok(value.to_string()),
+        toml::Value::Array(items) => {
+            let delimiter = arg
+                .get_value_delimiter()
+                .ok_or("expected a single value, not a list")?;
+            let items = items
+                .iter()
+                .map(|item| match item {
+                    toml::Value::Array(_) => Err("expected a list of values".to_owned()),
+                    item => env_value(arg, item),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(items.join(&delimiter.to_string()))
+        }
+        toml::Value::Datetime(_) | toml::Value::Table(_) => {
+            Err("expected a string, number, boolean or list".to_owned())
+        }
+    }
+}
+
+fn toml_value(arg: &clap::Arg, values: &[String]) -> String {
+    let multiple = arg
+        .get_num_args()
+        .is_some_and(|range| range.max_values() > 1)
+        || arg.get_value_delimiter().is_some();
+    if multiple {
+        let items: Vec<_> = values.iter().map(|v| toml_scalar(v)).collect();
+        format!("[{}]", items.join(", "))
+    } else {
+        values.first().map_or_else(String::new, |v| toml_scalar(v))
+    }
+}
+
+/// Value read from a config file, written back as TOML
+fn toml_literal(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => format!("{:?}", value),
+        toml::Value::Array(items) => {
+            let items: Vec<_> = items.iter().map(toml_literal).collect();
+            format!("[{}]", items.join(", "))
+        }
+        toml::Value::Integer(value) => value.to_string(),
+        toml::Value::Float(value) => value.to_string(),
+        toml::Value::Boolean(value) => value.to_string(),
+        toml::Value::Datetime(value) => value.to_string(),
+        toml::Value::Table(table) => {
+            let entries: Vec<_> = table
+                .iter()
+                .map(|(key, value)| format!("{} = {}", key, toml_literal(value)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+    }
+}
+
+/// Keeps numbers and booleans bare, quotes everything else
+fn toml_scalar(value: &str) -> String {
+    if value == "true" || value == "false" || value.parse::<f64>().is_ok_and(f64::is_finite) {
+        value.to_owned()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+fn write_comment(out: &mut String, text: &str) {
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > COMMENT_WIDTH - 2 {
+            let _ = writeln!(out, "# {}", line);
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        let _ = writeln!(out, "# {}", line);
+    }
+}
@@ -0,0 +1,397 @@
+//! Control socket accepting line-based text commands
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread, time,
+};
+
+use crate::{metrics::Metrics, EmulationHandle};
+use inertpad::{GestureHooks, LiveParams, Params};
+
+/// Summary of the most recent glide
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlideSummary {
+    /// Launch speed after the response curve, in touchpad units per second
+    pub launch_speed: f64,
+    /// Distance emitted so far, in pixels or high-resolution wheel units
+    pub distance: f64,
+    /// When the glide came to a stop on its own
+    pub ended: Option<time::SystemTime>,
+}
+
+/// Configuration context reported by `info`
+#[derive(Debug)]
+pub struct RunInfo {
+    /// What drives the emulation, e.g. `touchpad inertia-as=pointer`
+    pub mode: String,
+    /// Whether metrics are served over HTTP
+    pub metrics: bool,
+}
+
+/// Device the capture thread reads from
+#[derive(Debug, Clone)]
+pub struct DeviceInfo {
+    pub name: String,
+    pub path: PathBuf,
+    /// Version of the kernel evdev interface
+    pub driver_version: (u8, u8, u8),
+}
+
+/// State shared with the control socket
+#[derive(Debug)]
+pub struct ControlState {
+    started: time::Instant,
+    /// Whether the capture thread currently has a touchpad open
+    pub capture_connected: AtomicBool,
+    /// Set by `--safe-mode-pause` on runaway emission and by `--strict` on
+    /// non-finite glide values, cleared by `resume`
+    pub safe_mode: AtomicBool,
+    /// Cleared by `disable` to switch inertia off until `enable`
+    pub enabled: AtomicBool,
+    /// Updated by the emulation loop as the glide progresses
+    pub last_glide: Mutex<GlideSummary>,
+    /// Connections streaming emulation frames
+    subscribers: Mutex<Vec<mpsc::SyncSender<String>>>,
+    emulation: EmulationHandle,
+    params: Arc<LiveParams>,
+    /// Parameters from the command line, restored by `reset`
+    startup_params: Params,
+    /// Also served over HTTP with `--metrics-addr`
+    pub metrics: Metrics,
+    /// `use-device` request waiting for the capture thread
+    device_request: Mutex<Option<DeviceRequest>>,
+    /// Polled by the capture thread, readable while a request is pending
+    pub capture_wake: UnixStream,
+    wake_sender: UnixStream,
+    run_info: RunInfo,
+    /// Most recently opened capture device
+    device: Mutex<Option<DeviceInfo>>,
+    /// Embedder callbacks on flicks and glides
+    pub hooks: GestureHooks,
+}
+
+/// Request to capture from another device
+#[derive(Debug)]
+pub struct DeviceRequest {
+    /// Device node path or name
+    pub query: String,
+    /// Receives the reply line
+    pub reply: mpsc::Sender<String>,
+}
+
+impl ControlState {
+    pub fn new(
+        emulation: EmulationHandle,
+        params: Arc<LiveParams>,
+        run_info: RunInfo,
+        hooks: GestureHooks,
+    ) -> io::Result<Self> {
+        let (capture_wake, wake_sender) = UnixStream::pair()?;
+        capture_wake.set_nonblocking(true)?;
+        Ok(Self {
+            startup_params: params.load(),
+            params,
+            started: time::Instant::now(),
+            capture_connected: AtomicBool::new(false),
+            safe_mode: AtomicBool::new(false),
+            enabled: AtomicBool::new(true),
+            last_glide: Mutex::new(GlideSummary::default()),
+            subscribers: Mutex::new(Vec::new()),
+            emulation,
+            metrics: Metrics::new(),
+            device_request: Mutex::new(None),
+            capture_wake,
+            wake_sender,
+            run_info,
+            device: Mutex::new(None),
+            hooks,
+        })
+    }
+
+    /// Sends a line to every subscriber, dropping it for those lagging behind
+    ///
+    /// The line is only formatted if anyone is subscribed.
+    pub fn publish(&self, line: impl FnOnce() -> String) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+        let line = line();
+        subscribers.retain(|subscriber| match subscriber.try_send(line.clone()) {
+            Ok(()) | Err(mpsc::TrySendError::Full(_)) => true,
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        });
+    }
+
+    /// Resets the glide summary for a newly launched glide
+    pub fn start_glide(&self, vx: f64, vy: f64) {
+        let launch_speed = vx.hypot(vy);
+        self.metrics.glide_started(launch_speed);
+        *self.last_glide.lock().unwrap() = GlideSummary {
+            launch_speed,
+            distance: 0.0,
+            ended: None,
+        };
+        self.hooks.glide_started(launch_speed);
+    }
+
+    /// Marks the glide as decelerated to a stop
+    pub fn end_glide(&self) {
+        let distance = {
+            let mut glide = self.last_glide.lock().unwrap();
+            glide.ended = Some(time::SystemTime::now());
+            glide.distance
+        };
+        self.metrics.glide_completed(distance);
+        self.hooks.glide_ended(distance);
+    }
+
+    /// Adds an emitted relative movement to the glide distance
+    pub fn add_glide_distance(&self, x: i32, y: i32) {
+        self.last_glide.lock().unwrap().distance += f64::from(x).hypot(f64::from(y));
+    }
+
+    /// Undoes all live parameter changes and stops any active glide
+    pub fn reset_params(&self) {
+        self.params.store(self.startup_params);
+        self.emulation.stop();
+        log::info!(
+            "Parameters reset to startup values: {:?}",
+            self.startup_params
+        );
+    }
+
+    /// Records the device capture has opened
+    pub fn set_device(&self, device: DeviceInfo) {
+        *self.device.lock().unwrap() = Some(device);
+    }
+
+    /// Handles `info`
+    fn info(&self) -> String {
+        let features: Vec<_> = [
+            ("dbus", cfg!(feature = "dbus")),
+            ("tui", cfg!(feature = "tui")),
+            ("x11", cfg!(feature = "x11")),
+        ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect();
+        let device = match &*self.device.lock().unwrap() {
+            Some(device) => {
+                let (major, minor, patch) = device.driver_version;
+                format!(
+                    "device={:?} path={} evdev={}.{}.{}",
+                    device.name,
+                    device.path.display(),
+                    major,
+                    minor,
+                    patch
+                )
+            }
+            None => "device=none".to_owned(),
+        };
+        format!(
+            "info version={} build_timestamp={} mode={:?} features={} metrics={} {}",
+            env!("CARGO_PKG_VERSION"),
+            env!("INERTPAD_BUILD_TIMESTAMP"),
+            self.run_info.mode,
+            if features.is_empty() {
+                "none".to_owned()
+            } else {
+                features.join(",")
+            },
+            if self.run_info.metrics { "on" } else { "off" },
+            device
+        )
+    }
+
+    /// Handles `enable` and `disable`
+    fn set_enabled(&self, enabled: bool) -> String {
+        if self.enabled.swap(enabled, Ordering::Relaxed) != enabled {
+            if enabled {
+                log::info!("Inertia enabled");
+            } else {
+                log::info!("Inertia disabled until enabled again");
+                self.emulation.stop();
+            }
+        }
+        "ok".to_owned()
+    }
+
+    /// Handles `status`
+    fn status(&self) -> String {
+        let on_off = |flag: &AtomicBool| {
+            if flag.load(Ordering::Relaxed) {
+                "on"
+            } else {
+                "off"
+            }
+        };
+        let params = self.params.load();
+        format!(
+            "status enabled={} safe_mode={} mode={:?} drag={} speed_factor={} speed_threshold={}",
+            on_off(&self.enabled),
+            on_off(&self.safe_mode),
+            self.run_info.mode,
+            params.drag,
+            params.speed_factor,
+            params.speed_threshold
+        )
+    }
+
+    /// Takes the pending `use-device` request, if any, consuming the wake-up
+    pub fn take_device_request(&self) -> Option<DeviceRequest> {
+        let mut buffer = [0; 16];
+        while matches!((&self.capture_wake).read(&mut buffer), Ok(n) if n > 0) {}
+        self.device_request.lock().unwrap().take()
+    }
+
+    /// Handles `use-device <NAME|PATH>`
+    fn use_device(&self, query: &str) -> String {
+        let (reply, replies) = mpsc::channel();
+        // A request still pending is superseded, its client gets an error
+        *self.device_request.lock().unwrap() = Some(DeviceRequest {
+            query: query.to_owned(),
+            reply,
+        });
+        if let Err(e) = (&self.wake_sender).write_all(&[1]) {
+            return format!("error failed to notify capture: {}", e);
+        }
+        match replies.recv_timeout(DEVICE_SWAP_TIMEOUT) {
+            Ok(reply) => reply,
+            Err(mpsc::RecvTimeoutError::Timeout) => "error capture did not respond".to_owned(),
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                "error superseded by another use-device".to_owned()
+            }
+        }
+    }
+
+    /// Handles `flick <VX> <VY>`
+    fn flick(&self, args: &str) -> String {
+        let velocity: Vec<_> = args.split_whitespace().map(str::parse::<f64>).collect();
+        let [Ok(vx), Ok(vy)] = velocity[..] else {
+            return format!("error expected flick <VX> <VY>, got: flick {}", args);
+        };
+        // The emulation would drop the launch without a word
+        if self.safe_mode.load(Ordering::Relaxed) {
+            return "error in safe mode, send resume first".to_owned();
+        }
+        if !self.enabled.load(Ordering::Relaxed) {
+            return "error inertia is disabled, send enable first".to_owned();
+        }
+        match self.emulation.flick(vx, vy) {
+            Ok(()) => "ok".to_owned(),
+            Err(e) => format!("error {}", e),
+        }
+    }
+
+    fn handle(&self, command: &str) -> String {
+        match command.trim() {
+            "ping" => {
+                let capture = if self.capture_connected.load(Ordering::Relaxed) {
+                    "connected"
+                } else {
+                    "reconnecting"
+                };
+                format!(
+                    "pong uptime={}s capture={}",
+                    self.started.elapsed().as_secs(),
+                    capture
+                )
+            }
+            "glide" => {
+                let glide = *self.last_glide.lock().unwrap();
+                format!(
+                    "glide launch_speed={:.0} distance={:.0}",
+                    glide.launch_speed, glide.distance
+                )
+            }
+            "info" => self.info(),
+            "status" => self.status(),
+            "enable" => self.set_enabled(true),
+            "disable" => self.set_enabled(false),
+            "reset" => {
+                self.reset_params();
+                "ok".to_owned()
+            }
+            "resume" => {
+                if self.safe_mode.swap(false, Ordering::Relaxed) {
+                    log::info!("Safe mode left, flicks launch inertial movement again");
+                    "ok".to_owned()
+                } else {
+                    "error not in safe mode".to_owned()
+                }
+            }
+            command if command.starts_with("flick ") => self.flick(&command[6..]),
+            command if command.starts_with("use-device ") => self.use_device(command[11..].trim()),
+            command => format!("error unknown command: {}", command),
+        }
+    }
+}
+
+/// Default control socket path: `$XDG_RUNTIME_DIR/inertpad.sock`
+pub fn default_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR").map(|dir| Path::new(&dir).join("inertpad.sock"))
+}
+
+/// Binds the control socket and serves it on a background thread
+pub fn spawn(path: &Path, state: Arc<ControlState>) -> io::Result<()> {
+    // Remove a stale socket left behind by a previous instance
+    if path.exists() && UnixStream::connect(path).is_err() {
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    log::info!("Listening for control commands on {}", path.display());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let state = state.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = serve(stream, &state) {
+                            log::debug!("Control connection closed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => log::warn!("Failed to accept control connection: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Time the capture thread has to switch devices for `use-device`
+const DEVICE_SWAP_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+/// Lines buffered per subscriber before new ones are dropped
+const SUBSCRIBER_BACKLOG: usize = 256;
+
+fn serve(stream: UnixStream, state: &ControlState) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        match line.trim() {
+            "" => continue,
+            // Streams until the client disconnects
+            "subscribe" => return stream_frames(writer, state),
+            _ => writeln!(writer, "{}", state.handle(&line))?,
+        }
+    }
+    Ok(())
+}
+
+fn stream_frames(mut writer: UnixStream, state: &ControlState) -> io::Result<()> {
+    let (sender, receiver) = mpsc::sync_channel(SUBSCRIBER_BACKLOG);
+    state.subscribers.lock().unwrap().push(sender);
+    for line in receiver {
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use evdev::{AbsoluteAxisType, BusType, Device, Key, PropType};
+use std::{fmt, fs, io, path::PathBuf};
+
+/// Capability flags of a touchpad device
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Reports single finger contact (`BTN_TOOL_FINGER`)
+    pub finger: bool,
+    /// Reports touch state (`BTN_TOUCH`)
+    pub touch: bool,
+    /// Reports multitouch positions (`ABS_MT_POSITION_X/Y`)
+    pub multitouch: bool,
+    /// Reports contact pressure (`ABS_PRESSURE` or `ABS_MT_PRESSURE`)
+    pub pressure: bool,
+    /// The whole touchpad surface is a clickable button (`INPUT_PROP_BUTTONPAD`)
+    pub buttonpad: bool,
+    /// Reports absolute position (`ABS_X/Y`)
+    pub position: bool,
+    /// Is flagged as a pointing device (`INPUT_PROP_POINTER`)
+    pub pointer: bool,
+}
+
+impl Capabilities {
+    /// Reads capability flags of a device
+    pub fn of(device: &Device) -> Self {
+        let keys = device.supported_keys();
+        let axes = device.supported_absolute_axes();
+        let has_key = |key| keys.is_some_and(|keys| keys.contains(key));
+        let has_axis = |axis| axes.is_some_and(|axes| axes.contains(axis));
+        Self {
+            finger: has_key(Key::BTN_TOOL_FINGER),
+            touch: has_key(Key::BTN_TOUCH),
+            multitouch: has_axis(AbsoluteAxisType::ABS_MT_POSITION_X)
+                && has_axis(AbsoluteAxisType::ABS_MT_POSITION_Y),
+            pressure: has_axis(AbsoluteAxisType::ABS_PRESSURE)
+                || has_axis(AbsoluteAxisType::ABS_MT_PRESSURE),
+            buttonpad: device.properties().contains(PropType::BUTTONPAD),
+            position: has_axis(AbsoluteAxisType::ABS_X) && has_axis(AbsoluteAxisType::ABS_Y),
+            pointer: device.properties().contains(PropType::POINTER),
+        }
+    }
+}
+
+/// Rule deciding which devices count as touchpads
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MatchStrategy {
+    /// Reports both `BTN_TOOL_FINGER` and `BTN_TOUCH`, or is a pointer
+    /// reporting positions only per multitouch slot
+    #[default]
+    FingerAndTouch,
+    /// Reports multitouch positions
+    MtPosition,
+    /// Reports absolute position and is a pointer or reports touch
+    AnyAbsPointer,
+}
+
+impl MatchStrategy {
+    pub const ALL: [MatchStrategy; 3] = [
+        MatchStrategy::FingerAndTouch,
+        MatchStrategy::MtPosition,
+        MatchStrategy::AnyAbsPointer,
+    ];
+
+    /// Checks whether a device with these capabilities satisfies the strategy
+    pub fn matches(self, caps: &Capabilities) -> bool {
+        match self {
+            MatchStrategy::FingerAndTouch => {
+                caps.finger && caps.touch || caps.pointer && caps.multitouch && !caps.position
+            }
+            MatchStrategy::MtPosition => caps.multitouch,
+            MatchStrategy::AnyAbsPointer => caps.position && (caps.pointer || caps.touch),
+        }
+    }
+}
+
+/// Choice among several devices satisfying the match strategy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Selection {
+    /// The first one enumerated
+    #[default]
+    First,
+    /// The one with the highest [`selection_score`], the first one on a tie
+    Best,
+}
+
+/// Preference for a touchpad candidate, higher is better
+///
+/// Built-in touchpads, on buses laptops wire them to, outrank external
+/// ones. Among those, multitouch with more slots, pressure and a higher
+/// resolution count.
+pub fn selection_score(device: &Device) -> u32 {
+    let caps = Capabilities::of(device);
+    let internal = matches!(
+        device.input_id().bus_type(),
+        BusType::BUS_I8042 | BusType::BUS_I2C | BusType::BUS_SPI | BusType::BUS_RMI
+    );
+    let state = device.get_abs_state().ok();
+    let axis = |axis: AbsoluteAxisType| {
+        let supported = device
+            .supported_absolute_axes()
+            .is_some_and(|axes| axes.contains(axis));
+        state
+            .filter(|_| supported)
+            .map(|state| state[axis.0 as usize])
+    };
+    let slots = axis(AbsoluteAxisType::ABS_MT_SLOT).map_or(0, |info| info.maximum + 1);
+    let resolution = axis(AbsoluteAxisType::ABS_X).map_or(0, |info| info.resolution);
+    let mut score = 0;
+    if internal {
+        score += 16;
+    }
+    if caps.multitouch {
+        score += 4 + slots.clamp(0, 5) as u32;
+    }
+    if caps.pressure {
+        score += 2;
+    }
+    // Touchpads report 10 to 40 units per millimeter
+    score + (resolution / 10).clamp(0, 4) as u32
+}
+
+/// Description of a touchpad candidate
+#[derive(Clone, Debug)]
+pub struct TouchpadInfo {
+    /// Device node path, e.g. `/dev/input/event7`
+    pub path: PathBuf,
+    /// Device name reported by the kernel
+    pub name: String,
+    /// Capability flags
+    pub capabilities: Capabilities,
+    /// X and Y resolution in units per millimeter, if reported
+    pub resolution: Option<(i32, i32)>,
+    /// Match strategies the device satisfies
+    pub strategies: Vec<MatchStrategy>,
+}
+
+impl TouchpadInfo {
+    fn new(path: PathBuf, device: &Device) -> Self {
+        let resolution = device.get_abs_state().ok().and_then(|state| {
+            let x = state[AbsoluteAxisType::ABS_X.0 as usize].resolution;
+            let y = state[AbsoluteAxisType::ABS_Y.0 as usize].resolution;
+            (x > 0 && y > 0).then_some((x, y))
+        });
+        let capabilities = Capabilities::of(device);
+        Self {
+            path,
+            name: device.name().unwrap_or_default().to_owned(),
+            capabilities,
+            resolution,
+            strategies: MatchStrategy::ALL
+                .into_iter()
+                .filter(|strategy| strategy.matches(&capabilities))
+                .collect(),
+        }
+    }
+}
+
+/// Failure to inspect a single device node
+#[derive(Debug)]
+pub struct DeviceError {
+    /// Device node path
+    pub path: PathBuf,
+    /// Underlying error
+    pub error: io::Error,
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for DeviceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Opens an input device by node path, or by kernel name among touchpad
+/// candidates
+///
+/// Anything containing a `/` is taken as a path, which may be a glob as
+/// accepted by [`resolve_glob`]. Names match case-insensitively, in full
+/// or else as a substring, preferring the first full match.
+pub fn open_device(query: &str) -> io::Result<(PathBuf, Device)> {
+    if query.contains('/') {
+        let path = resolve_glob(query)?;
+        let device = Device::open(&path)?;
+        return Ok((path, device));
+    }
+    let query_lower = query.to_lowercase();
+    let mut partial = None;
+    for (path, device) in evdev::enumerate() {
+        let touchpad = MatchStrategy::ALL
+            .iter()
+            .any(|strategy| strategy.matches(&Capabilities::of(&device)));
+        let name = device.name().unwrap_or_default().to_lowercase();
+        if !touchpad || !name.contains(&query_lower) {
+            continue;
+        }
+        if name == query_lower {
+            return Ok((path, device));
+        }
+        partial.get_or_insert((path, device));
+    }
+    partial.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no touchpad name matches {:?}", query),
+        )
+    })
+}
+
+/// Resolves a path with `*` and `?` wildcards to the single node it matches
+///
+/// Symlinks such as `/dev/input/by-path/*` entries are followed, so the
+/// result is the `/dev/input/event*` node itself. Matching nothing or more
+/// than one node is an error.
+pub fn resolve_glob(pattern: &str) -> io::Result<PathBuf> {
+    let root = if pattern.starts_with('/') { "/" } else { "." };
+    let mut paths = vec![PathBuf::from(root)];
+    for component in pattern.split('/').filter(|c| !c.is_empty()) {
+        if !component.contains(['*', '?']) {
+            paths.iter_mut().for_each(|path| path.push(component));
+            continue;
+        }
+        let mut matches = Vec::new();
+        for dir in &paths {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.filter_map(Result::ok) {
+                let name = entry.file_name();
+                if wildcard_match(component.as_bytes(), name.as_encoded_bytes()) {
+                    matches.push(entry.path());
+                }
+            }
+        }
+        paths = matches;
+    }
+    let mut nodes = paths
+        .iter()
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .collect::<Vec<_>>();
+    nodes.sort();
+    // Several symlinks may lead to the same node
+    nodes.dedup();
+    match nodes.len() {
+        0 => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no device node matches {:?}", pattern),
+        )),
+        1 => Ok(nodes.remove(0)),
+        n => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} device nodes match {:?}: {}",
+                n,
+                pattern,
+                nodes
+                    .iter()
+                    .map(|node| node.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )),
+    }
+}
+
+/// Matches a file name against a pattern where `*` stands for any run of
+/// bytes and `?` for a single one
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it was tried at
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` absorb one more byte
+                Some((star, start)) => {
+                    p = star;
+                    n = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Checks whether a device looks like a touchpad by the default strategy
+pub fn is_touchpad(device: &Device) -> bool {
+    MatchStrategy::default().matches(&Capabilities::of(device))
+}
+
+/// Lists touchpad candidates among `/dev/input/event*` nodes
+///
+/// A candidate satisfies at least one of the match strategies, listed in
+/// [`TouchpadInfo::strategies`].
+///
+/// Nodes that fail to open are reported individually instead of failing
+/// the whole enumeration.
+pub fn list_touchpads() -> io::Result<Vec<Result<TouchpadInfo, DeviceError>>> {
+    let mut paths = fs::read_dir("/dev/input")?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("event"))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut touchpads = Vec::new();
+    for path in paths {
+        match Device::open(&path) {
+            Ok(device) => {
+                let info = TouchpadInfo::new(path, &device);
+                if !info.strategies.is_empty() {
+                    touchpads.push(Ok(info));
+                }
+            }
+            Err(error) => touchpads.push(Err(DeviceError { path, error })),
+        }
+    }
+    Ok(touchpads)
+}
@@ -0,0 +1,92 @@
+//! Daemon state built from a command line without any device, for tests and
+//! the fuzz target to drive the capture and the emulation with
+
+use crate::{
+    effective_params, Args, CaptureContext, CaptureOptions, CaptureState, ControlState,
+    EmulationHandle, FocusState, ModifierState, PrecisionState, RunInfo, SessionState,
+    SpeedHistory, ADAPTIVE_WINDOW,
+};
+use clap::{CommandFactory, FromArgMatches};
+use inertpad::{GestureHooks, LiveParams};
+use std::{
+    sync::{mpsc, Arc},
+    time,
+};
+
+/// Everything the daemon sets up from its command line
+pub struct Fixture {
+    pub args: Args,
+    pub params: Arc<LiveParams>,
+    pub options: CaptureOptions,
+    pub control: ControlState,
+    pub session: SessionState,
+    pub focus: FocusState,
+    pub suppress: ModifierState,
+    pub speeds: SpeedHistory,
+    pub precision: PrecisionState,
+}
+
+impl Fixture {
+    /// Sets up the daemon for the command line flags, panicking if they
+    /// don't parse
+    pub fn new(flags: &[&str]) -> Self {
+        let matches = Args::command()
+            .try_get_matches_from(std::iter::once("inertpad").chain(flags.iter().copied()))
+            .unwrap();
+        let args = Args::from_arg_matches(&matches).unwrap();
+        let params = Arc::new(LiveParams::new(effective_params(&args, &matches)));
+        let (sender, _) = mpsc::channel();
+        let control = ControlState::new(
+            EmulationHandle { sender },
+            params.clone(),
+            RunInfo {
+                mode: args.mode_description(),
+                metrics: false,
+            },
+            GestureHooks::default(),
+        )
+        .unwrap();
+        Self {
+            options: args.capture_options(),
+            precision: PrecisionState::new(args.precision_drag_mult),
+            args,
+            params,
+            control,
+            session: SessionState::default(),
+            focus: FocusState::default(),
+            suppress: ModifierState::default(),
+            speeds: SpeedHistory::new(ADAPTIVE_WINDOW),
+        }
+    }
+
+    pub fn context(&self) -> CaptureContext<'_> {
+        CaptureContext {
+            params: &self.params,
+            options: &self.options,
+            session: &self.session,
+            focus: &self.focus,
+            suppress: &self.suppress,
+            control: &self.control,
+            benchmark: None,
+            probe: None,
+            speeds: &self.speeds,
+        }
+    }
+
+    /// Capture state machine of a touchpad reporting contact with finger
+    /// keys and positions on unknown axis ranges
+    pub fn capture(&self) -> CaptureState<'_> {
+        CaptureState::new(self.context(), (None, None), None, None)
+    }
+}
+
+/// Event stamped `at` into the run of a test or fuzz input
+pub fn event(
+    at: time::Duration,
+    type_: evdev::EventType,
+    code: u16,
+    value: i32,
+) -> evdev::InputEvent {
+    let start = time::UNIX_EPOCH + time::Duration::from_secs(1_000_000);
+    inertpad::event_at(start + at, type_, code, value)
+}
@@ -0,0 +1,124 @@
+//! Per-application inertia via X11 focus tracking
+
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "x11")]
+pub use tracker::spawn_tracker;
+
+/// Application allowlist and denylist, matched against WM_CLASS
+#[derive(Debug, Clone, Default)]
+pub struct AppFilter {
+    /// If not empty, only these applications get inertia
+    pub apps: Vec<String>,
+    /// Applications that never get inertia
+    pub exclude: Vec<String>,
+}
+
+impl AppFilter {
+    pub fn is_empty(&self) -> bool {
+        self.apps.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether a window with the WM_CLASS instance and class names gets inertia
+    #[cfg_attr(not(feature = "x11"), allow(dead_code))]
+    pub fn allows(&self, names: &[&str]) -> bool {
+        let listed = |list: &[String]| {
+            list.iter()
+                .any(|app| names.iter().any(|name| name.eq_ignore_ascii_case(app)))
+        };
+        (self.apps.is_empty() || listed(&self.apps)) && !listed(&self.exclude)
+    }
+}
+
+/// Whether the focused application is excluded from inertia
+#[derive(Debug, Default)]
+pub struct FocusState {
+    excluded: AtomicBool,
+}
+
+impl FocusState {
+    /// Returns `true` if new inertial movement should be suppressed
+    pub fn is_excluded(&self) -> bool {
+        self.excluded.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(feature = "x11")]
+mod tracker {
+    use super::{AppFilter, FocusState};
+    use std::{
+        error::Error,
+        sync::{atomic::Ordering, Arc},
+        thread,
+    };
+    use x11rb::{
+        connection::Connection,
+        protocol::{
+            xproto::{AtomEnum, ChangeWindowAttributesAux, ConnectionExt, EventMask, Window},
+            Event,
+        },
+        rust_connection::RustConnection,
+    };
+
+    /// Connects to the X server and follows `_NET_ACTIVE_WINDOW` on
+    /// a background thread, updating `state` whenever the focus changes.
+    pub fn spawn_tracker(filter: AppFilter, state: Arc<FocusState>) -> Result<(), Box<dyn Error>> {
+        let (conn, screen) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen].root;
+        let active_window = conn
+            .intern_atom(false, b"_NET_ACTIVE_WINDOW")?
+            .reply()?
+            .atom;
+        conn.change_window_attributes(
+            root,
+            &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )?;
+        conn.flush()?;
+        thread::spawn(move || {
+            let result = (|| -> Result<(), Box<dyn Error>> {
+                loop {
+                    let window = conn
+                        .get_property(false, root, active_window, AtomEnum::WINDOW, 0, 1)?
+                        .reply()?
+                        .value32()
+                        .and_then(|mut values| values.next())
+                        .filter(|&window| window != x11rb::NONE);
+                    let class = match window {
+                        Some(window) => window_class(&conn, window)?,
+                        None => String::new(),
+                    };
+                    let names: Vec<_> = class.split('\0').filter(|n| !n.is_empty()).collect();
+                    let allowed = filter.allows(&names);
+                    log::debug!(
+                        "Focused application: {:?}, inertia {}",
+                        names,
+                        if allowed { "allowed" } else { "excluded" }
+                    );
+                    state.excluded.store(!allowed, Ordering::Relaxed);
+                    // Wait until the active window changes
+                    loop {
+                        if let Event::PropertyNotify(event) = conn.wait_for_event()? {
+                            if event.atom == active_window {
+                                break;
+                            }
+                        }
+                    }
+                }
+            })();
+            if let Err(e) = result {
+                log::error!("Focus tracking stopped: {}", e);
+                state.excluded.store(false, Ordering::Relaxed);
+            }
+        });
+        Ok(())
+    }
+
+    /// WM_CLASS of a window: instance and class names, NUL-separated
+    fn window_class(conn: &RustConnection, window: Window) -> Result<String, Box<dyn Error>> {
+        let reply = conn
+            .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)?
+            .reply();
+        // The window may be gone already
+        let value = reply.map(|reply| reply.value).unwrap_or_default();
+        Ok(String::from_utf8_lossy(&value).into_owned())
+    }
+}
@@ -0,0 +1,136 @@
+//! Fuzz target throwing arbitrary event streams at the capture state
+//! machine, built by `cargo fuzz run capture` from fuzz/
+//!
+//! The first byte picks the configuration and the way the touchpad reports
+//! contact, every following 5 bytes make an event: its kind, a little-endian
+//! `i16` value and a little-endian `u16` time step in microseconds. The
+//! state machine must not panic, launch with a non-finite velocity, or
+//! launch again without stopping the glide it launched before.
+
+use crate::{fixture, AbsRange, MomentumMessage, Slots};
+use evdev::{AbsoluteAxisType, EventType, Key, Synchronization};
+use std::{sync::OnceLock, time};
+
+/// Command lines of the configurations, along the ways of reporting contact
+const FLAGS: [&[&str]; 4] = [
+    &[],
+    &["--finger-modes", "1=pointer,2=scroll,3=ignore"],
+    &["--pressure-contact-threshold", "30", "--grab-glide"],
+    &[
+        "--velocity-samples",
+        "4",
+        "--report-smoothing",
+        "0.5",
+        "--rotate-deg",
+        "30",
+        "--edge-margin",
+        "0.1",
+        "--chain-window-ms",
+        "500",
+        "--cooldown-taper-ms",
+        "200",
+        "--soft-catch-ms",
+        "100",
+    ],
+];
+
+/// Axis range of the configurations with known ranges
+const RANGE: AbsRange = AbsRange {
+    min: 0,
+    max: 4000,
+    resolution: 40,
+};
+
+fn kinds() -> [(EventType, u16); 14] {
+    [
+        (EventType::ABSOLUTE, AbsoluteAxisType::ABS_X.0),
+        (EventType::ABSOLUTE, AbsoluteAxisType::ABS_Y.0),
+        (EventType::ABSOLUTE, AbsoluteAxisType::ABS_MT_SLOT.0),
+        (EventType::ABSOLUTE, AbsoluteAxisType::ABS_MT_TRACKING_ID.0),
+        (EventType::ABSOLUTE, AbsoluteAxisType::ABS_MT_POSITION_X.0),
+        (EventType::ABSOLUTE, AbsoluteAxisType::ABS_MT_POSITION_Y.0),
+        (EventType::ABSOLUTE, AbsoluteAxisType::ABS_PRESSURE.0),
+        (EventType::KEY, Key::BTN_TOUCH.code()),
+        (EventType::KEY, Key::BTN_TOOL_FINGER.code()),
+        (EventType::KEY, Key::BTN_TOOL_DOUBLETAP.code()),
+        (EventType::KEY, Key::BTN_TOOL_TRIPLETAP.code()),
+        (EventType::KEY, Key::BTN_LEFT.code()),
+        (EventType::SYNCHRONIZATION, Synchronization::SYN_REPORT.0),
+        (EventType::SYNCHRONIZATION, Synchronization::SYN_DROPPED.0),
+    ]
+}
+
+fn run(data: &[u8]) {
+    let Some((&setup, data)) = data.split_first() else {
+        return;
+    };
+    // Parsing the command line would take most of the time of an input,
+    // and nothing the fixtures keep across inputs changes the outcome
+    static FIXTURES: OnceLock<Vec<fixture::Fixture>> = OnceLock::new();
+    let fixtures = FIXTURES.get_or_init(|| {
+        FLAGS
+            .iter()
+            .map(|flags| fixture::Fixture::new(flags))
+            .collect()
+    });
+    let fixture = &fixtures[usize::from(setup) % FLAGS.len()];
+    let ranges = match setup & 0x10 {
+        0 => (None, None),
+        _ => (Some(RANGE), Some(RANGE)),
+    };
+    let pressure = (setup % 4 == 2).then_some(AbsoluteAxisType::ABS_PRESSURE);
+    let slots = match (setup >> 5) % 3 {
+        0 => None,
+        contact => Some(Slots {
+            contact: contact == 2,
+            ..Slots::default()
+        }),
+    };
+    let mut state = crate::CaptureState::new(fixture.context(), ranges, pressure, slots);
+
+    let kinds = kinds();
+    let mut at = time::Duration::from_secs(1);
+    let mut gliding = false;
+    let mut send = |message: MomentumMessage| match message {
+        MomentumMessage::StartMovement { vx, vy, .. } => {
+            assert!(
+                vx.is_finite() && vy.is_finite(),
+                "launched at ({}, {})",
+                vx,
+                vy
+            );
+            assert!(!gliding, "launched again without stopping the glide");
+            gliding = true;
+        }
+        MomentumMessage::StopMovement
+        | MomentumMessage::FingerGrab
+        | MomentumMessage::SoftCatch => {
+            gliding = false;
+        }
+        _ => {}
+    };
+    // The high bit of the kind ends the batch after the event
+    for batch in data
+        .chunks_exact(5)
+        .collect::<Vec<_>>()
+        .split_inclusive(|e| e[0] & 0x80 != 0)
+    {
+        let events = batch.iter().map(|e| {
+            let (type_, code) = kinds[usize::from(e[0] & 0x7f) % kinds.len()];
+            let mut value = i32::from(i16::from_le_bytes([e[1], e[2]]));
+            if type_ == EventType::KEY {
+                value = value.rem_euclid(3);
+            }
+            // Steps over 0x8000 go back in time, as after SYN_DROPPED
+            let step = u16::from_le_bytes([e[3], e[4]]);
+            at = match step.checked_sub(0x8000) {
+                Some(back) => at.saturating_sub(time::Duration::from_micros(back.into())),
+                None => at + time::Duration::from_micros(step.into()),
+            };
+            fixture::event(at, type_, code, value)
+        });
+        state.handle_batch(events.collect::<Vec<_>>(), &mut send);
+    }
+}
+
+libfuzzer_sys::fuzz_target!(|data: &[u8]| run(data));
@@ -0,0 +1,109 @@
+//! Callbacks fired at the lifecycle points of the gesture pipeline
+
+use crate::GestureKind;
+use std::fmt;
+
+type FlickCallback = Box<dyn Fn(GestureKind, f64, f64) + Send + Sync>;
+type ReasonCallback = Box<dyn Fn(&str) + Send + Sync>;
+type GlideCallback = Box<dyn Fn(f64) + Send + Sync>;
+
+/// Closures notified of flicks and glides
+///
+/// Flick callbacks run on the capture thread while it handles the lift-off,
+/// glide callbacks on the emulation thread between two frames. Both threads
+/// may call them at the same time, hence the `Sync` bound. No lock of the
+/// pipeline is held during a call, so callbacks may call back into
+/// InertPad, but they delay input handling or the next frame until they
+/// return: hand anything slow over to another thread.
+#[derive(Default)]
+pub struct GestureHooks {
+    flick_armed: Option<FlickCallback>,
+    flick_suppressed: Option<ReasonCallback>,
+    glide_started: Option<GlideCallback>,
+    glide_ended: Option<GlideCallback>,
+}
+
+impl GestureHooks {
+    pub fn builder() -> GestureHooksBuilder {
+        GestureHooksBuilder::default()
+    }
+
+    /// Reports a lift-off that launches inertial movement, with its
+    /// velocity in touchpad units per second
+    pub fn flick_armed(&self, kind: GestureKind, vx: f64, vy: f64) {
+        if let Some(callback) = &self.flick_armed {
+            callback(kind, vx, vy);
+        }
+    }
+
+    /// Reports a lift-off that doesn't launch inertial movement
+    ///
+    /// The reason is only formatted if a callback is attached.
+    pub fn flick_suppressed(&self, reason: &dyn fmt::Display) {
+        if let Some(callback) = &self.flick_suppressed {
+            callback(&reason.to_string());
+        }
+    }
+
+    /// Reports the start of a glide, with its launch speed in touchpad
+    /// units per second
+    pub fn glide_started(&self, launch_speed: f64) {
+        if let Some(callback) = &self.glide_started {
+            callback(launch_speed);
+        }
+    }
+
+    /// Reports a glide decelerated to a stop, with the distance it emitted
+    /// in pixels or high-resolution wheel units
+    pub fn glide_ended(&self, distance: f64) {
+        if let Some(callback) = &self.glide_ended {
+            callback(distance);
+        }
+    }
+}
+
+impl fmt::Debug for GestureHooks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GestureHooks")
+            .field("flick_armed", &self.flick_armed.is_some())
+            .field("flick_suppressed", &self.flick_suppressed.is_some())
+            .field("glide_started", &self.glide_started.is_some())
+            .field("glide_ended", &self.glide_ended.is_some())
+            .finish()
+    }
+}
+
+/// Attaches callbacks to [`GestureHooks`], unset ones do nothing
+#[derive(Default)]
+pub struct GestureHooksBuilder {
+    hooks: GestureHooks,
+}
+
+impl GestureHooksBuilder {
+    pub fn on_flick_armed(
+        mut self,
+        callback: impl Fn(GestureKind, f64, f64) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.flick_armed = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_flick_suppressed(mut self, callback: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.hooks.flick_suppressed = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_glide_started(mut self, callback: impl Fn(f64) + Send + Sync + 'static) -> Self {
+        self.hooks.glide_started = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_glide_ended(mut self, callback: impl Fn(f64) + Send + Sync + 'static) -> Self {
+        self.hooks.glide_ended = Some(Box::new(callback));
+        self
+    }
+
+    pub fn build(self) -> GestureHooks {
+        self.hooks
+    }
+}
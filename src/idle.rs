@@ -0,0 +1,135 @@
+//! Session activity tracking via systemd-logind over D-Bus
+
+#[cfg(feature = "dbus")]
+pub use monitor::spawn_monitor;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether the session is currently idle, locked or going to sleep
+#[derive(Debug, Default)]
+pub struct SessionState {
+    idle: AtomicBool,
+    locked: AtomicBool,
+    sleeping: AtomicBool,
+}
+
+impl SessionState {
+    /// Returns `true` if inertial movement should be suppressed
+    pub fn is_inactive(&self) -> bool {
+        self.idle.load(Ordering::Relaxed)
+            || self.locked.load(Ordering::Relaxed)
+            || self.sleeping.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(feature = "dbus")]
+mod monitor {
+    use super::SessionState;
+    use crate::MomentumMessage;
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+    };
+    use zbus::blocking::{Connection, Proxy};
+
+    const LOGIND: &str = "org.freedesktop.login1";
+
+    /// Starts background threads tracking session state.
+    /// Any active glide is stopped as soon as the session becomes inactive.
+    pub fn spawn_monitor(
+        state: Arc<SessionState>,
+        sender: mpsc::Sender<MomentumMessage>,
+    ) -> zbus::Result<()> {
+        let conn = Connection::system()?;
+        let manager = Proxy::new_owned(
+            conn.clone(),
+            LOGIND,
+            "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager",
+        )?;
+        let sleep_signals = manager.receive_signal("PrepareForSleep")?;
+        watch_property(
+            manager,
+            "IdleHint",
+            state.clone(),
+            |state| &state.idle,
+            sender.clone(),
+        );
+        let session_state = state.clone();
+        let session_sender = sender.clone();
+        thread::spawn(move || {
+            for signal in sleep_signals {
+                match signal.body().deserialize::<bool>() {
+                    Ok(sleeping) => {
+                        log::info!(
+                            "Session {}",
+                            if sleeping {
+                                "is going to sleep"
+                            } else {
+                                "woke up"
+                            }
+                        );
+                        update(
+                            &session_state,
+                            |state| &state.sleeping,
+                            sleeping,
+                            &session_sender,
+                        );
+                    }
+                    Err(e) => log::warn!("Malformed PrepareForSleep signal: {}", e),
+                }
+            }
+        });
+
+        // The session of the calling process, if InertPad runs within one
+        match Proxy::new_owned(
+            conn,
+            LOGIND,
+            "/org/freedesktop/login1/session/auto",
+            "org.freedesktop.login1.Session",
+        ) {
+            Ok(session) => {
+                watch_property(session, "LockedHint", state, |state| &state.locked, sender)
+            }
+            Err(e) => log::warn!("Session lock state is unavailable: {}", e),
+        }
+        Ok(())
+    }
+
+    fn watch_property(
+        proxy: Proxy<'static>,
+        name: &'static str,
+        state: Arc<SessionState>,
+        flag: fn(&SessionState) -> &AtomicBool,
+        sender: mpsc::Sender<MomentumMessage>,
+    ) {
+        thread::spawn(move || {
+            if let Ok(value) = proxy.get_property::<bool>(name) {
+                update(&state, flag, value, &sender);
+            }
+            for change in proxy.receive_property_changed::<bool>(name) {
+                match change.get() {
+                    Ok(value) => {
+                        log::info!("Session {} = {}", name, value);
+                        update(&state, flag, value, &sender);
+                    }
+                    Err(e) => log::warn!("Failed to read session {}: {}", name, e),
+                }
+            }
+        });
+    }
+
+    fn update(
+        state: &SessionState,
+        flag: fn(&SessionState) -> &AtomicBool,
+        value: bool,
+        sender: &mpsc::Sender<MomentumMessage>,
+    ) {
+        flag(state).store(value, Ordering::Relaxed);
+        if value {
+            let _ = sender.send(MomentumMessage::StopMovement);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Frame-by-frame deceleration of a glide, free of devices and threads
+
+/// Velocity of a glide and the movement left over from its frames
+///
+/// The core of the daemon's emulation loop: each frame emits the velocity
+/// scaled by the speed factor, then the drag takes its share of the
+/// velocity. Fractions of output units are carried over to later frames,
+/// as with `--rounding accumulate`.
+///
+/// A frame is made of the methods in the order the daemon calls them:
+/// [`InertiaState::frame_delta`], a stop once the delta is negligible,
+/// [`InertiaState::decelerate`] or [`InertiaState::brake`], then
+/// [`InertiaState::round`], with the caller's own adjustments in between.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InertiaState {
+    /// Horizontal and vertical velocity, which the speed factor turns into
+    /// the movement of a frame
+    pub velocity: (f64, f64),
+    /// Movement not emitted yet, in output units
+    remainder: (f64, f64),
+}
+
+impl InertiaState {
+    /// Frame movement below which the glide stops, in output units
+    pub const STOP_DELTA: f64 = 0.1;
+
+    /// Launches a glide with the given velocity
+    pub fn new(vx: f64, vy: f64) -> Self {
+        Self {
+            velocity: (vx, vy),
+            remainder: (0.0, 0.0),
+        }
+    }
+
+    /// Whether the glide has come to a stop
+    pub fn is_stopped(&self) -> bool {
+        self.velocity == (0.0, 0.0)
+    }
+
+    /// Speed of the glide, the magnitude of its velocity
+    pub fn speed(&self) -> f64 {
+        self.velocity.0.hypot(self.velocity.1)
+    }
+
+    /// Movement left out of the emitted whole units so far
+    pub fn remainder(&self) -> (f64, f64) {
+        self.remainder
+    }
+
+    /// Stops the glide, dropping the movement left over
+    pub fn stop(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Movement of a frame at the current velocity, in output units
+    pub fn frame_delta(&self, (factor_x, factor_y): (f64, f64)) -> (f64, f64) {
+        (self.velocity.0 * factor_x, self.velocity.1 * factor_y)
+    }
+
+    /// Keeps the given share of the velocity on each axis
+    pub fn decelerate(&mut self, (keep_x, keep_y): (f64, f64)) {
+        self.velocity = (self.velocity.0 * keep_x, self.velocity.1 * keep_y);
+    }
+
+    /// Takes `loss` off the speed, keeping the direction, and stops rather
+    /// than reversing once the loss exceeds the speed
+    pub fn brake(&mut self, loss: f64) {
+        let speed = self.speed();
+        let scale = if speed > loss {
+            1.0 - loss / speed
+        } else {
+            0.0
+        };
+        self.decelerate((scale, scale));
+    }
+
+    /// Rounds the movement of a frame to whole units with `round`, adding
+    /// what it leaves out to the remainder
+    ///
+    /// With `carry` the remainder is added to the movement first, so that
+    /// it's emitted once it adds up to whole units.
+    pub fn round(
+        &mut self,
+        (dx, dy): (f64, f64),
+        carry: bool,
+        mut round: impl FnMut(f64) -> i32,
+    ) -> (i32, i32) {
+        let carried = if carry { self.remainder } else { (0.0, 0.0) };
+        let x = round(carried.0 + dx);
+        let y = round(carried.1 + dy);
+        self.remainder = (
+            self.remainder.0 + dx - f64::from(x),
+            self.remainder.1 + dy - f64::from(y),
+        );
+        (x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame as the daemon runs it with `--rounding accumulate` and
+    /// exponential decay
+    fn frame(inertia: &mut InertiaState, drag: f64, speed_factor: f64) -> (i32, i32) {
+        let (dx, dy) = inertia.frame_delta((speed_factor, speed_factor));
+        if dx.abs() < InertiaState::STOP_DELTA && dy.abs() < InertiaState::STOP_DELTA {
+            inertia.stop();
+            return (0, 0);
+        }
+        inertia.decelerate((1.0 - drag, 1.0 - drag));
+        inertia.round((dx, dy), true, |value| value as i32)
+    }
+
+    #[test]
+    fn speed_decays_monotonically() {
+        let mut inertia = InertiaState::new(3000.0, -1500.0);
+        let mut speed = inertia.speed();
+        while !inertia.is_stopped() {
+            frame(&mut inertia, 0.15, 0.0075);
+            assert!(inertia.speed() < speed);
+            speed = inertia.speed();
+        }
+    }
+
+    #[test]
+    fn total_distance_is_finite() {
+        let mut inertia = InertiaState::new(10000.0, 0.0);
+        let mut distance = 0;
+        let mut frames = 0;
+        while !inertia.is_stopped() {
+            distance += frame(&mut inertia, 0.15, 0.0075).0;
+            frames += 1;
+            assert!(frames < 1000, "glide didn't stop");
+        }
+        // The geometric series of the frame deltas, less the stopped tail
+        let analytic = 10000.0 * 0.0075 / 0.15;
+        assert!(f64::from(distance) <= analytic);
+        assert!(f64::from(distance) > analytic - 5.0);
+    }
+
+    #[test]
+    fn no_output_below_stop_threshold() {
+        let speed = InertiaState::STOP_DELTA / 0.0075 * 0.9;
+        let mut inertia = InertiaState::new(speed, -speed);
+        assert_eq!(frame(&mut inertia, 0.15, 0.0075), (0, 0));
+        assert!(inertia.is_stopped());
+    }
+
+    #[test]
+    fn remainder_adds_up_to_whole_units() {
+        let mut inertia = InertiaState::new(0.4, 0.0);
+        let moved: i32 = (0..5).map(|_| frame(&mut inertia, 0.0, 1.0).0).sum();
+        assert_eq!(moved, 2);
+    }
+
+    #[test]
+    fn brake_stops_instead_of_reversing() {
+        let mut inertia = InertiaState::new(30.0, 40.0);
+        inertia.brake(20.0);
+        assert!((inertia.speed() - 30.0).abs() < 1e-9);
+        inertia.brake(100.0);
+        assert!(inertia.is_stopped());
+    }
+}
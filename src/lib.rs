@@ -0,0 +1,32 @@
+//! InertPad library
+//!
+//! Building blocks of the InertPad daemon that are useful to embedders,
+//! e.g. touchpad discovery for a device picker, non-blocking event
+//! sources for a custom event loop, callbacks on gesture events, the
+//! deceleration of glides on its own, or the virtual mouse emitting them.
+
+mod discovery;
+mod hooks;
+mod inertia;
+mod message;
+mod output;
+mod params;
+mod source;
+mod touchpad;
+mod wayland;
+
+pub use discovery::{
+    is_touchpad, list_touchpads, open_device, resolve_glob, selection_score, Capabilities,
+    DeviceError, MatchStrategy, Selection, TouchpadInfo,
+};
+pub use hooks::{GestureHooks, GestureHooksBuilder};
+pub use inertia::InertiaState;
+pub use message::MomentumMessage;
+pub use output::{
+    event_at, Axes, Component, CustomAxis, DeviceProp, InertiaSink, OutputDevice, Routing,
+    VirtualMouse, VirtualMouseKind,
+};
+pub use params::{DragCurve, GestureKind, LiveParams, Params};
+pub use source::{DevicePoller, TouchpadSource};
+pub use touchpad::Touchpad;
+pub use wayland::VirtualPointer;
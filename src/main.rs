@@ -1,7 +1,52 @@
+// The fuzz target of fuzz/ is built from this crate, see src/fuzz.rs
+#![cfg_attr(fuzzing, no_main, allow(dead_code, unused_imports))]
+
+mod adaptive;
+mod benchmark;
+mod config;
+mod control;
+#[cfg(any(test, fuzzing))]
+mod fixture;
+mod focus;
+#[cfg(fuzzing)]
+mod fuzz;
+mod idle;
+mod metrics;
+mod precision;
+mod pulse;
+mod shutdown;
+#[cfg(feature = "tui")]
+mod tui;
+mod wheel;
+
+use adaptive::SpeedHistory;
 use anyhow::Result;
-use clap::Parser;
-use evdev::{self, uinput};
-use std::{io, sync::mpsc, thread, time};
+use benchmark::{CaptureBenchmark, RateProbe};
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use control::{ControlState, DeviceInfo, DeviceRequest, RunInfo};
+use focus::{AppFilter, FocusState};
+use idle::SessionState;
+use inertpad::{
+    Axes, Capabilities, Component, CustomAxis, DeviceProp, DragCurve, GestureHooks, GestureKind,
+    InertiaSink, InertiaState, LiveParams, MatchStrategy, MomentumMessage, OutputDevice, Params,
+    Routing, Selection, Touchpad, VirtualMouse, VirtualMouseKind,
+};
+use precision::{Modifier, ModifierState, PrecisionState};
+use std::{
+    collections::VecDeque,
+    ffi::CString,
+    fs,
+    io::{self, Read},
+    mem,
+    os::unix::{
+        ffi::OsStrExt,
+        io::{AsRawFd, FromRawFd, RawFd},
+    },
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{atomic::Ordering, mpsc, Arc, Mutex},
+    thread, time,
+};
 
 /// Adds inertia to your touchpad
 ///
@@ -12,235 +57,6021 @@ use std::{io, sync::mpsc, thread, time};
 #[command(name = "InertPad")]
 struct Args {
     /// Inertia drag coefficient (must be between 0.0 and 1.0)
-    /// Affects inertial movement deceleration.
-    #[arg(long, default_value_t = 0.15)]
-    drag: f64,
+    /// Affects inertial movement deceleration. Takes X,Y for a separate
+    /// horizontal and vertical drag.
+    #[arg(long, default_value = "0.15", env = "INERTPAD_DRAG")]
+    drag: AxisValues,
+
+    /// Drag changing over the course of a glide, as START:END. It moves from
+    /// START at launch towards END as the glide slows down, e.g. 0.3:0.05
+    /// for a snappy start and a long coast. Overrides --drag and --scroll-drag.
+    #[arg(long, env = "INERTPAD_DRAG_CURVE")]
+    drag_curve: Option<DragCurve>,
+
+    /// How glides decelerate: exponential slows them by the drag every
+    /// frame, linear and ease-out by --deceleration, so that they come to a
+    /// definite stop.
+    #[arg(long, value_enum, default_value_t = DecayModel::Exponential, env = "INERTPAD_DECAY_MODEL")]
+    decay_model: DecayModel,
+
+    /// Deceleration of the linear and ease-out decay models, in raw
+    /// touchpad units per second squared.
+    #[arg(
+        long,
+        value_name = "UNITS",
+        value_parser = parse_positive,
+        required_if_eq_any = [("decay_model", "linear"), ("decay_model", "ease-out")],
+        env = "INERTPAD_DECELERATION"
+    )]
+    deceleration: Option<f64>,
 
     /// Scales velocity from raw touchpad units to virtual mouse units.
-    /// Affects initial inertial movement speed.
-    #[arg(long, default_value_t = 0.0075)]
-    speed_factor: f64,
+    /// Affects initial inertial movement speed. Takes X,Y for a separate
+    /// horizontal and vertical speed factor.
+    #[arg(long, default_value = "0.0075", env = "INERTPAD_SPEED_FACTOR")]
+    speed_factor: AxisValues,
+
+    /// Derives the speed factor at startup so that a flick at
+    /// --max-launch-speed glides about the specified number of pixels (or
+    /// high-resolution wheel units). Assumes the constant --drag, settle
+    /// damping only shortens the glide. Ignored if --speed-factor is set.
+    #[arg(long, value_name = "PX", value_parser = parse_positive, env = "INERTPAD_TARGET_MAX_DISTANCE")]
+    target_max_distance: Option<f64>,
+
+    /// Lift-off speed of a hard flick in touchpad units per second, used
+    /// by --target-max-distance.
+    #[arg(long, default_value_t = 10000.0, value_parser = parse_positive, env = "INERTPAD_MAX_LAUNCH_SPEED")]
+    max_launch_speed: f64,
 
     /// Minimum touchpad pointer speed required to trigger inertial movement.
     /// Increase if a short tap causes unwanted pointer movement.
     /// Decrease if intentional swipes don't trigger inertial movement.
-    #[arg(long, default_value_t = 2000.0)]
+    #[arg(long, default_value_t = 2000.0, env = "INERTPAD_SPEED_THRESHOLD")]
     speed_threshold: f64,
 
+    /// Exponent mapping lift-off speed to initial inertia speed.
+    /// Values above 1.0 make hard flicks disproportionately fast relative
+    /// to gentle ones, 1.0 keeps the mapping linear.
+    #[arg(long, default_value_t = 1.0, env = "INERTPAD_RESPONSE_CURVE")]
+    response_curve: f64,
+
     /// Pointer position refresh rate during inertial movement.
-    #[arg(long, default_value_t = 60.0)]
+    /// Rates above 500 Hz are clamped.
+    #[arg(long, default_value_t = 60.0, env = "INERTPAD_REFRESH_RATE")]
     refresh_rate: f64,
 
+    /// Emits inertial movement at the touchpad's report rate measured during
+    /// the swipe instead of --refresh-rate, which is used as a fallback.
+    #[arg(long, env = "INERTPAD_MATCH_INPUT_RATE")]
+    match_input_rate: bool,
+
+    /// Schedules inertial movement frames against fixed deadlines, so that
+    /// the time spent emitting a frame doesn't stretch the next one and
+    /// motion stays evenly spaced on a busy system.
+    #[arg(long, env = "INERTPAD_PACE_OUTPUT")]
+    pace_output: bool,
+
+    /// Stamps emitted glide events with their evenly spaced frame times
+    /// instead of the time they are sent, for compositors predicting motion
+    /// from timestamps. uinput devices get kernel timestamps regardless, so
+    /// this only takes effect with --sink wayland.
+    #[arg(long, env = "INERTPAD_STAMP_EVENTS")]
+    stamp_events: bool,
+
+    /// Emits a report on every frame of an active glide, flushing motion
+    /// held back by --min-emit-px, so that compositors which throttle
+    /// sparse input keep their frame pacing engaged.
+    #[arg(long, env = "INERTPAD_GLIDE_KEEPALIVE")]
+    glide_keepalive: bool,
+
+    /// Experimental: after a flick, emits a brief, small motion opposite to
+    /// it instead of inertia, to counteract overshoot from tremor.
+    #[arg(long, env = "INERTPAD_RESISTANCE")]
+    resistance: bool,
+
+    /// Turns the launch direction to the nearest of the eight horizontal,
+    /// vertical and diagonal directions, keeping its speed, for UIs
+    /// navigated by flicks.
+    #[arg(long, env = "INERTPAD_SNAP_OCTANTS")]
+    snap_octants: bool,
+
+    /// Weight of the running glide's velocity added to a flick launched
+    /// while it still moves (with --grab-glide, --soft-catch-ms, or --stop-on
+    /// without finger and touch), so that the trajectory curves instead of
+    /// restarting. 0 replaces the glide, 1 adds its full velocity.
+    #[arg(long, default_value_t = 0.0, value_parser = parse_fraction, env = "INERTPAD_BLEND_FLICKS")]
+    blend_flicks: f64,
+
+    /// Blends scroll flicks against the direction of the running scroll
+    /// glide as well. By default such a flick stops the glide first, so that
+    /// scrolling reverses cleanly as in native scroll views.
+    #[arg(long, env = "INERTPAD_BLEND_SCROLL_REVERSALS")]
+    blend_scroll_reversals: bool,
+
+    /// Scroll flicks following each other in quick succession launch
+    /// faster and faster, up to this multiple of their velocity. The boost
+    /// wears off once flicking pauses.
+    #[arg(long, value_parser = parse_multiplier, env = "INERTPAD_SCROLL_ACCEL")]
+    scroll_accel: Option<f64>,
+
+    /// Scroll glides travelling further than this, in high-resolution wheel
+    /// units (120 per wheel click), come to an increasingly damped stop, like
+    /// native kinetic scrolling running into the end of a list. Off by default.
+    #[arg(long, value_parser = parse_positive, env = "INERTPAD_SCROLL_OVERSCROLL_DISTANCE")]
+    scroll_overscroll_distance: Option<f64>,
+
+    /// Extra drag of a scroll glide once it has overshot
+    /// --scroll-overscroll-distance by that distance again. The drag ramps up
+    /// linearly from 0 at the distance.
+    #[arg(
+        long,
+        default_value_t = 0.2,
+        value_parser = parse_weight,
+        requires = "scroll_overscroll_distance",
+        env = "INERTPAD_SCROLL_OVERSCROLL_DAMPING"
+    )]
+    scroll_overscroll_damping: f64,
+
+    /// Ends a glide as soon as its speed drops below the specified value, in
+    /// the units of --speed-threshold, so that the pointer parks slightly
+    /// early instead of creeping the last pixels. By default a glide ends
+    /// once a frame rounds to no motion.
+    #[arg(long, value_parser = parse_positive, env = "INERTPAD_PARK_SPEED")]
+    park_speed: Option<f64>,
+
+    /// Delays the start of inertial movement after lift-off by the specified
+    /// number of milliseconds, cancelling it if a finger touches down again
+    /// in the meantime.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_LAUNCH_DELAY_MS")]
+    launch_delay_ms: u64,
+
+    /// Latency between lift-off and the first glide frame, in milliseconds,
+    /// that the first frame makes up for by moving as far as the glide
+    /// would have in the meantime. The lead is capped at a few frames.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_PREDICT_LATENCY_MS")]
+    predict_latency_ms: u64,
+
+    /// Largest movement emitted in a single frame (pixels, or high-resolution
+    /// wheel units when scrolling). The excess is deferred to later frames,
+    /// so fast glides take longer but travel the same distance.
+    #[arg(long, value_name = "PX", value_parser = parse_positive, env = "INERTPAD_MAX_FRAME_DELTA")]
+    max_frame_delta: Option<f64>,
+
+    /// Safety net against runaway motion: stops inertial movement if it
+    /// emits farther than this within a second without a new flick (pixels,
+    /// or high-resolution wheel units when scrolling).
+    #[arg(long, value_name = "PX", value_parser = parse_positive, env = "INERTPAD_SAFE_MODE_THRESHOLD")]
+    safe_mode_threshold: Option<f64>,
+
+    /// Ignores all flicks after --safe-mode-threshold stops a glide, until
+    /// the `resume` control command.
+    #[arg(
+        long,
+        requires = "safe_mode_threshold",
+        env = "INERTPAD_SAFE_MODE_PAUSE"
+    )]
+    safe_mode_pause: bool,
+
+    /// Also ignore flicks until the `resume` control command once a glide
+    /// produced a non-finite velocity or movement. Such values are always
+    /// logged and dropped, stopping the glide.
+    #[arg(long, env = "INERTPAD_STRICT")]
+    strict: bool,
+
+    /// Pointer acceleration profile of the compositor, pre-compensated so
+    /// that glides travel the same distance regardless of it. Best-effort,
+    /// since the compositor's exact curve can't be read.
+    #[arg(long, value_enum, default_value_t = AccelProfile::None, env = "INERTPAD_ACCEL_PROFILE")]
+    accel_profile: AccelProfile,
+
+    /// Pointer speed setting of the compositor for --accel-profile,
+    /// between -1.0 and 1.0 as in libinput.
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        allow_negative_numbers = true,
+        env = "INERTPAD_ACCEL_SPEED"
+    )]
+    accel_speed: f64,
+
+    /// Measured acceleration curve of the compositor to pre-compensate
+    /// instead of --accel-profile: a CSV file of `speed,multiplier` lines,
+    /// with speeds in pixels per millisecond, interpolated linearly.
+    #[arg(long, value_name = "PATH", value_parser = AccelCurve::load, env = "INERTPAD_ACCEL_CURVE_FILE")]
+    accel_curve_file: Option<AccelCurve>,
+
+    /// Rounding of per-frame movement to whole pixels (or wheel units).
+    #[arg(long, value_enum, default_value_t = Rounding::Accumulate, env = "INERTPAD_ROUNDING")]
+    rounding: Rounding,
+
+    /// Emits what the rounding left out over a glide, along with the
+    /// remaining tail of the velocity curve, as a final delta once the glide
+    /// ends on its own, so that it travels the distance of the curve.
+    #[arg(long, env = "INERTPAD_FLUSH_REMAINDER")]
+    flush_remainder: bool,
+
+    /// Raises the launch speed of every armed flick to at least this value
+    /// (in the same units as --speed-threshold), preserving direction, so
+    /// flicks just above the threshold produce a clearly visible glide.
+    #[arg(long, default_value_t = 0.0, env = "INERTPAD_MIN_LAUNCH_SPEED")]
+    min_launch_speed: f64,
+
+    /// Extra damping in the tail of a glide (between 0.0 and 1.0), once the
+    /// speed drops below 10% of the launch speed. Eases the pointer into
+    /// its stop instead of carrying the last large step.
+    #[arg(long, default_value_t = 0.0, env = "INERTPAD_SETTLE_DAMP")]
+    settle_damp: f64,
+
+    /// Keyboard modifier that enables precision mode while held: drag is
+    /// multiplied by --precision-drag-mult for shorter glides.
+    #[arg(long, value_enum, env = "INERTPAD_PRECISION_MODIFIER")]
+    precision_modifier: Option<Modifier>,
+
+    /// Drag multiplier applied while --precision-modifier is held.
+    #[arg(long, default_value_t = 3.0, env = "INERTPAD_PRECISION_DRAG_MULT")]
+    precision_drag_mult: f64,
+
+    /// Keyboard modifier that blocks inertia while held, e.g. for
+    /// modifier-based drags.
+    #[arg(long, value_enum, env = "INERTPAD_SUPPRESS_MODIFIER")]
+    suppress_modifier: Option<Modifier>,
+
+    /// Width of a band centered on the speed threshold where the launch
+    /// velocity fades in smoothly, so swipes just below the threshold
+    /// produce a tiny glide. 0 keeps a hard threshold.
+    #[arg(long, default_value_t = 0.0, env = "INERTPAD_SOFT_THRESHOLD")]
+    soft_threshold: f64,
+
+    /// Lower speed threshold used for a flick that closely follows one
+    /// that triggered inertial movement, giving the arming decision hysteresis.
+    /// Default: same as --speed-threshold.
+    #[arg(long, env = "INERTPAD_SPEED_THRESHOLD_LOW")]
+    speed_threshold_low: Option<f64>,
+
+    /// Adapts the speed threshold to the user's flick strength: it follows
+    /// this percentile (0 to 100) of recent lift-off speeds, within half
+    /// to twice --speed-threshold. Default: fixed threshold.
+    #[arg(long, value_name = "PERCENTILE", value_parser = parse_percentile, env = "INERTPAD_ADAPTIVE_THRESHOLD")]
+    adaptive_threshold: Option<f64>,
+
+    /// Discards the flick strength --adaptive-threshold learned in earlier
+    /// runs and starts learning afresh.
+    #[arg(long, requires = "adaptive_threshold", env = "INERTPAD_FORGET")]
+    forget: bool,
+
+    /// Inertia drag coefficient for scrolling. Default: same as --drag.
+    #[arg(long, env = "INERTPAD_SCROLL_DRAG")]
+    scroll_drag: Option<f64>,
+
+    /// Speed factor for scrolling. Default: same as --speed-factor.
+    #[arg(long, env = "INERTPAD_SCROLL_SPEED_FACTOR")]
+    scroll_speed_factor: Option<f64>,
+
+    /// Speed factor for horizontal inertia. --scroll-speed-factor still
+    /// takes precedence when scrolling. Default: same as --speed-factor.
+    #[arg(long, env = "INERTPAD_SPEED_FACTOR_X")]
+    speed_factor_x: Option<f64>,
+
+    /// Speed factor for vertical inertia. --scroll-speed-factor still
+    /// takes precedence when scrolling. Default: same as --speed-factor.
+    #[arg(long, env = "INERTPAD_SPEED_FACTOR_Y")]
+    speed_factor_y: Option<f64>,
+
+    /// Speed threshold for scrolling. Default: same as --speed-threshold.
+    #[arg(long, env = "INERTPAD_SCROLL_SPEED_THRESHOLD")]
+    scroll_speed_threshold: Option<f64>,
+
     /// Prevents inertial movement from multitouch by ignoring swipes
     /// for a specified number of milliseconds after multitouch release.
-    #[arg(long, default_value_t = 500)]
+    #[arg(long, default_value_t = 500, env = "INERTPAD_MULTITOUCH_COOLDOWN")]
     multitouch_cooldown: u64,
+
+    /// Final part of --multitouch-cooldown in milliseconds where flicks are
+    /// launched with reduced velocity, ramping up to full as the cooldown
+    /// ends, instead of being suppressed. 0 keeps a hard cutoff.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_COOLDOWN_TAPER_MS")]
+    cooldown_taper_ms: u64,
+
+    /// Ignores swipes for a specified number of milliseconds after
+    /// a physical button click is released.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_CLICK_COOLDOWN")]
+    click_cooldown: u64,
+
+    /// Launches flicks lifted off within this many milliseconds after a
+    /// button press with a tenth of their velocity, so that a flick ending
+    /// in a click barely travels past the click target.
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = 0,
+        env = "INERTPAD_CLICK_SETTLE"
+    )]
+    click_settle: u64,
+
+    /// Ignores swipes for a specified number of milliseconds after
+    /// a glide comes to a stop on its own.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_POST_GLIDE_COOLDOWN")]
+    post_glide_cooldown: u64,
+
+    /// Applies --post-glide-cooldown to every flick. By default a hard flick
+    /// repeating the previous one's direction within a second bypasses it.
+    #[arg(long, env = "INERTPAD_STRICT_POST_GLIDE_COOLDOWN")]
+    strict_post_glide_cooldown: bool,
+
+    /// Chains flicks in the same direction as the previous one within the
+    /// specified number of milliseconds after it: the chained flick adds
+    /// the momentum of the glide it interrupted and bypasses the post-glide
+    /// cooldown. Default: flicks replace the glide.
+    #[arg(long, value_name = "MS", env = "INERTPAD_CHAIN_WINDOW_MS")]
+    chain_window_ms: Option<u64>,
+
+    /// Cap on the launch speed of a chained flick with the momentum it
+    /// added, after the response curve, so that repeated flicks can't
+    /// compound without bound. Default: uncapped.
+    #[arg(
+        long,
+        value_name = "SPEED",
+        value_parser = parse_positive,
+        requires = "chain_window_ms",
+        env = "INERTPAD_CHAIN_MAX_SPEED"
+    )]
+    chain_max_speed: Option<f64>,
+
+    /// Multitouch cooldown for scroll flicks.
+    /// Default: same as --multitouch-cooldown.
+    #[arg(long, env = "INERTPAD_SCROLL_MULTITOUCH_COOLDOWN")]
+    scroll_multitouch_cooldown: Option<u64>,
+
+    /// Click cooldown for scroll flicks. Default: same as --click-cooldown.
+    #[arg(long, env = "INERTPAD_SCROLL_CLICK_COOLDOWN")]
+    scroll_click_cooldown: Option<u64>,
+
+    /// Post-glide cooldown for scroll flicks, e.g. shorter for rapid repeated
+    /// scroll flings. Default: same as --post-glide-cooldown.
+    #[arg(long, env = "INERTPAD_SCROLL_POST_GLIDE_COOLDOWN")]
+    scroll_post_glide_cooldown: Option<u64>,
+
+    /// Restricts inertial movement to the specified axes.
+    /// The virtual device only registers the relative axes it needs.
+    #[arg(long, value_enum, default_value_t = Axes::Both, env = "INERTPAD_AXES")]
+    axes: Axes,
+
+    /// Rotates the lift-off velocity by this many degrees clockwise, for
+    /// touchpads mounted rotated relative to the screen. --axes and finger
+    /// modes apply to the rotated velocity.
+    #[arg(
+        long,
+        value_name = "ANGLE",
+        default_value_t = 0.0,
+        allow_negative_numbers = true,
+        env = "INERTPAD_ROTATE_DEG"
+    )]
+    rotate_deg: f64,
+
+    /// Device class advertised by the virtual device.
+    #[arg(long, value_enum, default_value_t = VirtualMouseKind::Mouse, env = "INERTPAD_VMOUSE_KIND")]
+    vmouse_kind: VirtualMouseKind,
+
+    /// Comma-separated input properties the virtual device advertises on
+    /// top of those of --vmouse-kind, to influence how the compositor
+    /// classifies it, e.g. `pointing-stick`. Also applied to the devices of
+    /// --mirror-sinks and --clone-source.
+    #[arg(long, value_enum, value_delimiter = ',', env = "INERTPAD_VMOUSE_PROPS")]
+    vmouse_props: Vec<DeviceProp>,
+
+    /// Output path of inertial movement. `wayland` uses the wlroots virtual
+    /// pointer protocol, which needs no uinput access, and falls back to
+    /// uinput with a warning if the compositor doesn't support it.
+    #[arg(long, value_enum, default_value_t = SinkKind::Uinput, env = "INERTPAD_SINK")]
+    sink: SinkKind,
+
+    /// Emits through an already created uinput device inherited as this
+    /// file descriptor, e.g. from a privileged helper, instead of creating
+    /// one. The device must have the relative axes inertial movement needs.
+    #[arg(long, conflicts_with_all = ["clone_source", "sink"], env = "INERTPAD_UINPUT_FD")]
+    uinput_fd: Option<RawFd>,
+
+    /// Comma-separated device classes of extra virtual devices receiving the
+    /// same inertial movement, e.g. to compare how the compositor treats
+    /// each. Meant for development.
+    #[arg(long, value_enum, value_delimiter = ',', env = "INERTPAD_MIRROR_SINKS")]
+    mirror_sinks: Vec<VirtualMouseKind>,
+
+    /// Number of attempts to create the virtual mouse device before giving up.
+    /// Useful when started early in the boot sequence, before uinput is ready.
+    #[arg(long, default_value_t = 5, env = "INERTPAD_VMOUSE_RETRIES")]
+    vmouse_retries: u32,
+
+    /// Delay in milliseconds before the first virtual mouse creation retry.
+    /// The delay doubles after each failed attempt.
+    #[arg(long, default_value_t = 250, env = "INERTPAD_VMOUSE_RETRY_DELAY")]
+    vmouse_retry_delay: u64,
+
+    /// Experimental: makes the virtual device a clone of the touchpad
+    /// (name, IDs, keys, axes and properties), so per-device compositor
+    /// settings apply to inertial movement as well. See README for risks.
+    #[arg(long, env = "INERTPAD_CLONE_SOURCE")]
+    clone_source: bool,
+
+    /// Creates the virtual mouse, reopens it through evdev and checks that
+    /// it advertises the name, IDs, axes and keys it was built with, then
+    /// exits. Meant for CI on machines with uinput access.
+    #[arg(long, conflicts_with_all = ["clone_source", "uinput_fd"], env = "INERTPAD_VERIFY_DEVICE")]
+    verify_device: bool,
+
+    /// Minimum accumulated movement (in pixels along any axis) emitted at once.
+    /// Smaller per-frame movements are held back and combined, which helps on
+    /// compositors that coalesce or drop tiny relative events. Total distance
+    /// is unaffected. 0 emits every frame.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_MIN_EMIT_PX")]
+    min_emit_px: u32,
+
+    /// Most relative events emitted per second during inertial movement.
+    /// Movement keeps being computed at --refresh-rate and accumulates
+    /// between events, so total distance is unaffected. Unlimited by default.
+    #[arg(long, value_parser = parse_positive, env = "INERTPAD_MAX_EVENT_RATE")]
+    max_event_rate: Option<f64>,
+
+    /// Stops any inertial movement after the specified number of milliseconds,
+    /// regardless of remaining velocity. 0 disables the limit.
+    #[arg(
+        long,
+        alias = "max-duration",
+        default_value_t = 0,
+        env = "INERTPAD_MAX_GLIDE_MS"
+    )]
+    max_glide_ms: u64,
+
+    /// Output used for inertial movement after lift-off.
+    /// Movement during contact is always delivered by the touchpad itself.
+    #[arg(long, value_enum, default_value_t = InertiaSink::Pointer, env = "INERTPAD_INERTIA_AS")]
+    inertia_as: InertiaSink,
+
+    /// Output for the horizontal component of inertial movement.
+    /// Default: same as --inertia-as.
+    #[arg(long, value_enum, env = "INERTPAD_INERTIA_AS_X")]
+    inertia_as_x: Option<InertiaSink>,
+
+    /// Output for the vertical component of inertial movement.
+    /// Default: same as --inertia-as.
+    #[arg(long, value_enum, env = "INERTPAD_INERTIA_AS_Y")]
+    inertia_as_y: Option<InertiaSink>,
+
+    /// Delivers inertial movement on this relative axis alone instead of
+    /// the pointer or wheel, for custom kinetic widgets such as dials.
+    /// The axis is driven by --rel-axis-component of the glide velocity,
+    /// which keeps the drag and speed factor of --inertia-as.
+    #[arg(long, value_enum, env = "INERTPAD_REL_AXIS")]
+    rel_axis: Option<RelAxis>,
+
+    /// Velocity component driving --rel-axis.
+    #[arg(long, value_enum, default_value_t = Component::Y, env = "INERTPAD_REL_AXIS_COMPONENT")]
+    rel_axis_component: Component,
+
+    /// Emits scroll inertia as discrete wheel clicks only, one per this many
+    /// high-resolution units (120 per notch), for apps that ignore smooth
+    /// scrolling. Default: high-resolution scrolling.
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..), env = "INERTPAD_SCROLL_STEP")]
+    scroll_step: Option<i32>,
+
+    /// Runs this shell command on every flick instead of creating a virtual
+    /// mouse. {dx}, {dy} and {speed} are replaced with the lift-off velocity
+    /// in touchpad units per second, and {direction} with `left`, `right`,
+    /// `up` or `down`. Commands run at most 4 times a second.
+    #[arg(long, value_name = "COMMAND", env = "INERTPAD_ON_FLICK")]
+    on_flick: Option<String>,
+
+    /// Shell command run once for flicks of the `command` finger mode of
+    /// --finger-modes, e.g. to switch workspaces with a hard three-finger
+    /// flick. Placeholders as in --on-flick, plus {direction}.
+    #[arg(long, value_name = "COMMAND", env = "INERTPAD_FLING_COMMAND")]
+    fling_command: Option<String>,
+
+    /// Path of the control socket.
+    /// Default: $XDG_RUNTIME_DIR/inertpad.sock (disabled if XDG_RUNTIME_DIR is unset)
+    #[arg(long, env = "INERTPAD_CONTROL_SOCKET")]
+    control_socket: Option<PathBuf>,
+
+    /// Serves Prometheus metrics over HTTP at this address, e.g.
+    /// 127.0.0.1:9899. Disabled by default.
+    #[arg(long, env = "INERTPAD_METRICS_ADDR")]
+    metrics_addr: Option<std::net::SocketAddr>,
+
+    /// Comma-separated applications (X11 WM_CLASS names) that get inertial
+    /// movement, all others don't. Requires the `x11` feature.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "APPS",
+        env = "INERTPAD_INERTIA_APPS"
+    )]
+    inertia_apps: Vec<String>,
+
+    /// Comma-separated applications (X11 WM_CLASS names) that never get
+    /// inertial movement. Requires the `x11` feature.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "APPS",
+        env = "INERTPAD_INERTIA_EXCLUDE"
+    )]
+    inertia_exclude: Vec<String>,
+
+    /// Prints detected touchpad devices and exits.
+    #[arg(long)]
+    list_devices: bool,
+
+    /// TOML file of options keyed by their long names, as printed by
+    /// --dump-config. Repeatable, later files overriding earlier ones.
+    /// Command line options and INERTPAD_* variables take precedence.
+    /// Defaults to $XDG_CONFIG_HOME/inertpad/config.toml, which may be
+    /// missing.
+    #[arg(long, value_name = "PATH", env = "INERTPAD_CONFIG")]
+    config: Vec<PathBuf>,
+
+    /// Prints the effective configuration (defaults and command line
+    /// options) as TOML and exits.
+    #[arg(long)]
+    dump_config: bool,
+
+    /// Validates the configuration without opening any device: prints the
+    /// effective inertia parameters as TOML, warns about unknown INERTPAD_*
+    /// environment variables, and exits.
+    #[arg(long)]
+    check_config: bool,
+
+    /// Reopens the touchpad if it delivers no events for the specified
+    /// number of milliseconds. Works around devices that silently stop
+    /// reporting after a driver glitch. 0 disables the watchdog.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_WATCHDOG_MS")]
+    watchdog_ms: u64,
+
+    /// Closes the touchpad after the specified number of milliseconds
+    /// without events, so that InertPad doesn't keep it from entering
+    /// low-power states, and reopens it as soon as another reader, such as
+    /// the compositor, reads from it again. 0 keeps the touchpad open.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_IDLE_RELEASE_MS")]
+    idle_release_ms: u64,
+
+    /// Logs a summary of every contact episode: touch-down time, peak speed,
+    /// finger count over time and whether lift-off triggered inertial movement.
+    #[arg(long, env = "INERTPAD_DEBUG_GESTURES")]
+    debug_gestures: bool,
+
+    /// Stops inertial movement and suppresses new one while the session
+    /// is idle, locked or going to sleep (requires the `dbus` feature).
+    #[arg(long, env = "INERTPAD_RESPECT_IDLE")]
+    respect_idle: bool,
+
+    /// Treats pressure at or above this value (raw ABS_PRESSURE or
+    /// ABS_MT_PRESSURE units) as finger contact, and pressure dropping below
+    /// it as lift-off. For touchpads that signal contact via pressure.
+    #[arg(long, env = "INERTPAD_PRESSURE_CONTACT_THRESHOLD")]
+    pressure_contact_threshold: Option<i32>,
+
+    /// Delay before creating the virtual mouse device in milliseconds,
+    /// e.g. to let the compositor finish its input configuration first.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_STARTUP_DELAY_MS")]
+    startup_delay_ms: u64,
+
+    /// Number of position deltas discarded after each touch-down before
+    /// tracking velocity, to ignore the jump of the finger landing.
+    #[arg(long, default_value_t = 1, env = "INERTPAD_SKIP_FIRST_SAMPLES")]
+    skip_first_samples: u32,
+
+    /// Weight (above 0 up to 1) of the newest velocity sample per 10 ms of
+    /// reports in the smoothed swipe velocity, for touchpads whose high
+    /// report rate makes single-report deltas noisy. The weight per report
+    /// follows the report interval. 1 disables smoothing.
+    #[arg(long, value_name = "ALPHA", default_value_t = 1.0, value_parser = parse_weight, env = "INERTPAD_REPORT_SMOOTHING")]
+    report_smoothing: f64,
+
+    /// Number of most recent position deltas the swipe velocity averages,
+    /// weighted by their durations, so that a jittery last report before
+    /// lift-off doesn't decide the flick on its own. 1 takes the last
+    /// delta only.
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..), env = "INERTPAD_VELOCITY_SAMPLES")]
+    velocity_samples: u32,
+
+    /// Highest lift-off speed launching a glide, in touchpad units per
+    /// second like --speed-threshold. Faster flicks launch at this speed,
+    /// in their direction. 0 means no cap.
+    #[arg(long, value_name = "SPEED", default_value_t = 0.0, value_parser = parse_non_negative, env = "INERTPAD_MAX_SPEED")]
+    max_speed: f64,
+
+    /// Most fingers allowed to touch during a swipe for it to trigger pointer
+    /// inertia, even momentarily.
+    #[arg(long, default_value_t = 1, env = "INERTPAD_POINTER_MAX_FINGERS")]
+    pointer_max_fingers: u8,
+
+    /// Comma-separated inertia modes per finger count, as COUNT=MODE, e.g.
+    /// 1=pointer,2=ignore,3=scroll-h. Finger counts that aren't listed get
+    /// no inertia. Overrides --pointer-max-fingers. Default: pointer inertia
+    /// for swipes within --pointer-max-fingers.
+    #[arg(
+        long,
+        value_name = "COUNT=MODE",
+        value_delimiter = ',',
+        env = "INERTPAD_FINGER_MODES"
+    )]
+    finger_modes: Vec<FingerModeEntry>,
+
+    /// Comma-separated drag multipliers per finger count of the swipe, as
+    /// COUNT:MULTIPLIER, e.g. 1:1.0,2:1.5,3:2.0. Counts between two listed
+    /// ones interpolate, others keep the drag unchanged.
+    #[arg(
+        long,
+        value_name = "COUNT:MULTIPLIER",
+        value_delimiter = ',',
+        env = "INERTPAD_FINGER_DRAG"
+    )]
+    finger_drag: Vec<FingerDragEntry>,
+
+    /// Swipes starting within this fraction of the touchpad size from any
+    /// edge don't trigger inertial movement, e.g. to keep edge gestures free
+    /// of glides. 0 disables.
+    #[arg(long, default_value_t = 0.0, env = "INERTPAD_EDGE_MARGIN")]
+    edge_margin: f64,
+
+    /// Swipes lifted off less than this many milliseconds after touching
+    /// down don't trigger inertial movement, so that fast taps never glide.
+    /// 0 disables.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_MIN_CONTACT_MS")]
+    min_contact_ms: u64,
+
+    /// Makes touching down during inertial movement "grab" it: the glide
+    /// decays smoothly within a few frames instead of stopping instantly,
+    /// while the finger takes over control.
+    #[arg(long, env = "INERTPAD_GRAB_GLIDE")]
+    grab_glide: bool,
+
+    /// Makes touching down during inertial movement bring it to a stop
+    /// over the specified number of milliseconds with a steeply raised
+    /// drag, instead of stopping it instantly. Ignored with --grab-glide.
+    /// 0 stops instantly.
+    #[arg(long, default_value_t = 0, env = "INERTPAD_SOFT_CATCH_MS")]
+    soft_catch_ms: u64,
+
+    /// How touchpad events are read: `latency` handles each batch as soon
+    /// as it's read, `throughput` first drains everything already queued
+    /// and handles it at once, waking less often at the cost of a later
+    /// launch when reports pile up.
+    #[arg(long, value_enum, default_value_t = ReadStrategy::Latency, env = "INERTPAD_READ_STRATEGY")]
+    read_strategy: ReadStrategy,
+
+    /// Measures per-event handling time and lift-off to StartMovement
+    /// latency, and logs their histograms on exit (Ctrl+C).
+    #[arg(long, env = "INERTPAD_BENCHMARK_CAPTURE")]
+    benchmark_capture: bool,
+
+    /// Observes the touchpad for a few seconds of normal use, logs a
+    /// histogram of its report intervals and prints the --refresh-rate
+    /// matching its report rate, then exits without emitting motion.
+    #[arg(long, env = "INERTPAD_PROBE_RATE")]
+    probe_rate: bool,
+
+    /// Holds the touchpad through a read-only descriptor, so that InertPad
+    /// can't write to it. InertPad never grabs the touchpad either way, and
+    /// the compositor keeps receiving its events as usual.
+    #[arg(long, env = "INERTPAD_READ_ONLY")]
+    read_only: bool,
+
+    /// Rule deciding which input device is the touchpad.
+    #[arg(long, value_enum, default_value_t = MatchStrategy::default(), env = "INERTPAD_MATCH_STRATEGY")]
+    match_strategy: MatchStrategy,
+
+    /// Which touchpad to capture when several satisfy the match strategy:
+    /// the first one, or the best by a capability score that prefers
+    /// built-in multitouch touchpads. The scores are logged.
+    #[arg(long, value_enum, default_value_t = Selection::default(), env = "INERTPAD_SELECT")]
+    select: Selection,
+
+    /// Device node to capture instead of detecting one, such as
+    /// /dev/input/by-path/*i2c*-event-mouse. `*` and `?` wildcards must
+    /// match exactly one node, resolved again on reconnect.
+    #[arg(long, env = "INERTPAD_DEVICE_PATH")]
+    device_path: Option<String>,
+
+    /// Exits with the no-touchpad status when the device disconnects or
+    /// fails, instead of waiting for it to come back, e.g. for a service
+    /// manager to restart InertPad.
+    #[arg(long, env = "INERTPAD_NO_RECONNECT")]
+    no_reconnect: bool,
+
+    /// Device to capture instead of detecting one: a node path such as
+    /// /dev/input/event7 (wildcards as in --device-path), or a touchpad
+    /// name as shown by --list-devices, matched case-insensitively in full
+    /// or else as a substring.
+    #[arg(
+        long,
+        value_name = "PATH|NAME",
+        conflicts_with = "device_path",
+        env = "INERTPAD_DEVICE"
+    )]
+    device: Option<String>,
+
+    /// Ignores the input device and launches a glide at --max-launch-speed
+    /// every --test-pattern-interval-ms, alternating direction, to compare
+    /// glide smoothness across compositors. Meant for diagnostics.
+    #[arg(long, env = "INERTPAD_TEST_PATTERN")]
+    test_pattern: bool,
+
+    /// Time between test pattern launches in milliseconds.
+    #[arg(
+        long,
+        default_value_t = 2000,
+        env = "INERTPAD_TEST_PATTERN_INTERVAL_MS"
+    )]
+    test_pattern_interval_ms: u64,
+
+    /// Nudges the pointer a pixel back and forth every this many seconds,
+    /// regardless of the touchpad, to keep the screen from blanking, e.g.
+    /// during presentations. The pointer ends where it was.
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), env = "INERTPAD_KEEP_AWAKE")]
+    keep_awake: Option<u64>,
+
+    /// Plays a short rumble when a glide decelerates to a stop, on the first
+    /// device with force-feedback rumble support. Best-effort: few touchpads
+    /// have one, and without it nothing happens.
+    #[arg(long, env = "INERTPAD_END_PULSE")]
+    end_pulse: bool,
+
+    /// Input whose movement gets inertia.
+    #[arg(long, value_enum, default_value_t = Source::Touchpad, env = "INERTPAD_SOURCE")]
+    source: Source,
+
+    /// Shows an interactive terminal UI for tuning drag, speed factor and
+    /// speed threshold live (requires the `tui` feature).
+    #[arg(long, env = "INERTPAD_TUI")]
+    tui: bool,
+
+    /// Overrides the touchpad X axis range and resolution reported by the device,
+    /// as MIN:MAX:RES (resolution in units per millimeter).
+    /// An escape hatch for touchpads with broken descriptors.
+    #[arg(long, value_name = "MIN:MAX:RES", env = "INERTPAD_ABS_RANGE_X")]
+    abs_range_x: Option<AbsRange>,
+
+    /// Overrides the touchpad Y axis range and resolution reported by the device,
+    /// as MIN:MAX:RES (resolution in units per millimeter).
+    #[arg(long, value_name = "MIN:MAX:RES", env = "INERTPAD_ABS_RANGE_Y")]
+    abs_range_y: Option<AbsRange>,
+
+    /// Comma-separated list of touchpad key presses that stop inertial movement.
+    /// Button presses stop it by default, so that a click lands where the
+    /// pointer was when clicking.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [StopKey::Finger, StopKey::Multitouch, StopKey::Left, StopKey::Right, StopKey::Middle],
+        env = "INERTPAD_STOP_ON"
+    )]
+    stop_on: Vec<StopKey>,
+}
+
+impl Args {
+    fn routing(&self) -> Routing {
+        Routing {
+            x: self.inertia_as_x.unwrap_or(self.inertia_as),
+            y: self.inertia_as_y.unwrap_or(self.inertia_as),
+        }
+    }
+
+    /// Inertia mode for each finger count, if configured
+    fn finger_modes(&self) -> Option<FingerModes> {
+        if self.finger_modes.is_empty() {
+            return None;
+        }
+        let mut modes = [FingerMode::Ignore; MAX_FINGERS];
+        for entry in &self.finger_modes {
+            modes[usize::from(entry.fingers) - 1] = entry.mode;
+        }
+        Some(modes)
+    }
+
+    /// Drag multiplier for each finger count, if configured
+    ///
+    /// Unlisted counts between two listed ones interpolate linearly, those
+    /// outside the listed range get 1.
+    fn finger_drag(&self) -> Option<FingerDrag> {
+        if self.finger_drag.is_empty() {
+            return None;
+        }
+        let mut listed = self.finger_drag.clone();
+        listed.sort_by_key(|entry| entry.fingers);
+        let mut table = [1.0; MAX_FINGERS];
+        for (fingers, multiplier) in (1..).zip(&mut table) {
+            let below = listed.iter().rev().find(|entry| entry.fingers <= fingers);
+            let above = listed.iter().find(|entry| entry.fingers >= fingers);
+            if let (Some(below), Some(above)) = (below, above) {
+                *multiplier = if below.fingers == above.fingers {
+                    below.multiplier
+                } else {
+                    let t = f64::from(fingers - below.fingers)
+                        / f64::from(above.fingers - below.fingers);
+                    below.multiplier + (above.multiplier - below.multiplier) * t
+                };
+            }
+        }
+        Some(table)
+    }
+
+    /// Tunable parameters as given on the command line
+    fn base_params(&self) -> Params {
+        Params {
+            drag: self.drag.x,
+            speed_factor: self.speed_factor.x,
+            speed_threshold: self.speed_threshold,
+            response_curve: self.response_curve,
+            min_launch_speed: self.min_launch_speed,
+            settle_damp: self.settle_damp,
+            soft_threshold: self.soft_threshold,
+            speed_threshold_low: self.speed_threshold_low,
+            drag_curve: self.drag_curve,
+            scroll_drag: self.scroll_drag,
+            drag_x: None,
+            drag_y: self.drag.y,
+            scroll_speed_factor: self.scroll_speed_factor,
+            speed_factor_x: self.speed_factor_x,
+            speed_factor_y: self.speed_factor_y.or(self.speed_factor.y),
+            scroll_speed_threshold: self.scroll_speed_threshold,
+        }
+    }
+
+    /// What drives the emulation, for the `info` control command
+    fn mode_description(&self) -> String {
+        if self.test_pattern {
+            return "test-pattern".to_owned();
+        }
+        if self.on_flick.is_some() {
+            return "on-flick".to_owned();
+        }
+        let name = |value: Option<clap::builder::PossibleValue>| {
+            value.map_or_else(String::new, |value| value.get_name().to_owned())
+        };
+        format!(
+            "{} inertia-as={}",
+            name(self.source.to_possible_value()),
+            name(self.inertia_as.to_possible_value())
+        )
+    }
+
+    fn emulation_options(&self) -> EmulationOptions {
+        EmulationOptions {
+            refresh_rate: self.refresh_rate,
+            min_emit_px: self.min_emit_px,
+            min_emit_interval: self
+                .max_event_rate
+                .map(|rate| time::Duration::from_secs_f64(rate.recip())),
+            max_glide: time::Duration::from_millis(self.max_glide_ms),
+            rounding: self.rounding,
+            decay: match self.decay_model {
+                DecayModel::Exponential => None,
+                model => self.deceleration.map(|deceleration| (model, deceleration)),
+            },
+            flush_remainder: self.flush_remainder,
+            match_input_rate: self.match_input_rate,
+            pace_output: self.pace_output,
+            stamp_events: self.stamp_events,
+            glide_keepalive: self.glide_keepalive,
+            resistance: self.resistance,
+            snap_octants: self.snap_octants,
+            blend_flicks: self.blend_flicks,
+            chain_max_speed: self.chain_max_speed,
+            blend_scroll_reversals: self.blend_scroll_reversals,
+            scroll_accel: self.scroll_accel,
+            overscroll: self
+                .scroll_overscroll_distance
+                .map(|distance| (distance, self.scroll_overscroll_damping)),
+            park_speed: self.park_speed,
+            launch_delay: time::Duration::from_millis(self.launch_delay_ms),
+            predict_latency: time::Duration::from_millis(self.predict_latency_ms),
+            finger_drag: self.finger_drag(),
+            soft_catch: time::Duration::from_millis(self.soft_catch_ms),
+            max_frame_delta: self.max_frame_delta,
+            safe_mode_threshold: self.safe_mode_threshold,
+            safe_mode_pause: self.safe_mode_pause,
+            strict: self.strict,
+            accel_profile: self.accel_profile,
+            accel_speed: self.accel_speed,
+            accel_curve: self.accel_curve_file.clone(),
+            kind: self.inertia_as.into(),
+        }
+    }
+
+    fn capture_options(&self) -> CaptureOptions {
+        CaptureOptions {
+            cooldowns: Cooldowns::from_millis(
+                self.multitouch_cooldown,
+                self.click_cooldown,
+                self.post_glide_cooldown,
+            ),
+            scroll_cooldowns: Cooldowns::from_millis(
+                self.scroll_multitouch_cooldown
+                    .unwrap_or(self.multitouch_cooldown),
+                self.scroll_click_cooldown.unwrap_or(self.click_cooldown),
+                self.scroll_post_glide_cooldown
+                    .unwrap_or(self.post_glide_cooldown),
+            ),
+            cooldown_taper: time::Duration::from_millis(self.cooldown_taper_ms),
+            click_settle: time::Duration::from_millis(self.click_settle),
+            strict_post_glide_cooldown: self.strict_post_glide_cooldown,
+            chain_window: self.chain_window_ms.map(time::Duration::from_millis),
+            adaptive_threshold: self.adaptive_threshold,
+            axes: self.axes,
+            rotate_deg: self.rotate_deg,
+            watchdog: (self.watchdog_ms > 0).then(|| time::Duration::from_millis(self.watchdog_ms)),
+            idle_release: (self.idle_release_ms > 0)
+                .then(|| time::Duration::from_millis(self.idle_release_ms)),
+            stop_on: self.stop_on.clone(),
+            debug_gestures: self.debug_gestures,
+            abs_range: (self.abs_range_x, self.abs_range_y),
+            kind: self.inertia_as.into(),
+            pressure_contact_threshold: self.pressure_contact_threshold,
+            grab_glide: self.grab_glide,
+            soft_catch: self.soft_catch_ms > 0,
+            skip_first_samples: self.skip_first_samples,
+            report_smoothing: (self.report_smoothing < 1.0).then_some(self.report_smoothing),
+            velocity_samples: self.velocity_samples as usize,
+            max_speed: (self.max_speed > 0.0).then_some(self.max_speed),
+            pointer_max_fingers: self.pointer_max_fingers,
+            read_only: self.read_only,
+            read_strategy: self.read_strategy,
+            finger_modes: self.finger_modes(),
+            fling_command: self.fling_command.clone(),
+            edge_margin: self.edge_margin,
+            min_contact: time::Duration::from_millis(self.min_contact_ms),
+        }
+    }
 }
 
-enum MomentumMessage {
-    StartMovement(f64, f64),
-    StopMovement,
+/// Touchpad axis range and resolution
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct AbsRange {
+    min: i32,
+    max: i32,
+    /// Units per millimeter
+    resolution: i32,
 }
 
-/// Emulates mouse device (via uinput) which performs inertial pointer movement
-struct VirtualMouse {
-    device: uinput::VirtualDevice,
+impl AbsRange {
+    fn clamp(&self, value: i32) -> i32 {
+        value.clamp(self.min, self.max)
+    }
+
+    /// Whether the value lies within `margin` (a fraction of the range)
+    /// of either end
+    fn near_end(&self, value: i32, margin: f64) -> bool {
+        let margin = f64::from(self.max - self.min) * margin;
+        f64::from(value) < f64::from(self.min) + margin
+            || f64::from(value) > f64::from(self.max) - margin
+    }
+
+    /// Whether a position lies within the edge margin, false if the range
+    /// of an axis is unknown
+    fn in_edge(
+        x_range: Option<AbsRange>,
+        y_range: Option<AbsRange>,
+        (x, y): (i32, i32),
+        margin: f64,
+    ) -> bool {
+        x_range.is_some_and(|r| r.near_end(x, margin))
+            || y_range.is_some_and(|r| r.near_end(y, margin))
+    }
 }
 
-impl VirtualMouse {
-    fn new() -> Result<Self> {
-        use evdev::{AttributeSet, BusType, InputId, Key, RelativeAxisType};
-        let device = uinput::VirtualDeviceBuilder::new()?
-            .name("InertPad Virtual Mouse")
-            .input_id(InputId::new(BusType::BUS_USB, 0x1234, 0x5678, 0))
-            .with_keys(&[Key::BTN_LEFT].into_iter().collect::<AttributeSet<_>>())?
-            .with_relative_axes(
-                &[RelativeAxisType::REL_X, RelativeAxisType::REL_Y]
-                    .into_iter()
-                    .collect::<AttributeSet<_>>(),
-            )?
-            .build()?;
-        Ok(Self { device })
+/// Rotates a vector by `deg` degrees, clockwise with Y growing downwards
+fn rotate((x, y): (f64, f64), deg: f64) -> (f64, f64) {
+    if deg == 0.0 {
+        return (x, y);
     }
+    let (sin, cos) = deg.to_radians().sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+}
 
-    fn set_position(&mut self, x: i32, y: i32) -> io::Result<()> {
-        use evdev::{EventType, InputEvent, RelativeAxisType, Synchronization};
-        let events = [
-            InputEvent::new(EventType::RELATIVE, RelativeAxisType::REL_X.0, x),
-            InputEvent::new(EventType::RELATIVE, RelativeAxisType::REL_Y.0, y),
-            InputEvent::new(EventType::SYNCHRONIZATION, Synchronization::SYN_REPORT.0, 0),
-        ];
-        self.device.emit(&events)?;
-        Ok(())
+fn parse_positive(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|e| format!("invalid number: {}", e))?;
+    if !(value > 0.0 && value.is_finite()) {
+        return Err("must be a positive number".to_owned());
     }
+    Ok(value)
+}
 
-    fn run_emulation(
-        &mut self,
-        receiver: mpsc::Receiver<MomentumMessage>,
-        drag: f64,
-        speed_factor: f64,
-        refresh_rate: f64,
-    ) {
-        let period = time::Duration::from_secs_f64(refresh_rate.recip());
-        let mut is_moving = false;
-        let (mut vx, mut vy) = (0f64, 0f64);
-        let deceleration_factor = 1.0 - drag.clamp(0.0, 1.0);
+fn parse_non_negative(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|e| format!("invalid number: {}", e))?;
+    if !(value >= 0.0 && value.is_finite()) {
+        return Err("must be a number of at least 0".to_owned());
+    }
+    Ok(value)
+}
 
-        loop {
-            if is_moving {
-                if let Ok(MomentumMessage::StopMovement) = receiver.recv_timeout(period) {
-                    log::debug!("Emulation: stop movement");
-                    is_moving = false;
-                    (vx, vy) = (0.0, 0.0);
-                } else {
-                    let (x, y) = ((vx * speed_factor) as i32, (vy * speed_factor) as i32);
-                    if x == 0 && y == 0 {
-                        is_moving = false;
-                        (vx, vy) = (0.0, 0.0);
-                    } else {
-                        (vx, vy) = (vx * deceleration_factor, vy * deceleration_factor);
-                        log::trace!("Emulation: relative position = ({}, {})", x, y);
-                        self.set_position(x, y).unwrap();
-                    }
-                }
-            } else if let Ok(MomentumMessage::StartMovement(x, y)) = receiver.recv() {
-                log::debug!(
-                    "Emulation: start movement, velocity = ({:.02}, {:.02})",
-                    x,
-                    y
-                );
-                is_moving = true;
-                (vx, vy) = (x, y);
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|e| format!("invalid number: {}", e))?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err("must be between 0 and 1".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_multiplier(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|e| format!("invalid number: {}", e))?;
+    if !(value >= 1.0 && value.is_finite()) {
+        return Err("must be a number of at least 1".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_weight(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|e| format!("invalid number: {}", e))?;
+    if !(value > 0.0 && value <= 1.0) {
+        return Err("must be above 0 and at most 1".to_owned());
+    }
+    Ok(value)
+}
+
+fn parse_percentile(s: &str) -> Result<f64, String> {
+    let percentile: f64 = s.parse().map_err(|e| format!("invalid number: {}", e))?;
+    if !(0.0..=100.0).contains(&percentile) {
+        return Err("percentile must be between 0 and 100".to_owned());
+    }
+    Ok(percentile)
+}
+
+impl std::str::FromStr for AbsRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .split(':')
+            .map(|part| part.trim().parse::<i32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid number: {}", e))?;
+        let [min, max, resolution] = parts[..] else {
+            return Err("expected MIN:MAX:RES".to_owned());
+        };
+        if min >= max {
+            return Err("MIN must be less than MAX".to_owned());
+        }
+        if resolution <= 0 {
+            return Err("RES must be positive".to_owned());
+        }
+        Ok(Self {
+            min,
+            max,
+            resolution,
+        })
+    }
+}
+
+impl std::fmt::Display for AbsRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.min, self.max, self.resolution)
+    }
+}
+
+/// Deceleration of glides over time
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DecayModel {
+    /// Lose a share of the velocity every frame, coasting on ever slower
+    Exponential,
+    /// Lose a fixed amount of speed per second, stopping at a predictable
+    /// distance
+    Linear,
+    /// Like linear, but the deceleration eases off as the glide slows down,
+    /// for a softer landing
+    EaseOut,
+}
+
+impl DecayModel {
+    /// Speed a glide loses over `seconds`, decelerating by `deceleration`
+    /// from its launch speed
+    fn speed_loss(self, deceleration: f64, speed: f64, launch_speed: f64, seconds: f64) -> f64 {
+        match self {
+            DecayModel::Exponential => 0.0,
+            DecayModel::Linear => deceleration * seconds,
+            DecayModel::EaseOut if launch_speed > 0.0 => {
+                // Velocity falls along (1 - t/T)^2, twice as long as linear
+                deceleration * (speed / launch_speed).sqrt() * seconds
             }
+            DecayModel::EaseOut => 0.0,
         }
     }
 }
 
-/// Captures raw evdev touchpad events and forwards
-struct Touchpad {
-    device: evdev::Device,
+/// Rounding of per-frame deltas to whole output units
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Rounding {
+    /// Truncate toward zero, under-emits motion
+    Trunc,
+    /// Round to nearest
+    Round,
+    /// Round up with probability equal to the fraction, preserving the
+    /// expected distance
+    Stochastic,
+    /// Carry the fraction over to the next frames, emitting whole units as
+    /// they add up
+    Accumulate,
 }
 
-impl Touchpad {
-    fn default() -> Option<Self> {
-        for (_path, device) in evdev::enumerate() {
-            if let Some(keys) = device.supported_keys() {
-                if keys.contains(evdev::Key::BTN_TOOL_FINGER)
-                    && keys.contains(evdev::Key::BTN_TOUCH)
-                {
-                    return Some(Self { device });
-                }
+impl Rounding {
+    /// Frame delta below which an accumulating glide stops, where a unit
+    /// takes ten frames to add up
+    const ACCUMULATE_STOP: f64 = InertiaState::STOP_DELTA;
+
+    fn apply(self, value: f64, rng: &mut u64) -> i32 {
+        match self {
+            Rounding::Trunc | Rounding::Accumulate => value as i32,
+            Rounding::Round => value.round() as i32,
+            Rounding::Stochastic => {
+                let floor = value.floor();
+                let round_up = next_random(rng) < value - floor;
+                floor as i32 + i32::from(round_up)
             }
         }
-        None
     }
 
-    fn run_capture(
-        &mut self,
-        sender: mpsc::Sender<MomentumMessage>,
-        speed_threshold: f64,
-        multitouch_cooldown: u64,
-    ) {
-        use evdev::{AbsoluteAxisType, InputEventKind, Key};
-        let (mut vx, mut vy) = (0f64, 0f64);
-        let (mut x, mut y) = (0, 0);
-        let (mut prev_x, mut prev_y) = (0, 0);
-        let mut timestamp = time::SystemTime::UNIX_EPOCH;
-        let mut prev_timestamp = time::SystemTime::UNIX_EPOCH;
-        let mut multitouch_timestamp = time::SystemTime::UNIX_EPOCH;
-        let multitouch_cooldown = time::Duration::from_millis(multitouch_cooldown);
-
-        while let Ok(events) = self.device.fetch_events() {
-            for event in events {
-                timestamp = event.timestamp();
-                log::trace!("Touchpad event: {:?} = {}", event.kind(), event.value());
-                match event.kind() {
-                    InputEventKind::AbsAxis(axis) => match axis {
-                        AbsoluteAxisType::ABS_X => x = event.value(),
-                        AbsoluteAxisType::ABS_Y => y = event.value(),
-                        _ => (),
-                    },
-                    InputEventKind::Key(key) => match key {
-                        Key::BTN_TOOL_FINGER => {
-                            if event.value() == 1 {
-                                let _ = sender.send(MomentumMessage::StopMovement);
-                                (vx, vy) = (0.0, 0.0);
-                                (prev_x, prev_y) = (x, y); // Prevent velocity overwrite later
-                            } else {
-                                // Filter out multi-touch lift-off
-                                if timestamp
-                                    .duration_since(multitouch_timestamp)
-                                    .unwrap_or_default()
-                                    < multitouch_cooldown
-                                {
-                                    continue;
-                                }
-                                let speed = (vx * vx + vy * vy).sqrt();
-                                if speed >= speed_threshold {
-                                    let _ = sender.send(MomentumMessage::StartMovement(vx, vy));
-                                }
-                            }
-                        }
-                        Key::BTN_TOOL_DOUBLETAP
-                        | Key::BTN_TOOL_TRIPLETAP
-                        | Key::BTN_TOOL_QUADTAP
-                        | Key::BTN_TOOL_QUINTTAP => {
-                            if event.value() == 1 {
-                                let _ = sender.send(MomentumMessage::StopMovement);
-                            } else {
-                                multitouch_timestamp = timestamp;
-                            }
-                        }
-                        _ => {}
-                    },
-                    _ => {}
-                }
+    /// Whether a delta is too small to keep the glide going
+    fn is_negligible(self, value: f64) -> bool {
+        match self {
+            Rounding::Trunc => value.abs() < 1.0,
+            Rounding::Round | Rounding::Stochastic => value.abs() < 0.5,
+            Rounding::Accumulate => value.abs() < Self::ACCUMULATE_STOP,
+        }
+    }
+}
+
+/// Pointer acceleration the compositor is assumed to apply, modeled after
+/// libinput's profiles
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum AccelProfile {
+    /// No compensation, motion is emitted as is
+    None,
+    /// Constant factor of 1 + speed
+    Flat,
+    /// Factor growing with the pointer speed, from 1 up to a maximum
+    Adaptive,
+}
+
+impl AccelProfile {
+    /// Bisection steps for the adaptive profile, well below a pixel
+    const ITERATIONS: usize = 24;
+
+    /// Acceleration factor for a pointer speed in pixels per millisecond,
+    /// with `speed` being the libinput speed setting in `[-1, 1]`
+    fn factor(self, velocity: f64, speed: f64) -> f64 {
+        let speed = speed.clamp(-1.0, 1.0);
+        match self {
+            AccelProfile::None => 1.0,
+            AccelProfile::Flat => (1.0 + speed).max(0.1),
+            AccelProfile::Adaptive => {
+                let threshold = 0.4 - 0.25 * speed;
+                let incline = 1.1 + 0.5 * speed;
+                let max_accel = (2.0 + 1.5 * speed).max(1.0);
+                (1.0 + incline * (velocity - threshold)).clamp(1.0, max_accel)
             }
-            if x != prev_x || y != prev_y {
-                let dx = (x - prev_x) as f64;
-                let dy = (y - prev_y) as f64;
-                let dt = timestamp
-                    .duration_since(prev_timestamp)
-                    .unwrap()
-                    .as_secs_f64();
-                (vx, vy) = (dx / dt, dy / dt);
-                (prev_x, prev_y) = (x, y);
-                prev_timestamp = timestamp;
-                log::trace!("Velocity: ({:.02}, {:.02})", vx, vy);
+        }
+    }
+
+    /// Distance to emit in a frame, so that it's `distance` once accelerated
+    fn compensated(self, distance: f64, period: time::Duration, speed: f64) -> f64 {
+        let ms = (period.as_secs_f64() * 1000.0).max(f64::EPSILON);
+        if self != AccelProfile::Adaptive {
+            return distance / self.factor(0.0, speed);
+        }
+        // The accelerated distance grows monotonically with the emitted one,
+        // and the factor is at least 1, so bisect within `[0, distance]`
+        let (mut low, mut high) = (0.0, distance);
+        for _ in 0..Self::ITERATIONS {
+            let mid = (low + high) / 2.0;
+            if mid * self.factor(mid / ms, speed) < distance {
+                low = mid;
+            } else {
+                high = mid;
             }
         }
+        (low + high) / 2.0
     }
 }
 
-fn main() {
-    env_logger::Builder::new()
-        .filter_module("inertpad", log::LevelFilter::Info)
-        .parse_default_env()
-        .init();
+/// Pointer acceleration measured as multipliers at input speeds
+#[derive(Clone, Debug)]
+struct AccelCurve {
+    /// `(speed, multiplier)` points with increasing speeds, in pixels per
+    /// millisecond
+    points: Vec<(f64, f64)>,
+}
 
-    let args = Args::parse();
-    let (sender, receiver) = mpsc::channel();
+impl AccelCurve {
+    /// Reads a curve file, for use as a clap value parser
+    fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&text)
+    }
 
-    match Touchpad::default() {
-        None => log::error!("Touchpad not found!"),
-        Some(mut touchpad) => {
-            log::info!(
-                "Found touchpad: {}",
-                touchpad.device.name().unwrap_or_default()
-            );
-            match VirtualMouse::new() {
-                Err(e) => log::error!("Failed to create virtual mouse device: {}", e),
-                Ok(mut vmouse) => {
-                    log::info!("Virtual mouse device is created");
-                    thread::spawn(move || {
-                        touchpad.run_capture(
-                            sender,
-                            args.speed_threshold,
-                            args.multitouch_cooldown,
-                        );
-                    });
-                    vmouse.run_emulation(receiver, args.drag, args.speed_factor, args.refresh_rate);
+    /// Parses `speed,multiplier` lines, skipping blank lines and `#` comments
+    ///
+    /// Speeds must increase and the accelerated speed must never decrease,
+    /// so that every output distance has a single input distance.
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        for (number, line) in (1..).zip(text.lines()) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<_> = line.split(',').map(str::trim).collect();
+            let &[speed, multiplier] = fields.as_slice() else {
+                return Err(format!("line {}: expected speed,multiplier", number));
+            };
+            let number_at = |field: &str| {
+                field
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(|| format!("line {}: invalid number {:?}", number, field))
+            };
+            let (speed, multiplier) = (number_at(speed)?, number_at(multiplier)?);
+            if speed < 0.0 {
+                return Err(format!("line {}: speed must not be negative", number));
+            }
+            if multiplier <= 0.0 {
+                return Err(format!("line {}: multiplier must be positive", number));
+            }
+            if let Some(&(last_speed, last_multiplier)) = points.last() {
+                if speed <= last_speed {
+                    return Err(format!("line {}: speeds must increase", number));
                 }
+                if speed * multiplier < last_speed * last_multiplier {
+                    return Err(format!(
+                        "line {}: accelerated speed must not decrease",
+                        number
+                    ));
+                }
+            }
+            points.push((speed, multiplier));
+        }
+        if points.is_empty() {
+            return Err("no speed,multiplier points".to_owned());
+        }
+        Ok(Self { points })
+    }
+
+    /// Multiplier at a pointer speed, held constant beyond the first and
+    /// last points
+    fn factor(&self, velocity: f64) -> f64 {
+        let next = self.points.partition_point(|&(speed, _)| speed < velocity);
+        match (next.checked_sub(1), self.points.get(next)) {
+            (Some(i), Some(&(speed, multiplier))) => {
+                let (last_speed, last_multiplier) = self.points[i];
+                let t = (velocity - last_speed) / (speed - last_speed);
+                last_multiplier + (multiplier - last_multiplier) * t
+            }
+            (None, Some(&(_, multiplier))) => multiplier,
+            (_, None) => self.points[self.points.len() - 1].1,
+        }
+    }
+
+    /// Distance to emit in a frame, so that it's `distance` once accelerated
+    fn compensated(&self, distance: f64, period: time::Duration) -> f64 {
+        let ms = (period.as_secs_f64() * 1000.0).max(f64::EPSILON);
+        // Validation keeps the accelerated distance monotonic, and the
+        // smallest multiplier bounds the emitted one
+        let min = self
+            .points
+            .iter()
+            .map(|&(_, multiplier)| multiplier)
+            .fold(f64::INFINITY, f64::min);
+        let (mut low, mut high) = (0.0, distance / min);
+        for _ in 0..AccelProfile::ITERATIONS {
+            let mid = (low + high) / 2.0;
+            if mid * self.factor(mid / ms) < distance {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        (low + high) / 2.0
+    }
+}
+
+/// Uniform random number in `[0, 1)` from a xorshift64 state
+fn next_random(state: &mut u64) -> f64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum StopKey {
+    /// Single finger touch-down (BTN_TOOL_FINGER)
+    Finger,
+    /// Any contact with the surface (BTN_TOUCH)
+    Touch,
+    /// Two or more fingers touch-down (BTN_TOOL_DOUBLETAP..BTN_TOOL_QUINTTAP)
+    Multitouch,
+    /// Left button press (BTN_LEFT)
+    Left,
+    /// Right button press (BTN_RIGHT)
+    Right,
+    /// Middle button press (BTN_MIDDLE)
+    Middle,
+}
+
+impl StopKey {
+    fn matches(self, key: evdev::Key) -> bool {
+        use evdev::Key;
+        match self {
+            StopKey::Finger => key == Key::BTN_TOOL_FINGER,
+            StopKey::Touch => key == Key::BTN_TOUCH,
+            StopKey::Multitouch => matches!(
+                key,
+                Key::BTN_TOOL_DOUBLETAP
+                    | Key::BTN_TOOL_TRIPLETAP
+                    | Key::BTN_TOOL_QUADTAP
+                    | Key::BTN_TOOL_QUINTTAP
+            ),
+            StopKey::Left => key == Key::BTN_LEFT,
+            StopKey::Right => key == Key::BTN_RIGHT,
+            StopKey::Middle => key == Key::BTN_MIDDLE,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Source {
+    /// Touchpad swipes, found with --match-strategy
+    Touchpad,
+    /// Native wheel events of a device that scrolls without moving a pointer,
+    /// continued with a kinetic scroll tail
+    Wheel,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ReadStrategy {
+    /// Handle every read right away
+    Latency,
+    /// Drain all queued events before handling them
+    Throughput,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum SinkKind {
+    /// Virtual input device created through /dev/uinput
+    Uinput,
+    /// Wayland `zwlr_virtual_pointer_v1`
+    Wayland,
+}
+
+/// Relative axis for `--rel-axis`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum RelAxis {
+    /// REL_DIAL
+    Dial,
+    /// REL_MISC
+    Misc,
+    /// REL_Z
+    Z,
+    /// REL_RX
+    Rx,
+    /// REL_RY
+    Ry,
+    /// REL_RZ
+    Rz,
+}
+
+impl From<RelAxis> for evdev::RelativeAxisType {
+    fn from(axis: RelAxis) -> Self {
+        match axis {
+            RelAxis::Dial => Self::REL_DIAL,
+            RelAxis::Misc => Self::REL_MISC,
+            RelAxis::Z => Self::REL_Z,
+            RelAxis::Rx => Self::REL_RX,
+            RelAxis::Ry => Self::REL_RY,
+            RelAxis::Rz => Self::REL_RZ,
+        }
+    }
+}
+
+/// Inertial movement of swipes with a given finger count
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum FingerMode {
+    /// Inertia on the outputs selected with --inertia-as
+    Pointer,
+    /// Scroll inertia along both axes
+    Scroll,
+    /// Vertical scroll inertia
+    ScrollV,
+    /// Horizontal scroll inertia
+    ScrollH,
+    /// No inertia
+    Ignore,
+    /// A single run of --fling-command instead of inertia
+    Command,
+}
+
+impl FingerMode {
+    /// Output routing replacing the configured one, if any
+    fn routing(self) -> Option<Routing> {
+        match self {
+            FingerMode::Scroll | FingerMode::ScrollV | FingerMode::ScrollH => Some(Routing {
+                x: InertiaSink::Scroll,
+                y: InertiaSink::Scroll,
+            }),
+            FingerMode::Pointer | FingerMode::Ignore | FingerMode::Command => None,
+        }
+    }
+
+    /// Keeps the velocity components the mode moves along
+    fn filter(self, vx: f64, vy: f64) -> (f64, f64) {
+        match self {
+            FingerMode::ScrollV => (0.0, vy),
+            FingerMode::ScrollH => (vx, 0.0),
+            FingerMode::Pointer | FingerMode::Scroll | FingerMode::Ignore | FingerMode::Command => {
+                (vx, vy)
+            }
+        }
+    }
+}
+
+/// Most fingers touchpads report a count for (BTN_TOOL_QUINTTAP)
+const MAX_FINGERS: usize = 5;
+
+/// Inertia mode per finger count, starting with one finger
+type FingerModes = [FingerMode; MAX_FINGERS];
+
+/// A `--finger-modes` entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FingerModeEntry {
+    fingers: u8,
+    mode: FingerMode,
+}
+
+impl std::str::FromStr for FingerModeEntry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (fingers, mode) = s.split_once('=').ok_or("expected COUNT=MODE")?;
+        let fingers: u8 = fingers
+            .trim()
+            .parse()
+            .map_err(|e| format!("invalid finger count: {}", e))?;
+        if !(1..=MAX_FINGERS as u8).contains(&fingers) {
+            return Err(format!(
+                "finger count must be between 1 and {}",
+                MAX_FINGERS
+            ));
+        }
+        let mode = FingerMode::from_str(mode.trim(), true)?;
+        Ok(Self { fingers, mode })
+    }
+}
+
+/// Drag multiplier of each finger count
+type FingerDrag = [f64; MAX_FINGERS];
+
+/// A value of `--drag` or `--speed-factor`, for both axes or as X,Y
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct AxisValues {
+    x: f64,
+    /// Vertical value, if it differs from the horizontal one
+    y: Option<f64>,
+}
+
+impl std::str::FromStr for AxisValues {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|e| format!("invalid number: {}", e))
+        };
+        match s.split_once(',') {
+            Some((x, y)) => Ok(Self {
+                x: parse(x)?,
+                y: Some(parse(y)?),
+            }),
+            None => Ok(Self {
+                x: parse(s)?,
+                y: None,
+            }),
+        }
+    }
+}
+
+/// A `--finger-drag` entry
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FingerDragEntry {
+    fingers: u8,
+    multiplier: f64,
+}
+
+impl std::str::FromStr for FingerDragEntry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (fingers, multiplier) = s.split_once(':').ok_or("expected COUNT:MULTIPLIER")?;
+        let fingers: u8 = fingers
+            .trim()
+            .parse()
+            .map_err(|e| format!("invalid finger count: {}", e))?;
+        if !(1..=MAX_FINGERS as u8).contains(&fingers) {
+            return Err(format!(
+                "finger count must be between 1 and {}",
+                MAX_FINGERS
+            ));
+        }
+        let multiplier = parse_positive(multiplier.trim())?;
+        Ok(Self {
+            fingers,
+            multiplier,
+        })
+    }
+}
+
+/// Handle for injecting movement into the emulation, bypassing the touchpad
+#[derive(Debug, Clone)]
+struct EmulationHandle {
+    sender: mpsc::Sender<MomentumMessage>,
+}
+
+impl EmulationHandle {
+    /// Launches a glide as if the finger lifted off with the given velocity,
+    /// in raw touchpad units per second
+    ///
+    /// The velocity goes through the same response curve and decay as
+    /// a real flick, but isn't compared against the speed threshold.
+    fn flick(&self, vx: f64, vy: f64) -> Result<()> {
+        if !vx.is_finite() || !vy.is_finite() {
+            anyhow::bail!("velocity must be finite, got ({}, {})", vx, vy);
+        }
+        self.sender
+            .send(MomentumMessage::StartMovement {
+                vx,
+                vy,
+                interval: None,
+                routing: None,
+                chained: false,
+                fingers: 0,
+            })
+            .map_err(|_| anyhow::anyhow!("emulation has terminated"))
+    }
+
+    /// Stops any inertial movement immediately
+    fn stop(&self) {
+        let _ = self.sender.send(MomentumMessage::StopMovement);
+    }
+}
+
+/// Emulates inertial movement on a virtual mouse
+struct Emulator {
+    mouse: VirtualMouse,
+    /// Configured routing, which a glide's finger mode may replace
+    routing: Routing,
+}
+
+impl Emulator {
+    fn new(mouse: VirtualMouse) -> Self {
+        Self {
+            routing: mouse.routing(),
+            mouse,
+        }
+    }
+
+    /// Handles a `KeepAwake` message between glides, logging the outcome
+    fn nudge(&mut self) {
+        match self.mouse.keep_awake() {
+            Ok(true) => log::trace!("Emulation: keep-awake nudge"),
+            Ok(false) => log::debug!("Emulation: no pointer axis to keep awake with"),
+            Err(e) => log::warn!("Failed to emit keep-awake nudge: {}", e),
+        }
+    }
+
+    /// Rotates a velocity to the nearest multiple of 45°, keeping its magnitude
+    fn snap_to_octant(x: f64, y: f64) -> (f64, f64) {
+        let step = std::f64::consts::FRAC_PI_4;
+        let angle = (y.atan2(x) / step).round() * step;
+        let speed = x.hypot(y);
+        (speed * angle.cos(), speed * angle.sin())
+    }
+
+    /// Shrinks pointer deltas so that after the compositor's pointer
+    /// acceleration they travel the intended distance
+    fn compensate_accel(
+        &self,
+        options: &EmulationOptions,
+        dx: f64,
+        dy: f64,
+        period: time::Duration,
+    ) -> (f64, f64) {
+        let pointer = |sink, value| {
+            if sink == InertiaSink::Pointer {
+                value
+            } else {
+                0.0
+            }
+        };
+        let distance =
+            pointer(self.mouse.routing().x, dx).hypot(pointer(self.mouse.routing().y, dy));
+        if distance == 0.0 {
+            return (dx, dy);
+        }
+        let compensated = match &options.accel_curve {
+            Some(curve) => curve.compensated(distance, period),
+            None => options
+                .accel_profile
+                .compensated(distance, period, options.accel_speed),
+        };
+        let scale = compensated / distance;
+        let compensate = |sink, value| {
+            if sink == InertiaSink::Pointer {
+                value * scale
+            } else {
+                value
+            }
+        };
+        (
+            compensate(self.mouse.routing().x, dx),
+            compensate(self.mouse.routing().y, dy),
+        )
+    }
+
+    /// Shortest frame period allowed, to keep the emulation loop from spinning hot
+    const MIN_FRAME_PERIOD: time::Duration = time::Duration::from_millis(2);
+
+    /// Drag applied once a finger grabs the glide with `--grab-glide`
+    const GRAB_DRAG: f64 = 0.35;
+
+    /// Counter-motion launch velocity with `--resistance`, relative to the flick
+    const RESISTANCE_SCALE: f64 = 0.1;
+
+    /// Minimum drag with `--resistance`, keeping the counter-motion brief
+    const RESISTANCE_DRAG: f64 = 0.3;
+
+    /// Speed left at the end of a soft catch, as a fraction of the speed
+    /// at touch-down
+    const SOFT_CATCH_RESIDUAL: f64 = 0.01;
+
+    /// Glide tail where `settle_damp` applies, as a fraction of the launch speed
+    const SETTLE_TAIL_FRACTION: f64 = 0.1;
+
+    /// Most frames of movement `--predict-latency-ms` adds to the first
+    /// frame, so that a bad estimate can't make it jump
+    const MAX_PREDICTED_FRAMES: f64 = 3.0;
+
+    /// Upper bound of frames emitted in a single glide, as a safety net
+    /// against glides that never decelerate (e.g. zero drag)
+    const MAX_GLIDE_FRAMES: u64 = 30_000;
+
+    /// Frame limit for a single glide: twice the frames `max_glide` allows
+    /// if it's set, capped by `MAX_GLIDE_FRAMES`
+    fn glide_frame_limit(period: time::Duration, max_glide: time::Duration) -> u64 {
+        if max_glide.is_zero() {
+            return Self::MAX_GLIDE_FRAMES;
+        }
+        let frames = (max_glide.as_secs_f64() / period.as_secs_f64()).ceil() as u64;
+        frames.saturating_mul(2).min(Self::MAX_GLIDE_FRAMES)
+    }
+
+    /// Computes the frame period for the requested refresh rate,
+    /// clamping it to `MIN_FRAME_PERIOD`
+    fn frame_period(refresh_rate: f64) -> time::Duration {
+        if !(refresh_rate.is_finite() && refresh_rate > 0.0) {
+            log::warn!("Invalid refresh rate {}, using 60 Hz", refresh_rate);
+            return time::Duration::from_secs_f64(60f64.recip());
+        }
+        let period = time::Duration::from_secs_f64(refresh_rate.recip());
+        if period < Self::MIN_FRAME_PERIOD {
+            log::warn!(
+                "Refresh rate {} Hz is too high, clamping to {} Hz",
+                refresh_rate,
+                Self::MIN_FRAME_PERIOD.as_secs_f64().recip()
+            );
+            return Self::MIN_FRAME_PERIOD;
+        }
+        period
+    }
+
+    /// Leaves the virtual mouse with an empty report after dropping the
+    /// movement in flight, so that no motion is left half-delivered
+    fn shut_down(&mut self, done: mpsc::Sender<()>) {
+        log::debug!("Emulation: shutting down");
+        self.mouse.set_stamp(None);
+        if let Err(e) = self.mouse.emit_relative(&[]) {
+            log::warn!("Failed to emit the final report: {}", e);
+        }
+        // The process exits once answered, staying idle until then
+        let _ = done.send(());
+    }
+
+    /// Reports non-finite glide values, which are dropped, and with
+    /// `--strict` enters safe mode
+    fn contain_non_finite(
+        options: &EmulationOptions,
+        control: &ControlState,
+        what: &str,
+        values: (f64, f64),
+    ) {
+        log::error!(
+            "Emulation: non-finite {} ({}, {}), stopping inertial movement{}",
+            what,
+            values.0,
+            values.1,
+            if options.strict {
+                " and ignoring flicks until `resume`"
+            } else {
+                ""
+            }
+        );
+        if options.strict {
+            control.safe_mode.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Stops the glide and drops the movement left in flight, firing the
+    /// glide-ended hooks if it decelerated to a stop on its own
+    fn end_glide(
+        &mut self,
+        glide: &mut Glide,
+        end: GlideEnd,
+        options: &EmulationOptions,
+        control: &ControlState,
+    ) {
+        let velocity = glide.inertia.velocity;
+        (glide.blended, glide.interrupted) = match end {
+            GlideEnd::Superseded => (
+                (
+                    velocity.0 * options.blend_flicks,
+                    velocity.1 * options.blend_flicks,
+                ),
+                velocity,
+            ),
+            GlideEnd::Interrupted => ((0.0, 0.0), velocity),
+            GlideEnd::Decelerated | GlideEnd::Cut => ((0.0, 0.0), (0.0, 0.0)),
+        };
+        if end == GlideEnd::Decelerated {
+            control.end_glide();
+        }
+        glide.moving = false;
+        glide.inertia.stop();
+        glide.pending = (0, 0);
+        glide.carry = (0.0, 0.0);
+        self.mouse.reset_wheel_remainder();
+        control.metrics.set_speed(0.0);
+    }
+
+    fn run_emulation(
+        &mut self,
+        receiver: mpsc::Receiver<MomentumMessage>,
+        params: Arc<LiveParams>,
+        options: &EmulationOptions,
+        control: &ControlState,
+        precision: &PrecisionState,
+    ) {
+        let base_period = Self::frame_period(options.refresh_rate);
+        log::info!("Refresh rate: {:.1} Hz", base_period.as_secs_f64().recip());
+        let mut period = base_period;
+        let max_glide = options.max_glide;
+        let rounding = options.rounding;
+        let mut frame_limit = Self::glide_frame_limit(period, max_glide);
+        let mut glide_start = time::Instant::now();
+        // Deadline of the next frame with `pace_output`
+        let mut next_frame = glide_start;
+        let mut glide_frames = 0u64;
+        let mut launch_speed = 0f64;
+        let mut grabbed = false;
+        // End of the soft catch in progress, if any
+        let mut catch_end: Option<time::Instant> = None;
+        // Message received while moving that has to be handled once stopped
+        let mut next_message = None;
+        let mut glide = Glide::default();
+        // Frames of movement the next frame adds for the predicted latency
+        let mut lead = 0f64;
+        // Drag multiplier for the finger count of the launching swipe
+        let mut drag_scale = 1f64;
+        // Scroll distance of the glide so far, for `--scroll-overscroll-distance`
+        let mut scrolled = 0f64;
+        let mut scroll_accel = options.scroll_accel.map(ScrollAccel::new);
+        let mut runaway_guard = options.safe_mode_threshold.map(RunawayGuard::new);
+        let min_emit_px = options.min_emit_px as i32;
+        let mut last_emit = time::Instant::now();
+        // Seed for stochastic rounding, xorshift requires it to be non-zero
+        let mut rng = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .map_or(1, |t| t.as_nanos() as u64)
+            | 1;
+
+        loop {
+            if glide.moving {
+                let timeout = if options.pace_output {
+                    next_frame.saturating_duration_since(time::Instant::now())
+                } else {
+                    period
+                };
+                let message = receiver.recv_timeout(timeout).ok();
+                if let Some(MomentumMessage::KeepAwake) = message {
+                    // The glide keeps the screen awake by itself
+                    continue;
+                }
+                if let Some(MomentumMessage::FingerGrab) = message {
+                    log::debug!("Emulation: movement grabbed");
+                    grabbed = true;
+                    continue;
+                }
+                if let Some(MomentumMessage::Shutdown(done)) = message {
+                    self.end_glide(&mut glide, GlideEnd::Cut, options, control);
+                    self.shut_down(done);
+                    continue;
+                }
+                if let Some(MomentumMessage::SoftCatch) = message {
+                    if catch_end.is_none() {
+                        log::debug!("Emulation: movement caught");
+                        catch_end = Some(time::Instant::now() + options.soft_catch);
+                    }
+                    continue;
+                }
+                if let Some(MomentumMessage::StopMovement) = message {
+                    log::debug!("Emulation: stop movement");
+                    self.end_glide(&mut glide, GlideEnd::Interrupted, options, control);
+                } else if let Some(message @ MomentumMessage::StartMovement { .. }) = message {
+                    // A new flick out of a grabbed glide, restart with its velocity
+                    // and what `--blend-flicks` keeps of the current one
+                    self.end_glide(&mut glide, GlideEnd::Superseded, options, control);
+                    next_message = Some(message);
+                } else if catch_end.is_some_and(|end| time::Instant::now() >= end) {
+                    log::debug!("Emulation: soft catch finished");
+                    self.end_glide(&mut glide, GlideEnd::Cut, options, control);
+                } else if !max_glide.is_zero() && glide_start.elapsed() >= max_glide {
+                    log::debug!(
+                        "Emulation: movement cut short by max glide duration {:?}",
+                        max_glide
+                    );
+                    self.end_glide(&mut glide, GlideEnd::Cut, options, control);
+                } else if glide_frames >= frame_limit {
+                    log::warn!(
+                        "Emulation: movement force-stopped after {} frames, check the drag parameter",
+                        glide_frames
+                    );
+                    self.end_glide(&mut glide, GlideEnd::Cut, options, control);
+                } else {
+                    glide_frames += 1;
+                    if let Some(stamp) = self.mouse.stamp() {
+                        // Evenly spaced, but never ahead of the actual time
+                        let stamp = (stamp + period).min(time::SystemTime::now());
+                        self.mouse.set_stamp(Some(stamp));
+                    }
+                    // Keep a steady cadence, but don't burst to catch up after a stall
+                    next_frame = (next_frame + period).max(time::Instant::now());
+                    let current = params.load();
+                    let speed = glide.inertia.speed();
+                    let in_tail = speed < launch_speed * Self::SETTLE_TAIL_FRACTION;
+                    let progress = if launch_speed > 0.0 {
+                        1.0 - speed / launch_speed
+                    } else {
+                        0.0
+                    };
+                    // Drag leaving the residual speed once the catch time is up
+                    let catch_drag = catch_end.map(|_| {
+                        let frames = options.soft_catch.as_secs_f64() / period.as_secs_f64();
+                        1.0 - Self::SOFT_CATCH_RESIDUAL.powf(1.0 / frames.max(1.0))
+                    });
+                    // Each axis uses the parameters of the sink it's routed to
+                    let axis = |sink: InertiaSink, vertical| {
+                        let kind = sink.into();
+                        // The decay models other than exponential replace the glide drag
+                        let mut drag = match options.decay {
+                            None => {
+                                precision.apply(current.glide_drag(kind, vertical, progress))
+                                    * drag_scale
+                            }
+                            Some(_) => 0.0,
+                        };
+                        if grabbed {
+                            drag = drag.max(Self::GRAB_DRAG);
+                        }
+                        if options.resistance {
+                            drag = drag.max(Self::RESISTANCE_DRAG);
+                        }
+                        if let Some(catch_drag) = catch_drag {
+                            drag = drag.max(catch_drag);
+                        }
+                        if let Some((distance, damping)) = options.overscroll {
+                            if kind == GestureKind::Scroll && scrolled > distance {
+                                let overshoot = (scrolled - distance) / distance;
+                                drag += damping * overshoot.min(1.0);
+                            }
+                        }
+                        let mut deceleration_factor = 1.0 - drag.clamp(0.0, 1.0);
+                        if in_tail {
+                            deceleration_factor *= 1.0 - current.settle_damp.clamp(0.0, 1.0);
+                        }
+                        (
+                            deceleration_factor,
+                            current.axis_speed_factor(kind, vertical),
+                        )
+                    };
+                    let (deceleration_x, speed_factor_x) = axis(self.mouse.routing().x, false);
+                    let (deceleration_y, speed_factor_y) = axis(self.mouse.routing().y, true);
+                    let (dx, dy) = glide.inertia.frame_delta((speed_factor_x, speed_factor_y));
+                    if options.overscroll.is_some() {
+                        let scroll = |sink, delta: f64| match sink {
+                            InertiaSink::Scroll => delta,
+                            _ => 0.0,
+                        };
+                        scrolled += scroll(self.mouse.routing().x, dx)
+                            .hypot(scroll(self.mouse.routing().y, dy));
+                    }
+                    let parked = options.park_speed.is_some_and(|park| speed < park);
+                    if parked {
+                        log::debug!("Emulation: parked at {:.0} units/s", speed);
+                    }
+                    if parked
+                        || rounding.is_negligible(dx)
+                            && rounding.is_negligible(dy)
+                            && glide.carry == (0.0, 0.0)
+                    {
+                        if options.flush_remainder && !parked {
+                            // Frames after this one would have emitted a
+                            // geometric series of its delta
+                            let tail = |delta: f64, deceleration: f64| {
+                                if deceleration < 1.0 {
+                                    delta / (1.0 - deceleration)
+                                } else {
+                                    0.0
+                                }
+                            };
+                            let (tail_x, tail_y) = self.compensate_accel(
+                                options,
+                                tail(dx, deceleration_x),
+                                tail(dy, deceleration_y),
+                                period,
+                            );
+                            let residual = glide.inertia.remainder();
+                            let x = (residual.0 + tail_x).round() as i32;
+                            let y = (residual.1 + tail_y).round() as i32;
+                            if x != 0 || y != 0 {
+                                log::debug!("Emulation: flushing remainder ({}, {})", x, y);
+                                glide.pending = (glide.pending.0 + x, glide.pending.1 + y);
+                            }
+                        }
+                        if glide.pending.0 != 0 || glide.pending.1 != 0 {
+                            log::trace!(
+                                "Emulation: relative position = ({}, {})",
+                                glide.pending.0,
+                                glide.pending.1
+                            );
+                            self.mouse
+                                .emit_motion(glide.pending.0, glide.pending.1)
+                                .unwrap();
+                            control.add_glide_distance(glide.pending.0, glide.pending.1);
+                        }
+                        self.end_glide(&mut glide, GlideEnd::Decelerated, options, control);
+                    } else {
+                        glide.inertia.decelerate((deceleration_x, deceleration_y));
+                        if let Some((model, deceleration)) = options.decay {
+                            glide.inertia.brake(model.speed_loss(
+                                deceleration * drag_scale,
+                                glide.inertia.speed(),
+                                launch_speed,
+                                period.as_secs_f64(),
+                            ));
+                        }
+                        let (dx, dy) = self.compensate_accel(options, dx, dy, period);
+                        // The lead continues at the speed of the first frame
+                        let (dx, dy) = (dx * (1.0 + lead), dy * (1.0 + lead));
+                        lead = 0.0;
+                        let (dx, dy) = match options.max_frame_delta {
+                            Some(cap) => {
+                                // Defer what exceeds the cap to the next frames
+                                let (x, y) = (dx + glide.carry.0, dy + glide.carry.1);
+                                let scale = (cap / x.hypot(y)).min(1.0);
+                                glide.carry = (x - x * scale, y - y * scale);
+                                (x * scale, y * scale)
+                            }
+                            None => (dx, dy),
+                        };
+                        // A math bug would otherwise glide on for the longest
+                        // glide, or emit garbage once cast to integers
+                        let non_finite =
+                            [(glide.inertia.velocity, "velocity"), ((dx, dy), "movement")]
+                                .into_iter()
+                                .find(|&((x, y), _)| !(x.is_finite() && y.is_finite()));
+                        if let Some((values, what)) = non_finite {
+                            Self::contain_non_finite(options, control, what, values);
+                            self.end_glide(&mut glide, GlideEnd::Cut, options, control);
+                            continue;
+                        }
+                        // Accumulating emits what earlier frames left out
+                        // once it adds up to whole units
+                        let (x, y) = glide.inertia.round(
+                            (dx, dy),
+                            rounding == Rounding::Accumulate,
+                            |value| rounding.apply(value, &mut rng),
+                        );
+                        control.publish(|| {
+                            format!("frame speed={:.0} dx={} dy={}", glide.inertia.speed(), x, y)
+                        });
+                        glide.pending = (glide.pending.0 + x, glide.pending.1 + y);
+                        // Throttled movement stays pending until the next allowed event
+                        let throttled = options
+                            .min_emit_interval
+                            .is_some_and(|interval| last_emit.elapsed() < interval);
+                        let flush = options.glide_keepalive
+                            && (glide.pending.0 != 0 || glide.pending.1 != 0);
+                        if !throttled
+                            && (flush
+                                || glide.pending.0.abs().max(glide.pending.1.abs()) >= min_emit_px)
+                        {
+                            last_emit = time::Instant::now();
+                            log::trace!(
+                                "Emulation: relative position = ({}, {})",
+                                glide.pending.0,
+                                glide.pending.1
+                            );
+                            self.mouse
+                                .emit_motion(glide.pending.0, glide.pending.1)
+                                .unwrap();
+                            control.add_glide_distance(glide.pending.0, glide.pending.1);
+                            let runaway = runaway_guard.as_mut().is_some_and(|guard| {
+                                guard.emitted(glide.pending.0, glide.pending.1)
+                            });
+                            glide.pending = (0, 0);
+                            if runaway {
+                                log::error!(
+                                    "Emulation: emitted over {} within {:?} without a flick, stopping inertial movement{}",
+                                    options.safe_mode_threshold.unwrap_or_default(),
+                                    RunawayGuard::WINDOW,
+                                    if options.safe_mode_pause {
+                                        " and ignoring flicks until `resume`"
+                                    } else {
+                                        ""
+                                    }
+                                );
+                                if options.safe_mode_pause {
+                                    control.safe_mode.store(true, Ordering::Relaxed);
+                                }
+                                self.end_glide(&mut glide, GlideEnd::Cut, options, control);
+                            }
+                        } else if !throttled && options.glide_keepalive {
+                            log::trace!("Emulation: keepalive");
+                            last_emit = time::Instant::now();
+                            self.mouse.emit_relative(&[]).unwrap();
+                        }
+                    }
+                }
+                control.metrics.set_speed(if glide.moving {
+                    glide.inertia.speed()
+                } else {
+                    0.0
+                });
+            } else {
+                let message = match next_message.take() {
+                    Some(message) => message,
+                    None => {
+                        let Ok(message) = receiver.recv() else {
+                            log::error!("Emulation: touchpad capture has terminated");
+                            break;
+                        };
+                        message
+                    }
+                };
+                if let MomentumMessage::Shutdown(done) = message {
+                    self.shut_down(done);
+                    continue;
+                }
+                if let MomentumMessage::KeepAwake = message {
+                    self.nudge();
+                    continue;
+                }
+                let MomentumMessage::StartMovement {
+                    vx: x,
+                    vy: y,
+                    interval,
+                    routing,
+                    chained,
+                    fingers,
+                } = message
+                else {
+                    // Anything but a launch ends the interrupted glide for good
+                    glide.blended = (0.0, 0.0);
+                    continue;
+                };
+                if control.safe_mode.load(Ordering::Relaxed) {
+                    log::debug!("Emulation: in safe mode, flick ignored");
+                    continue;
+                }
+                if !control.enabled.load(Ordering::Relaxed) {
+                    log::debug!("Emulation: inertia disabled, flick ignored");
+                    continue;
+                }
+                if !options.launch_delay.is_zero() {
+                    // A touch-down within the delay means the user keeps control,
+                    // while keep-awake nudges just pass through
+                    let deadline = time::Instant::now() + options.launch_delay;
+                    let mut cancelled = false;
+                    while let Ok(message) = receiver
+                        .recv_timeout(deadline.saturating_duration_since(time::Instant::now()))
+                    {
+                        if let MomentumMessage::KeepAwake = message {
+                            self.nudge();
+                            continue;
+                        }
+                        log::debug!("Emulation: launch cancelled within the launch delay");
+                        next_message = Some(message);
+                        cancelled = true;
+                        break;
+                    }
+                    if cancelled {
+                        continue;
+                    }
+                }
+                period = match interval {
+                    Some(interval) if options.match_input_rate => {
+                        log::debug!("Emulation: matching input report interval {:?}", interval);
+                        interval.max(Self::MIN_FRAME_PERIOD)
+                    }
+                    _ => base_period,
+                };
+                frame_limit = Self::glide_frame_limit(period, max_glide);
+                self.mouse.set_routing(routing.unwrap_or(self.routing));
+                let kind = routing.map_or(options.kind, |routing| routing.y.into());
+                let (mut x, mut y) = params.load().launch_velocity(kind, x, y);
+                if chained {
+                    log::debug!("Emulation: flick chained onto the interrupted glide");
+                    glide.blended = glide.interrupted;
+                }
+                glide.interrupted = (0.0, 0.0);
+                let scroll = self.mouse.routing().x == InertiaSink::Scroll
+                    || self.mouse.routing().y == InertiaSink::Scroll;
+                let reversal = x * glide.blended.0 + y * glide.blended.1 < 0.0;
+                if scroll && reversal && !options.blend_scroll_reversals {
+                    log::debug!("Emulation: scroll reversed, dropping the glide velocity");
+                    glide.blended = (0.0, 0.0);
+                }
+                if let Some(accel) = scroll_accel.as_mut().filter(|_| scroll) {
+                    let boost = accel.flick(time::Instant::now());
+                    if boost > 1.0 {
+                        log::debug!("Emulation: scroll flick boosted {:.2}x", boost);
+                        (x, y) = (x * boost, y * boost);
+                    }
+                }
+                if glide.blended != (0.0, 0.0) {
+                    log::debug!(
+                        "Emulation: blending with the glide velocity ({:.02}, {:.02})",
+                        glide.blended.0,
+                        glide.blended.1
+                    );
+                    (x, y) = (x + glide.blended.0, y + glide.blended.1);
+                    glide.blended = (0.0, 0.0);
+                }
+                if let Some(cap) = options.chain_max_speed.filter(|_| chained) {
+                    let speed = x.hypot(y);
+                    if speed > cap {
+                        log::debug!("Emulation: chained flick capped at {:.0} units/s", cap);
+                        (x, y) = (x * cap / speed, y * cap / speed);
+                    }
+                }
+                if options.snap_octants {
+                    (x, y) = Self::snap_to_octant(x, y);
+                }
+                if options.resistance {
+                    (x, y) = (-x * Self::RESISTANCE_SCALE, -y * Self::RESISTANCE_SCALE);
+                }
+                if !(x.is_finite() && y.is_finite()) {
+                    Self::contain_non_finite(options, control, "launch velocity", (x, y));
+                    continue;
+                }
+                log::debug!(
+                    "Emulation: start movement, velocity = ({:.02}, {:.02})",
+                    x,
+                    y
+                );
+                glide.moving = true;
+                scrolled = 0.0;
+                if let Some(guard) = &mut runaway_guard {
+                    guard.launched();
+                }
+                lead = (options.predict_latency.as_secs_f64() / period.as_secs_f64())
+                    .min(Self::MAX_PREDICTED_FRAMES);
+                drag_scale = options
+                    .finger_drag
+                    .zip(fingers.checked_sub(1))
+                    .map_or(1.0, |(table, index)| {
+                        table[usize::from(index).min(MAX_FINGERS - 1)]
+                    });
+                glide_start = time::Instant::now();
+                if options.stamp_events {
+                    self.mouse.set_stamp(Some(time::SystemTime::now()));
+                }
+                next_frame = glide_start + period;
+                glide_frames = 0;
+                grabbed = false;
+                catch_end = None;
+                launch_speed = x.hypot(y);
+                control.start_glide(x, y);
+                glide.inertia = InertiaState::new(x, y);
+            }
+        }
+    }
+}
+
+/// State of the glide in progress, reset as a whole by
+/// [`Emulator::end_glide`]
+#[derive(Default)]
+struct Glide {
+    moving: bool,
+    inertia: InertiaState,
+    /// Velocity of the interrupted glide added to the next launch
+    blended: (f64, f64),
+    /// Velocity of the last glide stopped by a touch or a new flick, for a
+    /// chained flick to add in full
+    interrupted: (f64, f64),
+    /// Movement held back by `--min-emit-px`, in output units
+    pending: (i32, i32),
+    /// Movement deferred by `max_frame_delta`, in output units
+    carry: (f64, f64),
+}
+
+/// Why a glide ended, which decides what carries over to the next one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GlideEnd {
+    /// Decelerated to a stop on its own
+    Decelerated,
+    /// Stopped by a touch, a chained flick may pick its velocity up
+    Interrupted,
+    /// Replaced by a new flick, which blends in its velocity
+    Superseded,
+    /// Cut short by a limit, an error or shutdown
+    Cut,
+}
+
+/// Launch multiplier of `--scroll-accel`, escalating with rapid scroll flicks
+struct ScrollAccel {
+    cap: f64,
+    boost: f64,
+    last_flick: Option<time::Instant>,
+}
+
+impl ScrollAccel {
+    /// Longest pause between two flicks for the boost to escalate
+    const WINDOW: time::Duration = time::Duration::from_millis(800);
+
+    /// Boost increase per rapid flick
+    const STEP: f64 = 1.5;
+
+    /// Time for the boost beyond 1 to wear off by half
+    const HALF_LIFE: time::Duration = time::Duration::from_millis(400);
+
+    fn new(cap: f64) -> Self {
+        Self {
+            cap,
+            boost: 1.0,
+            last_flick: None,
+        }
+    }
+
+    /// Returns the multiplier of a scroll flick launched at `now`
+    fn flick(&mut self, now: time::Instant) -> f64 {
+        let pause = self.last_flick.map(|last| now - last);
+        self.last_flick = Some(now);
+        self.boost = match pause {
+            Some(pause) if pause < Self::WINDOW => {
+                let decay = 0.5f64.powf(pause.as_secs_f64() / Self::HALF_LIFE.as_secs_f64());
+                ((1.0 + (self.boost - 1.0) * decay) * Self::STEP).min(self.cap)
+            }
+            _ => 1.0,
+        };
+        self.boost
+    }
+}
+
+/// Emitted distance watch of `--safe-mode-threshold`
+struct RunawayGuard {
+    threshold: f64,
+    window_start: time::Instant,
+    /// Distance emitted since `window_start`
+    distance: f64,
+}
+
+impl RunawayGuard {
+    /// Time over which emitted distance is summed
+    const WINDOW: time::Duration = time::Duration::from_secs(1);
+
+    fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            window_start: time::Instant::now(),
+            distance: 0.0,
+        }
+    }
+
+    /// Starts over for a newly launched glide, which accounts for its motion
+    fn launched(&mut self) {
+        self.window_start = time::Instant::now();
+        self.distance = 0.0;
+    }
+
+    /// Adds an emitted movement, returns whether the distance of the
+    /// window has exceeded the threshold
+    fn emitted(&mut self, x: i32, y: i32) -> bool {
+        if self.window_start.elapsed() >= Self::WINDOW {
+            self.window_start = time::Instant::now();
+            self.distance = 0.0;
+        }
+        self.distance += f64::from(x).hypot(f64::from(y));
+        self.distance > self.threshold
+    }
+}
+
+/// Virtual mouse emulation options that stay fixed while running
+struct EmulationOptions {
+    refresh_rate: f64,
+    min_emit_px: u32,
+    /// Shortest time between two emitted events of a glide
+    min_emit_interval: Option<time::Duration>,
+    /// Zero means unlimited
+    max_glide: time::Duration,
+    rounding: Rounding,
+    /// Decay model and its deceleration, `None` for exponential decay
+    decay: Option<(DecayModel, f64)>,
+    /// Emit the rounding error and the tail of a glide as it ends
+    flush_remainder: bool,
+    /// Use the swipe's input report interval as the frame period
+    match_input_rate: bool,
+    /// Schedule frames by deadline instead of waiting a period after each
+    pace_output: bool,
+    /// Whether glide events carry their frame times
+    stamp_events: bool,
+    /// Emit a report every frame of a glide, even without whole-pixel motion
+    glide_keepalive: bool,
+    /// Nudge back against the flick instead of continuing it
+    resistance: bool,
+    /// Launch in the nearest horizontal, vertical or diagonal direction
+    snap_octants: bool,
+    /// Share of a running glide's velocity kept by a flick launched during it
+    blend_flicks: f64,
+    /// Cap on the launch speed of chained flicks
+    chain_max_speed: Option<f64>,
+    /// Whether scroll flicks reversing the glide are blended too
+    blend_scroll_reversals: bool,
+    /// Largest launch multiplier of rapid scroll flicks
+    scroll_accel: Option<f64>,
+    /// Scroll distance after which the glide is damped, and the extra drag
+    /// reached after overshooting it by as much again
+    overscroll: Option<(f64, f64)>,
+    /// Speed below which the glide ends without emitting the rest
+    park_speed: Option<f64>,
+    /// Time to wait after lift-off before the glide starts
+    launch_delay: time::Duration,
+    /// Lift-off to first frame latency the first frame catches up on
+    predict_latency: time::Duration,
+    /// Drag multiplier for each finger count of the launching swipe
+    finger_drag: Option<FingerDrag>,
+    /// Time a touch-down takes to stop the glide, zero stops instantly
+    soft_catch: time::Duration,
+    /// Largest movement emitted in a single frame, in output units
+    max_frame_delta: Option<f64>,
+    /// Most movement emitted within `RunawayGuard::WINDOW` without a launch
+    safe_mode_threshold: Option<f64>,
+    /// Keep ignoring launches once the threshold is exceeded
+    safe_mode_pause: bool,
+    /// Keep ignoring launches once the glide math produced a non-finite value
+    strict: bool,
+    /// Compositor pointer acceleration to pre-compensate
+    accel_profile: AccelProfile,
+    accel_speed: f64,
+    /// Measured acceleration curve, overriding the profile
+    accel_curve: Option<AccelCurve>,
+    /// Kind whose threshold shapes the launch velocity
+    kind: GestureKind,
+}
+
+/// Touchpad capture options that stay fixed while running
+struct CaptureOptions {
+    /// Cooldowns of pointer flicks
+    cooldowns: Cooldowns,
+    /// Cooldowns of scroll flicks
+    scroll_cooldowns: Cooldowns,
+    /// Final part of the multitouch cooldown weakening flicks instead
+    cooldown_taper: time::Duration,
+    /// Time after a button press weakening flicks to their settle tail
+    click_settle: time::Duration,
+    strict_post_glide_cooldown: bool,
+    /// Time after an armed flick to chain a co-directional one onto it
+    chain_window: Option<time::Duration>,
+    adaptive_threshold: Option<f64>,
+    axes: Axes,
+    /// Clockwise rotation of the lift-off velocity
+    rotate_deg: f64,
+    watchdog: Option<time::Duration>,
+    /// Idle time after which the touchpad is closed until used again
+    idle_release: Option<time::Duration>,
+    stop_on: Vec<StopKey>,
+    debug_gestures: bool,
+    /// User overrides of the device-reported X and Y axis ranges
+    abs_range: (Option<AbsRange>, Option<AbsRange>),
+    /// Kind of inertial movement triggered by lift-off
+    kind: GestureKind,
+    /// Pressure at or above which the finger counts as in contact
+    pressure_contact_threshold: Option<i32>,
+    /// Finger contact decays inertial movement instead of stopping it
+    grab_glide: bool,
+    /// Finger contact stops inertial movement over a short time
+    soft_catch: bool,
+    /// Position deltas discarded after each touch-down
+    skip_first_samples: u32,
+    /// Weight of the newest velocity sample per `SMOOTHING_INTERVAL`
+    report_smoothing: Option<f64>,
+    /// Position deltas averaged into the swipe velocity
+    velocity_samples: usize,
+    /// Lift-off speed launches are capped to
+    max_speed: Option<f64>,
+    /// Most fingers allowed during a swipe arming pointer inertia
+    pointer_max_fingers: u8,
+    /// Hold the device through a read-only descriptor
+    read_only: bool,
+    read_strategy: ReadStrategy,
+    /// Inertia mode per finger count, replacing `pointer_max_fingers`
+    finger_modes: Option<FingerModes>,
+    /// Command of the `command` finger mode
+    fling_command: Option<String>,
+    /// Fraction of the touchpad size along each side where swipes don't arm
+    edge_margin: f64,
+    /// Shortest contact that arms
+    min_contact: time::Duration,
+}
+
+/// Times swipes are ignored for after each cause of accidental flicks
+struct Cooldowns {
+    multitouch: time::Duration,
+    click: time::Duration,
+    post_glide: time::Duration,
+}
+
+impl Cooldowns {
+    fn from_millis(multitouch: u64, click: u64, post_glide: u64) -> Self {
+        Self {
+            multitouch: time::Duration::from_millis(multitouch),
+            click: time::Duration::from_millis(click),
+            post_glide: time::Duration::from_millis(post_glide),
+        }
+    }
+}
+
+impl CaptureOptions {
+    /// Cooldowns applying to flicks of the kind
+    fn cooldowns(&self, kind: GestureKind) -> &Cooldowns {
+        match kind {
+            GestureKind::Pointer => &self.cooldowns,
+            GestureKind::Scroll => &self.scroll_cooldowns,
+        }
+    }
+
+    /// Message sent when a finger touches down during inertial movement
+    fn contact_message(&self) -> MomentumMessage {
+        if self.grab_glide {
+            MomentumMessage::FingerGrab
+        } else if self.soft_catch {
+            MomentumMessage::SoftCatch
+        } else {
+            MomentumMessage::StopMovement
+        }
+    }
+
+    /// Whether a new finger contact stops inertial movement
+    fn stop_on_contact(&self) -> bool {
+        self.stop_on
+            .iter()
+            .any(|stop| matches!(stop, StopKey::Finger | StopKey::Touch))
+    }
+}
+
+/// Processes (pid, name) with the device node open, best-effort via /proc
+///
+/// Only processes readable by the current user are found.
+fn device_holders(path: &Path) -> Vec<(u32, String)> {
+    let own_pid = std::process::id();
+    let Ok(procs) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut holders = Vec::new();
+    for entry in procs.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == own_pid {
+            continue;
+        }
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let holds = fds
+            .flatten()
+            .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|target| target == path));
+        if holds {
+            let name = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            holders.push((pid, name.trim().to_string()));
+        }
+    }
+    holders
+}
+
+/// Longest gap between position samples still used to compute velocity
+const MAX_SAMPLE_GAP: time::Duration = time::Duration::from_millis(500);
+
+/// Report interval the `--report-smoothing` weight is given for
+const SMOOTHING_INTERVAL: time::Duration = time::Duration::from_millis(10);
+
+/// Fewest report intervals to trust the measured input rate
+const MIN_INTERVAL_SAMPLES: u32 = 3;
+
+/// Longest average report interval still considered a steady input rate
+const MAX_INPUT_INTERVAL: time::Duration = time::Duration::from_millis(50);
+
+/// Time after an armed flick during which the next one continues the
+/// streak: the lower speed threshold applies, and a reinforcing flick
+/// bypasses the post-glide cooldown
+const FLICK_STREAK_WINDOW: time::Duration = time::Duration::from_secs(1);
+
+/// Launch velocity factor of `--click-settle`, matching the speed where
+/// the settle tail of a glide begins
+const CLICK_SETTLE_SCALE: f64 = Emulator::SETTLE_TAIL_FRACTION;
+
+/// Number of recent lift-off speeds the adaptive threshold follows
+const ADAPTIVE_WINDOW: usize = 64;
+
+/// Lift-offs seen before the adaptive threshold replaces the fixed one
+const ADAPTIVE_MIN_SAMPLES: usize = 16;
+
+/// Speed, relative to the threshold, both flicks need to reinforce each other
+const REINFORCE_SPEED_RATIO: f64 = 1.5;
+
+/// Smallest cosine of the angle between two reinforcing flicks (about 30°)
+const REINFORCE_MIN_COSINE: f64 = 0.85;
+
+/// Outcome of a single finger lift-off
+#[derive(Clone, Copy, Debug)]
+enum LiftOff {
+    /// Inertial movement is triggered
+    Armed { speed: f64 },
+    /// Suppressed by the cooldown with the most time remaining
+    Cooldown {
+        cause: Cooldown,
+        elapsed: time::Duration,
+    },
+    /// Suppressed because the finger moved too slowly
+    BelowThreshold { speed: f64, threshold: f64 },
+    /// Suppressed because the session is idle, locked or asleep
+    SessionInactive,
+    /// Suppressed because more fingers than allowed touched during the episode
+    TooManyFingers { fingers: u8 },
+    /// Suppressed because the swipe started within the edge margin
+    EdgeSwipe,
+    /// Suppressed because the finger was down for too short
+    ShortContact { contact: time::Duration },
+    /// Suppressed because the focused application is excluded
+    AppExcluded,
+    /// Suppressed because `--suppress-modifier` is held
+    ModifierHeld,
+    /// Suppressed because a touchpad button is held, as in a drag
+    ButtonHeld,
+    /// Suppressed because inertia is disabled through the control socket
+    Disabled,
+}
+
+/// Recent event that suppresses arming for a while
+#[derive(Debug, Clone, Copy)]
+enum Cooldown {
+    Multitouch,
+    Click,
+    PostGlide,
+}
+
+impl std::fmt::Display for Cooldown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Cooldown::Multitouch => "multitouch released",
+            Cooldown::Click => "button click released",
+            Cooldown::PostGlide => "glide ended",
+        })
+    }
+}
+
+impl std::fmt::Display for LiftOff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LiftOff::Armed { speed } => write!(f, "armed, speed {:.0}", speed),
+            LiftOff::Cooldown { cause, elapsed } => {
+                write!(f, "suppressed, {} {} ms ago", cause, elapsed.as_millis())
+            }
+            LiftOff::BelowThreshold { speed, threshold } => write!(
+                f,
+                "suppressed, speed {:.0} is below threshold {:.0}",
+                speed, threshold
+            ),
+            LiftOff::SessionInactive => write!(f, "suppressed, session is inactive"),
+            LiftOff::EdgeSwipe => write!(f, "suppressed, swipe started at the edge"),
+            LiftOff::ShortContact { contact } => {
+                write!(f, "suppressed, contact lasted {} ms", contact.as_millis())
+            }
+            LiftOff::AppExcluded => write!(f, "suppressed, focused application is excluded"),
+            LiftOff::ModifierHeld => write!(f, "suppressed, suppress modifier is held"),
+            LiftOff::ButtonHeld => write!(f, "suppressed, a button is held"),
+            LiftOff::Disabled => write!(f, "suppressed, inertia is disabled"),
+            LiftOff::TooManyFingers { fingers } => {
+                write!(
+                    f,
+                    "suppressed, {} fingers touched during the swipe",
+                    fingers
+                )
+            }
+        }
+    }
+}
+
+/// Summary of a single contact episode, from touch-down to lift-off
+struct GestureEpisode {
+    start: time::SystemTime,
+    peak_speed: f64,
+    /// Finger count changes as (milliseconds since touch-down, finger count)
+    fingers: Vec<(u128, u8)>,
+    lift_off: Option<LiftOff>,
+}
+
+impl GestureEpisode {
+    fn new(start: time::SystemTime) -> Self {
+        Self {
+            start,
+            peak_speed: 0.0,
+            fingers: Vec::new(),
+            lift_off: None,
+        }
+    }
+
+    fn elapsed_ms(&self, timestamp: time::SystemTime) -> u128 {
+        timestamp
+            .duration_since(self.start)
+            .unwrap_or_default()
+            .as_millis()
+    }
+
+    fn log(&self, end: time::SystemTime) {
+        let start = self
+            .start
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let fingers = self
+            .fingers
+            .iter()
+            .map(|(ms, count)| format!("{}@{}ms", count, ms))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let lift_off = self
+            .lift_off
+            .map(|lift_off| lift_off.to_string())
+            .unwrap_or_else(|| "no single finger lift-off".to_owned());
+        log::info!(
+            "Gesture: touch-down at {}.{:03}, duration {} ms, peak speed {:.0}, fingers [{}], {}",
+            start.as_secs(),
+            start.subsec_millis(),
+            self.elapsed_ms(end),
+            self.peak_speed,
+            fingers,
+            lift_off
+        );
+    }
+}
+
+/// Number of fingers reported by a BTN_TOOL_* key
+fn finger_count(key: evdev::Key) -> Option<u8> {
+    use evdev::Key;
+    match key {
+        Key::BTN_TOOL_FINGER => Some(1),
+        Key::BTN_TOOL_DOUBLETAP => Some(2),
+        Key::BTN_TOOL_TRIPLETAP => Some(3),
+        Key::BTN_TOOL_QUADTAP => Some(4),
+        Key::BTN_TOOL_QUINTTAP => Some(5),
+        _ => None,
+    }
+}
+
+/// Opens the device `query` refers to if set, or else the first device
+/// of the source, touchpads satisfying the match strategy
+fn find_source(
+    source: Source,
+    strategy: MatchStrategy,
+    selection: Selection,
+    query: Option<&str>,
+) -> Option<Touchpad> {
+    if let Some(query) = query {
+        return Touchpad::open(query)
+            .map_err(|e| log::debug!("Failed to open {}: {}", query, e))
+            .ok();
+    }
+    match source {
+        Source::Touchpad => Touchpad::find(strategy, selection),
+        Source::Wheel => wheel::find().map(|(path, device)| Touchpad::new(path, device)),
+    }
+}
+
+/// Blocks until a device is found, polling with a delay that doubles
+/// up to `RECONNECT_MAX_DELAY`
+fn wait_for_source(
+    source: Source,
+    strategy: MatchStrategy,
+    selection: Selection,
+    query: Option<&str>,
+) -> Touchpad {
+    let mut delay = RECONNECT_MIN_DELAY;
+    loop {
+        thread::sleep(delay);
+        if let Some(touchpad) = find_source(source, strategy, selection, query) {
+            return touchpad;
+        }
+        delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+    }
+}
+
+/// Range and resolution of an absolute axis as reported by the device
+fn abs_range(touchpad: &Touchpad, axis: evdev::AbsoluteAxisType) -> Option<AbsRange> {
+    let state = touchpad.device().get_abs_state().ok()?;
+    let info = &state[axis.0 as usize];
+    (info.minimum < info.maximum).then_some(AbsRange {
+        min: info.minimum,
+        max: info.maximum,
+        resolution: info.resolution,
+    })
+}
+
+/// Resolves the axis range, preferring the user override
+fn effective_abs_range(
+    touchpad: &Touchpad,
+    axis: evdev::AbsoluteAxisType,
+    user: Option<AbsRange>,
+) -> Option<AbsRange> {
+    let reported = abs_range(touchpad, axis);
+    if let Some(user) = user {
+        log::info!(
+            "Overriding {:?} range {} with {}",
+            axis,
+            reported.map_or_else(|| "unknown".to_owned(), |range| range.to_string()),
+            user
+        );
+        return Some(user);
+    }
+    reported
+}
+
+/// Warns if another process holds an exclusive grab on the touchpad
+///
+/// A grabbed device delivers no events to InertPad, so capture would
+/// silently see nothing. Probes by grabbing and immediately releasing.
+fn check_grab_conflict(touchpad: &mut Touchpad) {
+    match touchpad.device_mut().grab() {
+        Ok(()) => {
+            // The probe must not leave the compositor without events
+            if let Err(e) = touchpad.device_mut().ungrab() {
+                log::warn!(
+                    "Failed to release the grab probe on {}, the touchpad may stop moving \
+                     the cursor: {}",
+                    touchpad.path().display(),
+                    e
+                );
+            }
+        }
+        Err(e) if e.raw_os_error() == Some(libc::EBUSY) => {
+            let holders = device_holders(touchpad.path());
+            let holders = if holders.is_empty() {
+                "unknown process".to_string()
+            } else {
+                holders
+                    .iter()
+                    .map(|(pid, name)| format!("{} (pid {})", name, pid))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            log::warn!(
+                "{} is exclusively grabbed by another process, InertPad won't receive its events. \
+                 Possible holders: {}. Stop the conflicting tool or run it without grabbing the device",
+                touchpad.path().display(),
+                holders
+            );
+        }
+        Err(e) => log::debug!(
+            "Failed to probe grab on {}: {}",
+            touchpad.path().display(),
+            e
+        ),
+    }
+}
+
+/// Slot state to follow, if the touchpad reports positions only per
+/// multitouch slot instead of `ABS_X/Y`
+fn slot_tracking(touchpad: &Touchpad) -> Option<Slots> {
+    let caps = Capabilities::of(touchpad.device());
+    if caps.position || !caps.multitouch {
+        return None;
+    }
+    let contact = !caps.finger && !caps.touch;
+    log::info!(
+        "Touchpad reports positions per multitouch slot only, following the primary finger{}",
+        if contact { " for contact too" } else { "" }
+    );
+    Some(Slots {
+        contact,
+        ..Slots::default()
+    })
+}
+
+/// Pressure axis used to detect contact, if requested and reported
+fn pressure_contact_axis(
+    touchpad: &Touchpad,
+    threshold: Option<i32>,
+) -> Option<evdev::AbsoluteAxisType> {
+    use evdev::AbsoluteAxisType;
+    let threshold = threshold?;
+    let axes = touchpad.device().supported_absolute_axes();
+    let axis = [
+        AbsoluteAxisType::ABS_PRESSURE,
+        AbsoluteAxisType::ABS_MT_PRESSURE,
+    ]
+    .into_iter()
+    .find(|axis| axes.is_some_and(|axes| axes.contains(*axis)));
+    match axis {
+        Some(axis) => log::info!("Using {:?} >= {} as finger contact", axis, threshold),
+        None => {
+            log::warn!("Touchpad doesn't report pressure, ignoring --pressure-contact-threshold")
+        }
+    }
+    axis
+}
+
+/// Gets a newly opened touchpad ready for capture
+fn prepare_touchpad(touchpad: &mut Touchpad, read_only: bool) {
+    if read_only {
+        match touchpad.drop_write_access() {
+            Ok(()) => log::debug!("Holding {} read-only", touchpad.path().display()),
+            Err(e) => log::warn!(
+                "Failed to hold {} read-only: {}",
+                touchpad.path().display(),
+                e
+            ),
+        }
+    }
+    check_grab_conflict(touchpad);
+}
+
+/// Closes the device node until another process reads from it, then
+/// reopens it
+///
+/// Touchpad events can't be seen while it's closed, but the compositor
+/// keeps reading them, which inotify reports as an access to the node.
+fn release_until_accessed(touchpad: &mut Touchpad, read_only: bool) -> io::Result<()> {
+    // Watch before closing, so that no access goes unnoticed
+    let watch = AccessWatch::new(touchpad.path())?;
+    let null = fs::File::open("/dev/null")?;
+    // evdev::Device can't be closed in place, so its descriptor is
+    // pointed at /dev/null until the device is replaced on reopen
+    // SAFETY: both descriptors are open and owned by live values
+    if unsafe { libc::dup2(null.as_raw_fd(), touchpad.device().as_raw_fd()) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    watch.wait()?;
+    touchpad.reopen(read_only)
+}
+
+/// Synthesizes a report of the contact already in progress on reopen
+///
+/// The first events of a touch that woke the touchpad up were read by
+/// the compositor only, so without this the flick would have no touch-down.
+fn resumed_contact(touchpad: &Touchpad) -> Vec<evdev::InputEvent> {
+    use evdev::{AbsoluteAxisType, EventType, InputEvent, Key};
+    let state = touchpad.device().cached_state();
+    let Some(keys) = state
+        .key_vals()
+        .filter(|keys| keys.contains(Key::BTN_TOUCH))
+    else {
+        return Vec::new();
+    };
+    let mut events = Vec::new();
+    if let Some(abs) = state.abs_vals() {
+        for axis in [AbsoluteAxisType::ABS_X, AbsoluteAxisType::ABS_Y] {
+            let value = abs[axis.0 as usize].value;
+            events.push(InputEvent::new_now(EventType::ABSOLUTE, axis.0, value));
+        }
+    }
+    // Positions first, so that the touch-down starts from them
+    for key in keys
+        .iter()
+        .filter(|&key| key == Key::BTN_TOUCH || finger_count(key).is_some())
+    {
+        events.push(InputEvent::new_now(EventType::KEY, key.code(), 1));
+    }
+    events.push(InputEvent::new_now(EventType::SYNCHRONIZATION, 0, 0));
+    events
+}
+
+/// Captures until the touchpad fails, or until `use-device` asks for
+/// another device, returning the request
+fn run_capture(
+    touchpad: &mut Touchpad,
+    sender: &mpsc::Sender<MomentumMessage>,
+    context: CaptureContext,
+) -> Option<DeviceRequest> {
+    use evdev::AbsoluteAxisType;
+    let options = context.options;
+    let slots = slot_tracking(touchpad);
+    let (x_axis, y_axis) = match slots {
+        Some(_) => (
+            AbsoluteAxisType::ABS_MT_POSITION_X,
+            AbsoluteAxisType::ABS_MT_POSITION_Y,
+        ),
+        None => (AbsoluteAxisType::ABS_X, AbsoluteAxisType::ABS_Y),
+    };
+    let x_range = effective_abs_range(touchpad, x_axis, options.abs_range.0);
+    let y_range = effective_abs_range(touchpad, y_axis, options.abs_range.1);
+    if options.edge_margin > 0.0 && (x_range.is_none() || y_range.is_none()) {
+        log::warn!("Touchpad axis range is unknown, --edge-margin only applies to known axes");
+    }
+    let pressure_axis = pressure_contact_axis(touchpad, options.pressure_contact_threshold);
+    let mut state = CaptureState::new(context, (x_range, y_range), pressure_axis, slots);
+
+    let wake = context.control.capture_wake.as_raw_fd();
+    loop {
+        // A touchpad left idle for long is released before the
+        // watchdog would reopen it
+        let idle_release = options
+            .idle_release
+            .filter(|idle| !state.in_contact && options.watchdog.is_none_or(|w| *idle <= w));
+        match poll_readable(
+            &[touchpad.device().as_raw_fd(), wake],
+            idle_release.or(options.watchdog),
+        ) {
+            Ok(Some(0)) => {}
+            Ok(Some(_)) => {
+                if let Some(request) = context.control.take_device_request() {
+                    return Some(request);
+                }
+                continue;
+            }
+            Ok(None) => {
+                if let Some(idle) = idle_release {
+                    log::debug!("Touchpad idle for {:?}, releasing it", idle);
+                    if let Err(e) = release_until_accessed(touchpad, options.read_only) {
+                        log::error!("Failed to reopen released touchpad: {}", e);
+                        break;
+                    }
+                    log::debug!("Touchpad in use again, reopened");
+                    state.handle_batch(resumed_contact(touchpad), &mut |message| {
+                        let _ = sender.send(message);
+                    });
+                } else {
+                    log::warn!(
+                        "No touchpad events for {:?}, reopening {}",
+                        options.watchdog.unwrap_or_default(),
+                        touchpad.path().display()
+                    );
+                    if let Err(e) = touchpad.reopen(options.read_only) {
+                        log::error!("Failed to reopen touchpad: {}", e);
+                        break;
+                    }
+                }
+                continue;
+            }
+            Err(e) => {
+                log::error!("Failed to poll touchpad: {}", e);
+                break;
+            }
+        }
+        let Ok(events) = touchpad.device_mut().fetch_events() else {
+            break;
+        };
+        if options.read_strategy == ReadStrategy::Latency {
+            state.handle_batch(events, &mut |message| {
+                let _ = sender.send(message);
+            });
+            continue;
+        }
+        let mut batch: Vec<_> = events.collect();
+        while let Ok(Some(_)) =
+            poll_readable(&[touchpad.device().as_raw_fd()], Some(time::Duration::ZERO))
+        {
+            match touchpad.device_mut().fetch_events() {
+                Ok(events) => batch.extend(events),
+                Err(_) => break,
+            }
+        }
+        state.handle_batch(batch, &mut |message| {
+            let _ = sender.send(message);
+        });
+    }
+    None
+}
+
+/// Read-only inputs of the capture state machine
+#[derive(Clone, Copy)]
+struct CaptureContext<'a> {
+    params: &'a LiveParams,
+    options: &'a CaptureOptions,
+    session: &'a SessionState,
+    focus: &'a FocusState,
+    /// Held `--suppress-modifier`
+    suppress: &'a ModifierState,
+    control: &'a ControlState,
+    benchmark: Option<&'a Mutex<CaptureBenchmark>>,
+    /// Report intervals of `--probe-rate`
+    probe: Option<&'a Mutex<RateProbe>>,
+    /// Recent lift-off speeds for `--adaptive-threshold`
+    speeds: &'a SpeedHistory,
+}
+
+/// Lift-off measurements the arming decision is based on
+struct Flick {
+    inputs: InputSnapshot,
+    /// Finger mode of the swipe, with `--finger-modes`
+    mode: Option<FingerMode>,
+    kind: GestureKind,
+    /// Lift-off velocity, filtered by axes and finger mode
+    velocity: (f64, f64),
+    /// Time the finger was down
+    contact: time::Duration,
+    speed: f64,
+    /// Speed threshold in effect, after hysteresis and adaptation
+    threshold: f64,
+    /// Launch velocity scale of the soft threshold, 0 below it
+    scale: f64,
+    /// Chains onto the previous flick, per `--chain-window-ms`
+    chained: bool,
+    /// Skips the post-glide cooldown as a chained or reinforcing flick
+    bypass_post_glide: bool,
+}
+
+/// State of the inputs besides the swipe itself that the arming decision
+/// consults, from the touchpad and the optional keyboard watchers
+///
+/// Sources that aren't configured read as idle.
+#[derive(Clone, Copy, Debug, Default)]
+struct InputSnapshot {
+    /// `--suppress-modifier` is held on any keyboard
+    modifier_held: bool,
+    /// A physical touchpad button is held
+    button_held: bool,
+    /// Most fingers seen since the touch episode began
+    fingers: u8,
+}
+
+/// What happened within a single device report, up to its SYN_REPORT
+#[derive(Default)]
+struct ReportFlags {
+    /// The finger count came into contact
+    touched_down: bool,
+    /// The finger count lost contact
+    lifted: bool,
+    /// BTN_TOUCH was released
+    touch_up: bool,
+    /// BTN_TOUCH was released while tracking a debug gesture episode
+    touch_released: bool,
+    /// Events were lost and the device state was re-synced
+    resynced: bool,
+    /// The primary slot's finger became the only one, so its last
+    /// tracked position is stale
+    primary_rejoined: bool,
+    /// The contact message went out, which a report sends once
+    contact_sent: bool,
+}
+
+/// Multitouch slot state of a touchpad reporting positions only per slot
+#[derive(Clone, Copy, Debug, Default)]
+struct Slots {
+    /// Slot the following multitouch events refer to
+    current: u32,
+    /// Bitmask of the slots with a finger on them
+    active: u64,
+    /// Whether contact follows the slots, for lack of finger keys
+    contact: bool,
+}
+
+impl Slots {
+    fn fingers(&self) -> u8 {
+        self.active.count_ones() as u8
+    }
+
+    /// Whether positions reported now are those of the primary finger,
+    /// alone on the touchpad
+    fn tracks_primary(&self) -> bool {
+        self.current == 0 && self.active == 1
+    }
+}
+
+/// Touchpad event state machine turning events into momentum messages
+///
+/// It does no device I/O, so any event source can drive it batch by batch.
+struct CaptureState<'a> {
+    context: CaptureContext<'a>,
+    x_range: Option<AbsRange>,
+    y_range: Option<AbsRange>,
+    /// Units per millimeter, for the speed readout in trace logs
+    resolution: Option<(f64, f64)>,
+    /// Pressure axis used to detect contact, if enabled
+    pressure_axis: Option<evdev::AbsoluteAxisType>,
+    /// Slot state, if positions are only reported per slot
+    slots: Option<Slots>,
+    velocity: (f64, f64),
+    /// Most recent position deltas of the contact and their durations in
+    /// seconds, for `--velocity-samples`
+    deltas: VecDeque<(f64, f64, f64)>,
+    position: (i32, i32),
+    prev_position: (i32, i32),
+    timestamp: time::SystemTime,
+    prev_timestamp: time::SystemTime,
+    multitouch_timestamp: time::SystemTime,
+    click_timestamp: time::SystemTime,
+    /// Last touchpad button press
+    press_timestamp: time::SystemTime,
+    /// Touchpad buttons currently pressed
+    buttons_held: u8,
+    episode: Option<GestureEpisode>,
+    last_armed: Option<time::SystemTime>,
+    /// Launch velocity of the last armed flick, before scaling
+    last_armed_velocity: (f64, f64),
+    in_contact: bool,
+    /// Position deltas left to discard after touch-down
+    skip_samples: u32,
+    /// Most fingers seen since the touch episode began
+    max_fingers: u8,
+    /// Whether the current contact started within the edge margin
+    edge_start: bool,
+    /// Touch-down time of the current contact
+    contact_start: time::SystemTime,
+    /// Sum and count of report intervals since touch-down
+    input_intervals: (time::Duration, u32),
+    /// The report in progress, if a batch ended before its SYN_REPORT
+    report: ReportFlags,
+}
+
+impl<'a> CaptureState<'a> {
+    fn new(
+        context: CaptureContext<'a>,
+        (x_range, y_range): (Option<AbsRange>, Option<AbsRange>),
+        pressure_axis: Option<evdev::AbsoluteAxisType>,
+        slots: Option<Slots>,
+    ) -> Self {
+        let resolution = match (x_range, y_range) {
+            (Some(x), Some(y)) if x.resolution > 0 && y.resolution > 0 => {
+                Some((f64::from(x.resolution), f64::from(y.resolution)))
+            }
+            _ => None,
+        };
+        Self {
+            context,
+            x_range,
+            y_range,
+            resolution,
+            pressure_axis,
+            slots,
+            velocity: (0.0, 0.0),
+            deltas: VecDeque::new(),
+            position: (0, 0),
+            prev_position: (0, 0),
+            timestamp: time::SystemTime::UNIX_EPOCH,
+            prev_timestamp: time::SystemTime::UNIX_EPOCH,
+            multitouch_timestamp: time::SystemTime::UNIX_EPOCH,
+            click_timestamp: time::SystemTime::UNIX_EPOCH,
+            press_timestamp: time::SystemTime::UNIX_EPOCH,
+            buttons_held: 0,
+            episode: None,
+            last_armed: None,
+            last_armed_velocity: (0.0, 0.0),
+            in_contact: false,
+            skip_samples: 0,
+            max_fingers: 0,
+            edge_start: false,
+            contact_start: time::SystemTime::UNIX_EPOCH,
+            input_intervals: (time::Duration::ZERO, 0),
+            report: ReportFlags::default(),
+        }
+    }
+
+    /// Processes a batch of events, passing the resulting messages to `send`
+    ///
+    /// Each report is finalized at its SYN_REPORT, so a batch may hold
+    /// several reports or end within one, which then continues in the next.
+    fn handle_batch(
+        &mut self,
+        events: impl IntoIterator<Item = evdev::InputEvent>,
+        send: &mut impl FnMut(MomentumMessage),
+    ) {
+        use evdev::{InputEventKind, Synchronization};
+        let mut flags = mem::take(&mut self.report);
+        for event in events {
+            let received = time::Instant::now();
+            self.handle_event(event, &mut flags, send);
+            if event.kind() == InputEventKind::Synchronization(Synchronization::SYN_REPORT) {
+                self.finish_report(mem::take(&mut flags), send);
+                if let Some(probe) = self.context.probe {
+                    let mut probe = probe.lock().unwrap();
+                    if self.in_contact {
+                        probe.record_report(event.timestamp());
+                    } else {
+                        probe.end_contact();
+                    }
+                }
+            }
+            if let Some(benchmark) = self.context.benchmark {
+                benchmark
+                    .lock()
+                    .unwrap()
+                    .record_handling(received.elapsed());
+            }
+        }
+        self.report = flags;
+    }
+
+    /// Acts on a complete device report, once its SYN_REPORT arrives
+    fn finish_report(&mut self, flags: ReportFlags, send: &mut impl FnMut(MomentumMessage)) {
+        let options = self.context.options;
+        if flags.resynced {
+            // The position jumped across the lost events, so neither the
+            // delta nor the velocity measured before the gap can be trusted
+            log::debug!("Touchpad events were dropped, resetting velocity baseline");
+            self.velocity = (0.0, 0.0);
+            self.input_intervals = (time::Duration::ZERO, 0);
+            self.prev_position = self.position;
+            self.prev_timestamp = self.timestamp;
+        }
+        if flags.primary_rejoined {
+            self.prev_position = self.position;
+            self.prev_timestamp = self.timestamp;
+        }
+        // Positions are reported after the contact keys in a report
+        if flags.touched_down {
+            self.edge_start = options.edge_margin > 0.0
+                && AbsRange::in_edge(
+                    self.x_range,
+                    self.y_range,
+                    self.position,
+                    options.edge_margin,
+                );
+        }
+        // A position change in the lift-off report is the latest movement,
+        // so it's taken into the velocity before the launch is decided
+        self.update_velocity();
+        // Decided once the whole report is seen, since a finger count
+        // change reports the old count's release before the new press
+        if flags.lifted {
+            self.lift_off(send);
+        }
+        if flags.touch_up {
+            self.max_fingers = 0;
+        }
+        if flags.touch_released {
+            if let Some(episode) = self.episode.take() {
+                episode.log(self.timestamp);
+            }
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        event: evdev::InputEvent,
+        flags: &mut ReportFlags,
+        send: &mut impl FnMut(MomentumMessage),
+    ) {
+        use evdev::{AbsoluteAxisType, InputEventKind, Key, Synchronization};
+        let options = self.context.options;
+        let timestamp = event.timestamp();
+        // evdev swallows SYN_DROPPED and re-emits the device state stamped
+        // with the resync time, so events queued behind it go back in time
+        if timestamp < self.timestamp {
+            flags.resynced = true;
+        }
+        self.timestamp = timestamp;
+        log::trace!("Touchpad event: {:?} = {}", event.kind(), event.value());
+        let clamp = |range: Option<AbsRange>, value| range.map_or(value, |r| r.clamp(value));
+        // Contact state reported by this event, if any
+        let mut contact = None;
+        match event.kind() {
+            InputEventKind::AbsAxis(axis) => match axis {
+                AbsoluteAxisType::ABS_X => self.position.0 = clamp(self.x_range, event.value()),
+                AbsoluteAxisType::ABS_Y => self.position.1 = clamp(self.y_range, event.value()),
+                AbsoluteAxisType::ABS_MT_SLOT => {
+                    if let Some(slots) = &mut self.slots {
+                        slots.current = event.value().max(0) as u32;
+                    }
+                }
+                AbsoluteAxisType::ABS_MT_TRACKING_ID if self.slots.is_some() => {
+                    contact = self.update_slot(event.value() >= 0, flags);
+                }
+                AbsoluteAxisType::ABS_MT_POSITION_X
+                    if self.slots.is_some_and(|slots| slots.tracks_primary()) =>
+                {
+                    self.position.0 = clamp(self.x_range, event.value());
+                }
+                AbsoluteAxisType::ABS_MT_POSITION_Y
+                    if self.slots.is_some_and(|slots| slots.tracks_primary()) =>
+                {
+                    self.position.1 = clamp(self.y_range, event.value());
+                }
+                axis if Some(axis) == self.pressure_axis => {
+                    let threshold = options.pressure_contact_threshold.unwrap_or_default();
+                    contact = Some(event.value() >= threshold);
+                }
+                _ => (),
+            },
+            InputEventKind::Synchronization(Synchronization::SYN_DROPPED) => {
+                flags.resynced = true;
+            }
+            InputEventKind::Key(key) => {
+                if event.value() == 1 {
+                    match options.stop_on.iter().find(|stop| stop.matches(key)) {
+                        Some(StopKey::Finger | StopKey::Touch) if !flags.contact_sent => {
+                            send(options.contact_message());
+                            flags.contact_sent = true;
+                        }
+                        Some(StopKey::Finger | StopKey::Touch) | None => {}
+                        Some(_) => send(MomentumMessage::StopMovement),
+                    }
+                }
+                if let (Some(count), 1) = (finger_count(key), event.value()) {
+                    self.max_fingers = self.max_fingers.max(count);
+                    if let Some(episode) = &mut self.episode {
+                        let ms = episode.elapsed_ms(timestamp);
+                        episode.fingers.push((ms, count));
+                    }
+                }
+                if key == Key::BTN_TOUCH && event.value() == 0 {
+                    flags.touch_up = true;
+                }
+                // Finger modes follow swipes with any number of fingers
+                let contact_key = if options.finger_modes.is_some() {
+                    Key::BTN_TOUCH
+                } else {
+                    Key::BTN_TOOL_FINGER
+                };
+                if key == contact_key {
+                    contact = Some(event.value() == 1);
+                }
+                match key {
+                    Key::BTN_TOUCH if options.debug_gestures => {
+                        if event.value() == 1 {
+                            self.episode = Some(GestureEpisode::new(timestamp));
+                        } else {
+                            flags.touch_released = true;
+                        }
+                    }
+                    Key::BTN_TOOL_DOUBLETAP
+                    | Key::BTN_TOOL_TRIPLETAP
+                    | Key::BTN_TOOL_QUADTAP
+                    | Key::BTN_TOOL_QUINTTAP
+                        if event.value() == 0 =>
+                    {
+                        self.multitouch_timestamp = timestamp;
+                    }
+                    Key::BTN_LEFT | Key::BTN_RIGHT | Key::BTN_MIDDLE => {
+                        let bit = 1 << (key.code() - Key::BTN_LEFT.code());
+                        match event.value() {
+                            0 => {
+                                self.buttons_held &= !bit;
+                                self.click_timestamp = timestamp;
+                            }
+                            1 => {
+                                self.buttons_held |= bit;
+                                self.press_timestamp = timestamp;
+                            }
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        match contact {
+            Some(true) if !self.in_contact => {
+                // Whatever reported the contact, as not all of it comes
+                // with a stop key
+                if options.stop_on_contact() && !flags.contact_sent {
+                    send(options.contact_message());
+                    flags.contact_sent = true;
+                }
+                self.in_contact = true;
+                self.contact_start = self.timestamp;
+                flags.touched_down = true;
+                self.skip_samples = options.skip_first_samples;
+                self.input_intervals = (time::Duration::ZERO, 0);
+                self.velocity = (0.0, 0.0);
+                self.deltas.clear();
+                self.prev_position = self.position; // Prevent velocity overwrite later
+            }
+            Some(false) if self.in_contact => {
+                self.in_contact = false;
+                flags.lifted = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Factor from the fixed speed threshold to the adaptive one
+    ///
+    /// A percentile of a bounded window follows changes quickly, while
+    /// a few extreme flicks barely move it.
+    fn adaptive_scale(&self) -> f64 {
+        let Some(percentile) = self.context.options.adaptive_threshold else {
+            return 1.0;
+        };
+        let speeds = self.context.speeds.sorted();
+        if speeds.len() < ADAPTIVE_MIN_SAMPLES {
+            return 1.0;
+        }
+        let rank = (percentile / 100.0 * (speeds.len() - 1) as f64).round() as usize;
+        let fixed = self
+            .context
+            .params
+            .load()
+            .speed_threshold(self.context.options.kind)
+            .max(1.0);
+        let scale = (speeds[rank] / fixed).clamp(0.5, 2.0);
+        log::trace!("Adaptive speed threshold: {:.0}", fixed * scale);
+        scale
+    }
+
+    /// Returns the active cooldown of the gesture kind with the most time
+    /// remaining, if any, leaving out the post-glide one if `bypass_post_glide`
+    fn active_cooldown(
+        &self,
+        kind: GestureKind,
+        bypass_post_glide: bool,
+    ) -> Option<(Cooldown, time::Duration)> {
+        let options = self.context.options;
+        let cooldowns = options.cooldowns(kind);
+        let glide_ended = self
+            .context
+            .control
+            .last_glide
+            .lock()
+            .unwrap()
+            .ended
+            .filter(|_| !bypass_post_glide);
+        [
+            // With finger modes a swipe is judged by its most fingers
+            // instead, and the release of those ends the swipe itself
+            (
+                Cooldown::Multitouch,
+                Some(self.multitouch_timestamp).filter(|_| options.finger_modes.is_none()),
+                // Flicks in the taper are weakened instead of suppressed
+                cooldowns.multitouch.saturating_sub(options.cooldown_taper),
+            ),
+            (Cooldown::Click, Some(self.click_timestamp), cooldowns.click),
+            (Cooldown::PostGlide, glide_ended, cooldowns.post_glide),
+        ]
+        .into_iter()
+        .filter_map(|(cause, since, cooldown)| {
+            let elapsed = self.timestamp.duration_since(since?).unwrap_or_default();
+            let remaining = cooldown.checked_sub(elapsed).filter(|r| !r.is_zero())?;
+            Some((cause, elapsed, remaining))
+        })
+        .max_by_key(|&(_, _, remaining)| remaining)
+        .map(|(cause, elapsed, _)| (cause, elapsed))
+    }
+
+    /// Launch velocity scale ramping from 0 to 1 over the final
+    /// `--cooldown-taper-ms` of the multitouch cooldown
+    fn cooldown_taper(&self, kind: GestureKind) -> f64 {
+        let options = self.context.options;
+        let cooldown = options.cooldowns(kind).multitouch;
+        let taper = options.cooldown_taper.min(cooldown);
+        if taper.is_zero() || options.finger_modes.is_some() {
+            return 1.0;
+        }
+        let elapsed = self
+            .timestamp
+            .duration_since(self.multitouch_timestamp)
+            .unwrap_or_default();
+        let into_taper = elapsed.saturating_sub(cooldown - taper);
+        (into_taper.as_secs_f64() / taper.as_secs_f64()).min(1.0)
+    }
+
+    /// Launch velocity factor of a flick lifted off right after a button
+    /// press, which goes straight into the settle tail
+    fn click_settle(&self) -> f64 {
+        let settle = self.context.options.click_settle;
+        let since_press = self
+            .timestamp
+            .duration_since(self.press_timestamp)
+            .unwrap_or_default();
+        if !settle.is_zero() && since_press < settle {
+            CLICK_SETTLE_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    /// Follows a finger touching or leaving the current slot, returning
+    /// the contact state if the slots report it
+    ///
+    /// Like the finger keys, the finger count rises as fingers touch and
+    /// contact is a single finger, or any with finger modes.
+    fn update_slot(&mut self, touching: bool, flags: &mut ReportFlags) -> Option<bool> {
+        let slots = self.slots.as_mut()?;
+        let bit = 1u64.checked_shl(slots.current)?;
+        let before = *slots;
+        if touching {
+            slots.active |= bit;
+        } else {
+            slots.active &= !bit;
+        }
+        let slots = *slots;
+        let fingers = slots.fingers();
+        self.max_fingers = self.max_fingers.max(fingers);
+        if fingers < before.fingers() && before.fingers() >= 2 {
+            self.multitouch_timestamp = self.timestamp;
+        }
+        if slots.active == 1 && before.active != 1 {
+            flags.primary_rejoined = true;
+        }
+        if !slots.contact {
+            return None;
+        }
+        if fingers == 0 {
+            flags.touch_up = true;
+        }
+        Some(if self.context.options.finger_modes.is_some() {
+            fingers > 0
+        } else {
+            fingers == 1
+        })
+    }
+
+    /// Current state of the other inputs
+    fn inputs(&self) -> InputSnapshot {
+        InputSnapshot {
+            modifier_held: self.context.suppress.is_held(),
+            button_held: self.buttons_held != 0,
+            fingers: self.max_fingers,
+        }
+    }
+
+    /// Measures the flick of the lift-off for the arming decision
+    fn measure_flick(&self) -> Flick {
+        let options = self.context.options;
+        let inputs = self.inputs();
+        let mode = options.finger_modes.map(|modes| {
+            let fingers = usize::from(inputs.fingers).clamp(1, MAX_FINGERS);
+            modes[fingers - 1]
+        });
+        let kind = match mode.and_then(FingerMode::routing) {
+            Some(routing) => routing.y.into(),
+            None => options.kind,
+        };
+        let (vx, vy) = rotate(self.velocity, options.rotate_deg);
+        let (vx, vy) = options.axes.filter(vx, vy);
+        let (vx, vy) = mode.map_or((vx, vy), |mode| mode.filter(vx, vy));
+        let speed = (vx * vx + vy * vy).sqrt();
+        let since_armed = self
+            .last_armed
+            .map(|armed| self.timestamp.duration_since(armed).unwrap_or_default());
+        let recently_armed = since_armed.is_some_and(|elapsed| elapsed < FLICK_STREAK_WINDOW);
+        let current = self.context.params.load();
+        let threshold = current.arm_threshold(kind, recently_armed) * self.adaptive_scale();
+        let (last_vx, last_vy) = self.last_armed_velocity;
+        let last_speed = last_vx.hypot(last_vy);
+        let same_direction = speed > 0.0
+            && last_speed > 0.0
+            && (vx * last_vx + vy * last_vy) / (speed * last_speed) >= REINFORCE_MIN_COSINE;
+        // A quick repeat within --chain-window-ms in the same direction
+        let chained = same_direction
+            && since_armed
+                .zip(options.chain_window)
+                .is_some_and(|(elapsed, window)| elapsed < window);
+        // A deliberate repeat of a hard flick in the same direction
+        let clear_speed = current.speed_threshold(kind) * REINFORCE_SPEED_RATIO;
+        let reinforcing =
+            recently_armed && same_direction && speed >= clear_speed && last_speed >= clear_speed;
+        Flick {
+            inputs,
+            mode,
+            kind,
+            velocity: (vx, vy),
+            contact: self
+                .timestamp
+                .duration_since(self.contact_start)
+                .unwrap_or_default(),
+            speed,
+            threshold,
+            scale: current.launch_scale(speed, threshold),
+            chained,
+            bypass_post_glide: chained || reinforcing && !options.strict_post_glide_cooldown,
+        }
+    }
+
+    /// Decides whether a flick triggers inertial movement, or the first
+    /// reason it doesn't
+    fn decide(&self, flick: &Flick) -> LiftOff {
+        let options = self.context.options;
+        let inputs = flick.inputs;
+        if !self.context.control.enabled.load(Ordering::Relaxed) {
+            LiftOff::Disabled
+        } else if self.context.session.is_inactive() {
+            LiftOff::SessionInactive
+        } else if self.context.focus.is_excluded() {
+            LiftOff::AppExcluded
+        } else if inputs.modifier_held {
+            LiftOff::ModifierHeld
+        } else if inputs.button_held {
+            LiftOff::ButtonHeld
+        } else if match flick.mode {
+            Some(mode) => mode == FingerMode::Ignore,
+            None => {
+                options.kind == GestureKind::Pointer && inputs.fingers > options.pointer_max_fingers
+            }
+        } {
+            LiftOff::TooManyFingers {
+                fingers: inputs.fingers,
+            }
+        } else if self.edge_start {
+            LiftOff::EdgeSwipe
+        } else if let Some(contact) = Some(flick.contact).filter(|&c| c < options.min_contact) {
+            LiftOff::ShortContact { contact }
+        } else if let Some((cause, elapsed)) =
+            self.active_cooldown(flick.kind, flick.bypass_post_glide)
+        {
+            LiftOff::Cooldown { cause, elapsed }
+        } else if flick.scale == 0.0 {
+            LiftOff::BelowThreshold {
+                speed: flick.speed,
+                threshold: flick.threshold,
+            }
+        } else {
+            LiftOff::Armed { speed: flick.speed }
+        }
+    }
+
+    /// Decides whether the lift-off triggers inertial movement, and
+    /// launches it
+    fn lift_off(&mut self, send: &mut impl FnMut(MomentumMessage)) {
+        let timestamp = self.timestamp;
+        let flick = self.measure_flick();
+        let lift_off = self.decide(&flick);
+        let Flick {
+            mode,
+            kind,
+            velocity: (vx, vy),
+            speed,
+            scale,
+            chained,
+            ..
+        } = flick;
+        log::debug!(
+            "Lift-off {}: kind={:?} speed={:.0} threshold={:.0} fingers={}",
+            lift_off,
+            kind,
+            speed,
+            flick.threshold,
+            flick.inputs.fingers
+        );
+        if !matches!(
+            lift_off,
+            LiftOff::SessionInactive | LiftOff::TooManyFingers { .. }
+        ) && speed > 0.0
+        {
+            self.context.speeds.push(speed);
+        }
+        match lift_off {
+            LiftOff::Armed { .. } => self.context.control.metrics.flick_armed(),
+            LiftOff::Cooldown { .. } => self.context.control.metrics.flick_cooldown(),
+            _ => {}
+        }
+        let hooks = &self.context.control.hooks;
+        match lift_off {
+            LiftOff::Armed { .. } => hooks.flick_armed(kind, vx, vy),
+            _ => hooks.flick_suppressed(&lift_off),
+        }
+        if let LiftOff::Armed { .. } = lift_off {
+            if let Some(benchmark) = self.context.benchmark {
+                let latency = time::SystemTime::now()
+                    .duration_since(timestamp)
+                    .unwrap_or_default();
+                benchmark.lock().unwrap().record_lift_off(latency);
+            }
+            let mut taper = self.cooldown_taper(kind);
+            if taper < 1.0 {
+                log::debug!(
+                    "Launch weakened to {:.2} near the multitouch cooldown",
+                    taper
+                );
+            }
+            let settle = self.click_settle();
+            if settle < 1.0 {
+                log::debug!("Launch settled to {:.2} after a button press", settle);
+                taper *= settle;
+            }
+            if mode == Some(FingerMode::Command) {
+                let recent = self
+                    .last_armed
+                    .and_then(|last| timestamp.duration_since(last).ok())
+                    .is_some_and(|elapsed| elapsed < FLICK_COMMAND_MIN_INTERVAL);
+                match &self.context.options.fling_command {
+                    Some(_) if recent => {
+                        log::debug!("Fling command skipped, the previous one ran too recently")
+                    }
+                    Some(template) => spawn_flick_command(template, vx, vy),
+                    None => {}
+                }
+                self.last_armed = Some(timestamp);
+                return;
+            }
+            let (sum, count) = self.input_intervals;
+            let interval = (count >= MIN_INTERVAL_SAMPLES)
+                .then(|| sum / count)
+                .filter(|interval| *interval <= MAX_INPUT_INTERVAL);
+            // Scaled as a whole, keeping the direction
+            let cap = match self.context.options.max_speed {
+                Some(max) if vx.hypot(vy) > max => {
+                    log::debug!("Launch speed {:.0} capped to {:.0}", vx.hypot(vy), max);
+                    max / vx.hypot(vy)
+                }
+                _ => 1.0,
+            };
+            send(MomentumMessage::StartMovement {
+                vx: vx * cap * scale * taper,
+                vy: vy * cap * scale * taper,
+                interval,
+                routing: mode.and_then(FingerMode::routing),
+                chained,
+                fingers: flick.inputs.fingers,
+            });
+            self.last_armed = Some(timestamp);
+            self.last_armed_velocity = (vx, vy);
+        }
+        if let Some(episode) = &mut self.episode {
+            episode.lift_off = Some(lift_off);
+        }
+    }
+
+    /// Recomputes velocity from the position change of the report
+    fn update_velocity(&mut self) {
+        if self.position == self.prev_position {
+            return;
+        }
+        // Stale after idle or suspend, or garbage after a clock change
+        let interval = self
+            .timestamp
+            .duration_since(self.prev_timestamp)
+            .ok()
+            .filter(|interval| *interval <= MAX_SAMPLE_GAP);
+        match interval {
+            // Reports sharing a timestamp have no duration to measure over,
+            // so their movement is measured along with the next report
+            Some(interval) if interval.is_zero() => return,
+            Some(interval) if self.skip_samples == 0 => {
+                let dx = (self.position.0 - self.prev_position.0) as f64;
+                let dy = (self.position.1 - self.prev_position.1) as f64;
+                self.input_intervals = (
+                    self.input_intervals.0 + interval,
+                    self.input_intervals.1 + 1,
+                );
+                let dt = interval.as_secs_f64();
+                let samples = self.context.options.velocity_samples;
+                if self.deltas.len() == samples {
+                    self.deltas.pop_front();
+                }
+                self.deltas.push_back((dx, dy, dt));
+                // Total movement over total time weighs each delta by its duration
+                let (sum_x, sum_y, sum_t) = self
+                    .deltas
+                    .iter()
+                    .fold((0.0, 0.0, 0.0), |(x, y, t), &(dx, dy, dt)| {
+                        (x + dx, y + dy, t + dt)
+                    });
+                if sum_t <= 0.0 {
+                    return;
+                }
+                let (mut vx, mut vy) = (sum_x / sum_t, sum_y / sum_t);
+                // The first sample of a swipe starts the filter
+                if let Some(alpha) = self.context.options.report_smoothing {
+                    if self.velocity != (0.0, 0.0) {
+                        let reports = dt / SMOOTHING_INTERVAL.as_secs_f64();
+                        let weight = 1.0 - (1.0 - alpha).powf(reports);
+                        vx = self.velocity.0 + (vx - self.velocity.0) * weight;
+                        vy = self.velocity.1 + (vy - self.velocity.1) * weight;
+                    }
+                }
+                self.velocity = (vx, vy);
+                // One line per report, with the delta and interval it was measured over
+                match self.resolution {
+                    Some((res_x, res_y)) => log::trace!(
+                        "Velocity: ({:.02}, {:.02}) units/s, {:.1} mm/s, from ({}, {}) in {:.2} ms",
+                        vx,
+                        vy,
+                        (vx / res_x).hypot(vy / res_y),
+                        dx,
+                        dy,
+                        dt * 1000.0
+                    ),
+                    None => log::trace!(
+                        "Velocity: ({:.02}, {:.02}) units/s, from ({}, {}) in {:.2} ms",
+                        vx,
+                        vy,
+                        dx,
+                        dy,
+                        dt * 1000.0
+                    ),
+                }
+                if let Some(episode) = &mut self.episode {
+                    episode.peak_speed = episode.peak_speed.max((vx * vx + vy * vy).sqrt());
+                }
+            }
+            _ => {
+                // Landing jitter or no recent baseline: track the position
+                // without taking velocity
+                self.skip_samples = self.skip_samples.saturating_sub(1);
+                if interval.is_none() {
+                    self.velocity = (0.0, 0.0);
+                    self.deltas.clear();
+                }
+            }
+        }
+        self.prev_position = self.position;
+        self.prev_timestamp = self.timestamp;
+    }
+}
+
+/// Launches a glide with the velocity every interval, alternating direction
+fn spawn_test_pattern(
+    sender: mpsc::Sender<MomentumMessage>,
+    (vx, vy): (f64, f64),
+    interval: time::Duration,
+) {
+    thread::spawn(move || {
+        for direction in [1.0, -1.0].into_iter().cycle() {
+            thread::sleep(interval);
+            let launch = MomentumMessage::StartMovement {
+                vx: vx * direction,
+                vy: vy * direction,
+                interval: None,
+                routing: None,
+                chained: false,
+                fingers: 0,
+            };
+            if sender.send(launch).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Sends a keep-awake nudge to the emulation every `interval`
+fn spawn_keep_awake(sender: mpsc::Sender<MomentumMessage>, interval: time::Duration) {
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        if sender.send(MomentumMessage::KeepAwake).is_err() {
+            break;
+        }
+    });
+}
+
+/// Launch speed of a typical flick relative to the speed threshold
+const TYPICAL_FLICK_RATIO: f64 = 2.0;
+
+/// Tunable parameters after deriving the speed factor, if requested
+fn effective_params(args: &Args, matches: &ArgMatches) -> Params {
+    let speed_factor = match args.target_max_distance {
+        Some(_) if matches.value_source("speed_factor") != Some(ValueSource::DefaultValue) => {
+            log::warn!(
+                "Both --speed-factor and --target-max-distance are set, using --speed-factor"
+            );
+            args.speed_factor.x
+        }
+        Some(distance) => {
+            let speed_factor = args.base_params().speed_factor_for_distance(
+                args.inertia_as.into(),
+                args.max_launch_speed,
+                distance,
+            );
+            log::info!(
+                "Derived speed factor {:.5} for a {} px glide at {} units/s",
+                speed_factor,
+                distance,
+                args.max_launch_speed
+            );
+            speed_factor
+        }
+        None => args.speed_factor.x,
+    };
+    Params {
+        speed_factor,
+        ..args.base_params()
+    }
+}
+
+/// Warns if a typical flick would glide less than a pixel or a wheel click,
+/// a common outcome of a drag set close to 1.0
+fn warn_if_invisible(params: &Params, kind: GestureKind) {
+    let launch_speed = params.speed_threshold(kind).max(1.0) * TYPICAL_FLICK_RATIO;
+    let distance = params.glide_distance(kind, launch_speed);
+    let (visible, unit) = match kind {
+        GestureKind::Pointer => (1.0, "px"),
+        GestureKind::Scroll => (f64::from(VirtualMouse::WHEEL_CLICK), "hi-res wheel units"),
+    };
+    if distance < visible {
+        log::warn!(
+            "A typical flick at {:.0} units/s would glide only {:.2} {} with drag {} and speed \
+             factor {}, so inertia won't visibly move. Lower the drag or raise the speed factor",
+            launch_speed,
+            distance,
+            unit,
+            params.drag(kind),
+            params.speed_factor(kind)
+        );
+    }
+}
+
+/// Time the emulation has to tidy up the virtual mouse on SIGINT or SIGTERM
+const SHUTDOWN_TIMEOUT: time::Duration = time::Duration::from_secs(1);
+
+/// First and longest delay between attempts to find a disconnected device
+const RECONNECT_MIN_DELAY: time::Duration = time::Duration::from_millis(250);
+const RECONNECT_MAX_DELAY: time::Duration = time::Duration::from_secs(5);
+
+/// Time `--probe-rate` observes the touchpad for
+const PROBE_TIME: time::Duration = time::Duration::from_secs(10);
+
+/// Shortest interval between two `--on-flick` commands
+const FLICK_COMMAND_MIN_INTERVAL: time::Duration = time::Duration::from_millis(250);
+
+/// Runs the `--on-flick` command template for every armed flick
+///
+/// Returns once the touchpad capture terminates.
+fn run_flick_commands(receiver: mpsc::Receiver<MomentumMessage>, template: &str) {
+    let mut last_spawn: Option<time::Instant> = None;
+    for message in receiver {
+        let MomentumMessage::StartMovement { vx, vy, .. } = message else {
+            continue;
+        };
+        if last_spawn.is_some_and(|last| last.elapsed() < FLICK_COMMAND_MIN_INTERVAL) {
+            log::debug!("Flick command skipped, the previous one ran too recently");
+            continue;
+        }
+        last_spawn = Some(time::Instant::now());
+        spawn_flick_command(template, vx, vy);
+    }
+    log::error!("Touchpad capture has terminated");
+}
+
+/// Runs a flick command template with the lift-off velocity filled in,
+/// without waiting for it to finish
+fn spawn_flick_command(template: &str, vx: f64, vy: f64) {
+    // Touchpad Y grows downwards
+    let direction = match (vx.abs() >= vy.abs(), vx < 0.0, vy < 0.0) {
+        (true, true, _) => "left",
+        (true, false, _) => "right",
+        (false, _, true) => "up",
+        (false, _, false) => "down",
+    };
+    let command = template
+        .replace("{dx}", &format!("{:.0}", vx))
+        .replace("{dy}", &format!("{:.0}", vy))
+        .replace("{speed}", &format!("{:.0}", vx.hypot(vy)))
+        .replace("{direction}", direction);
+    log::debug!("Running flick command: {}", command);
+    match std::process::Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .spawn()
+    {
+        // Reap it in the background, so that a slow command doesn't
+        // hold up the next flick
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => log::error!("Failed to run flick command: {}", e),
+    }
+}
+
+/// Inotify watch reporting reads of a file by any process
+struct AccessWatch {
+    fd: fs::File,
+}
+
+impl AccessWatch {
+    fn new(path: &Path) -> io::Result<Self> {
+        // SAFETY: plain syscall without pointer arguments
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a fresh descriptor owned by nothing else
+        let fd = unsafe { fs::File::from_raw_fd(fd) };
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        // A removed node also ends the wait, so that reopening fails and
+        // the touchpad is searched for again
+        let mask = libc::IN_ACCESS | libc::IN_ATTRIB | libc::IN_DELETE_SELF;
+        // SAFETY: `path` is a valid NUL-terminated string
+        if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), path.as_ptr(), mask) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { fd })
+    }
+
+    /// Blocks until the watched file is accessed
+    fn wait(mut self) -> io::Result<()> {
+        let mut buffer = [0u8; 256];
+        loop {
+            match self.fd.read(&mut buffer) {
+                Ok(_) => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Waits until the file descriptor becomes readable.
+/// Returns `false` on timeout.
+fn wait_readable(fd: RawFd, timeout: time::Duration) -> io::Result<bool> {
+    Ok(poll_readable(&[fd], Some(timeout))?.is_some())
+}
+
+/// Waits until any of the file descriptors becomes readable, without
+/// a timeout if `None`. Returns the index of the first readable one, or
+/// `None` on timeout. An interrupted wait reports the first one.
+fn poll_readable(fds: &[RawFd], timeout: Option<time::Duration>) -> io::Result<Option<usize>> {
+    let mut pollfds: Vec<_> = fds
+        .iter()
+        .map(|&fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        })
+        .collect();
+    let timeout = timeout.map_or(-1, |timeout| {
+        timeout.as_millis().min(i32::MAX as u128) as i32
+    });
+    // SAFETY: `pollfds` is a valid, exclusively borrowed array of its length
+    match unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as libc::nfds_t, timeout) } {
+        -1 => match io::Error::last_os_error() {
+            e if e.kind() == io::ErrorKind::Interrupted => Ok(Some(0)),
+            e => Err(e),
+        },
+        0 => Ok(None),
+        _ => Ok(pollfds.iter().position(|pollfd| pollfd.revents != 0)),
+    }
+}
+
+/// Logs the display server and desktop environment, as far as the
+/// environment tells, to give context to bug reports
+fn log_session_type() {
+    let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+    let session = var("XDG_SESSION_TYPE").unwrap_or_else(|| {
+        if var("WAYLAND_DISPLAY").is_some() {
+            "wayland".to_owned()
+        } else if var("DISPLAY").is_some() {
+            "x11".to_owned()
+        } else {
+            "unknown".to_owned()
+        }
+    });
+    let desktop = var("XDG_CURRENT_DESKTOP")
+        .or_else(|| var("DESKTOP_SESSION"))
+        .unwrap_or_else(|| "unknown".to_owned());
+    log::info!("Session type: {}, desktop: {}", session, desktop);
+}
+
+fn list_devices() {
+    match inertpad::list_touchpads() {
+        Err(e) => log::error!("Failed to enumerate input devices: {}", e),
+        Ok(touchpads) => {
+            for touchpad in touchpads {
+                match touchpad {
+                    Ok(info) => {
+                        let resolution = info
+                            .resolution
+                            .map(|(x, y)| format!("{}x{} units/mm", x, y))
+                            .unwrap_or_else(|| "unknown resolution".to_owned());
+                        println!(
+                            "{}\t{}\t{}, {:?}, matches: {:?}",
+                            info.path.display(),
+                            info.name,
+                            resolution,
+                            info.capabilities,
+                            info.strategies
+                        );
+                    }
+                    Err(e) => log::warn!("Skipping {}", e),
+                }
+            }
+        }
+    }
+}
+
+/// Process exit codes for conditions that wrapper scripts may handle
+#[derive(Clone, Copy, Debug)]
+enum Failure {
+    /// Any other failure, including the emulation loop ending unexpectedly
+    Other = 1,
+    /// No touchpad found
+    NoDevice = 2,
+    /// Failed to create the virtual mouse device
+    Uinput = 3,
+    /// Invalid command line arguments
+    Config = 4,
+}
+
+impl From<Failure> for ExitCode {
+    fn from(failure: Failure) -> Self {
+        ExitCode::from(failure as u8)
+    }
+}
+
+/// Parses the command line over the environment, keeping the matches to
+/// tell explicit options from defaults
+///
+/// Fails with the exit code once the error or the help is printed.
+fn parse_args() -> Result<(Args, ArgMatches), ExitCode> {
+    let parsed = Args::command()
+        .try_get_matches()
+        .and_then(|matches| Ok((Args::from_arg_matches(&matches)?, matches)));
+    parsed.map_err(|e| {
+        let _ = e.print();
+        // --help and --version are reported as errors too
+        if e.use_stderr() {
+            Failure::Config.into()
+        } else {
+            ExitCode::SUCCESS
+        }
+    })
+}
+
+/// Name of the device capture would open with `args`, to pick its config
+/// device block before anything else is set up
+fn probe_device_name(args: &Args) -> Option<String> {
+    let query = args.device.as_deref().or(args.device_path.as_deref());
+    let touchpad = find_source(args.source, args.match_strategy, args.select, query)?;
+    touchpad.device().name().map(str::to_owned)
+}
+
+#[cfg(not(fuzzing))]
+fn main() -> ExitCode {
+    env_logger::Builder::new()
+        .filter_module("inertpad", log::LevelFilter::Info)
+        .parse_default_env()
+        .init();
+
+    config::apply_env_aliases();
+    // Applied first, for the command line to override it. Named files
+    // must exist, the default one may be missing.
+    let mut config_files = config::explicit_paths();
+    if config_files.is_empty() {
+        config_files.extend(config::default_path().filter(|path| path.exists()));
+    }
+    let preset_env = config::preset_env_vars(&Args::command());
+    let mut device_blocks = config::DeviceBlocks::default();
+    for path in &config_files {
+        match config::apply_file(Args::command(), path, &preset_env) {
+            Ok(applied) => {
+                log::info!("Loaded {} options from {}", applied.options, path.display());
+                for (key, env) in applied.overridden {
+                    log::info!("{} overrides `{}` of {}", env, key, path.display());
+                }
+                device_blocks.merge(applied.device_blocks);
+            }
+            Err(e) => {
+                log::error!("Invalid config file {}: {}", path.display(), e);
+                return Failure::Config.into();
+            }
+        }
+    }
+    if config_files.len() > 1 {
+        let order: Vec<_> = config_files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        log::info!(
+            "Merged config files in order, later ones overriding: {}",
+            order.join(", ")
+        );
+    }
+    let (mut args, mut matches) = match parse_args() {
+        Ok(parsed) => parsed,
+        Err(code) => return code,
+    };
+    if args.list_devices {
+        list_devices();
+        return ExitCode::SUCCESS;
+    }
+    if args.dump_config {
+        print!(
+            "{}",
+            config::to_toml(Args::command(), &matches, &device_blocks)
+        );
+        return ExitCode::SUCCESS;
+    }
+    // The block of the device capture is going to open goes over the
+    // files, and the command line is parsed again on top of it
+    let mut device_block = None;
+    let probed = (!device_blocks.is_empty() && !args.check_config)
+        .then(|| probe_device_name(&args))
+        .flatten();
+    if let Some((name, block)) = probed.and_then(|probed| device_blocks.find(&probed)) {
+        match config::apply_device_block(Args::command(), block, &preset_env) {
+            Ok(applied) => {
+                log::debug!(
+                    "Loaded {} options from config block [device.{:?}]",
+                    applied.options,
+                    name
+                );
+                for (key, env) in applied.overridden {
+                    log::info!("{} overrides `{}` of [device.{:?}]", env, key, name);
+                }
+            }
+            Err(e) => {
+                log::error!("Invalid config block [device.{:?}]: {}", name, e);
+                return Failure::Config.into();
+            }
+        }
+        (args, matches) = match parse_args() {
+            Ok(parsed) => parsed,
+            Err(code) => return code,
+        };
+        device_block = Some(name.to_owned());
+    }
+    log_session_type();
+    if let Some(modes) = args.finger_modes() {
+        let mut listed = [false; MAX_FINGERS];
+        for entry in &args.finger_modes {
+            if mem::replace(&mut listed[usize::from(entry.fingers) - 1], true) {
+                log::error!(
+                    "--finger-modes lists {} fingers more than once",
+                    entry.fingers
+                );
+                return Failure::Config.into();
+            }
+        }
+        let modes: Vec<_> = (1..)
+            .zip(modes)
+            .filter_map(|(fingers, mode)| {
+                let name = mode.to_possible_value()?;
+                Some(format!("{}={}", fingers, name.get_name()))
+            })
+            .collect();
+        log::info!("Inertia modes by finger count: {}", modes.join(", "));
+        let commands = args
+            .finger_modes
+            .iter()
+            .any(|entry| entry.mode == FingerMode::Command);
+        if commands && args.fling_command.is_none() {
+            log::error!("The `command` finger mode requires --fling-command");
+            return Failure::Config.into();
+        }
+    }
+    let mut listed = [false; MAX_FINGERS];
+    for entry in &args.finger_drag {
+        if mem::replace(&mut listed[usize::from(entry.fingers) - 1], true) {
+            log::error!(
+                "--finger-drag lists {} fingers more than once",
+                entry.fingers
+            );
+            return Failure::Config.into();
+        }
+    }
+    if args.check_config {
+        for name in config::unknown_env_vars(&Args::command()) {
+            log::warn!("Unknown environment variable {}", name);
+        }
+        let params = effective_params(&args, &matches);
+        warn_if_invisible(&params, args.inertia_as.into());
+        print!("{}", config::params_toml(&params));
+        return ExitCode::SUCCESS;
+    }
+    let (sender, receiver) = mpsc::channel();
+    let benchmark = args
+        .benchmark_capture
+        .then(|| Arc::new(Mutex::new(CaptureBenchmark::default())));
+    let probe = args
+        .probe_rate
+        .then(|| Arc::new(Mutex::new(RateProbe::default())));
+    if args.probe_rate && args.source != Source::Touchpad {
+        log::error!("--probe-rate only applies to touchpads");
+        return Failure::Config.into();
+    }
+    let speeds = Arc::new(SpeedHistory::new(ADAPTIVE_WINDOW));
+    let speeds_path = args.adaptive_threshold.and_then(|_| adaptive::state_path());
+    if let Some(path) = &speeds_path {
+        if args.forget {
+            match fs::remove_file(path) {
+                Ok(()) => log::info!("Learned flick strength is forgotten"),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!("Failed to remove {}: {}", path.display(), e),
+            }
+        } else {
+            match speeds.load(path) {
+                Ok(count) => log::info!("Restored {} learned lift-off speeds", count),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!(
+                    "Ignoring learned state {}, starting afresh: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+    }
+    // Set up before spawning any threads, see `on_termination`
+    let shutdown_sender = sender.clone();
+    let shutdown_benchmark = benchmark.clone();
+    let shutdown_speeds = speeds_path.map(|path| (path, speeds.clone()));
+    let result = shutdown::on_termination(move |_| {
+        if let Some(benchmark) = shutdown_benchmark {
+            benchmark.lock().unwrap().log_summary();
+        }
+        if let Some((path, speeds)) = shutdown_speeds {
+            if let Err(e) = speeds.save(&path) {
+                log::warn!("Failed to save learned state to {}: {}", path.display(), e);
+            }
+        }
+        let (done, finished) = mpsc::channel();
+        // Unanswered if the emulation isn't running, e.g. with `--on-flick`
+        if shutdown_sender
+            .send(MomentumMessage::Shutdown(done))
+            .is_ok()
+        {
+            let _ = finished.recv_timeout(SHUTDOWN_TIMEOUT);
+        }
+    });
+    if let Err(e) = result {
+        log::error!("Failed to set up signal handling: {}", e);
+    }
+    let params = Arc::new(LiveParams::new(effective_params(&args, &matches)));
+    warn_if_invisible(&params.load(), args.inertia_as.into());
+    let session = Arc::new(SessionState::default());
+    let mut hooks = GestureHooks::builder();
+    if args.end_pulse {
+        match pulse::EndPulse::open() {
+            Some(pulse) => hooks = hooks.on_glide_ended(move |_| pulse.play()),
+            None => log::warn!("No force-feedback rumble device found, --end-pulse has no effect"),
+        }
+    }
+    let control = match ControlState::new(
+        EmulationHandle {
+            sender: sender.clone(),
+        },
+        params.clone(),
+        RunInfo {
+            mode: args.mode_description(),
+            metrics: args.metrics_addr.is_some(),
+        },
+        hooks.build(),
+    ) {
+        Ok(control) => Arc::new(control),
+        Err(e) => {
+            log::error!("Failed to set up control state: {}", e);
+            return Failure::Other.into();
+        }
+    };
+    match args.control_socket.clone().or_else(control::default_path) {
+        Some(path) => {
+            if let Err(e) = control::spawn(&path, control.clone()) {
+                log::error!("Failed to bind control socket {}: {}", path.display(), e);
+            }
+        }
+        None => log::info!("XDG_RUNTIME_DIR is not set, control socket is disabled"),
+    }
+    if let Some(addr) = args.metrics_addr {
+        if let Err(e) = metrics::spawn(addr, control.clone()) {
+            log::error!("Failed to bind metrics endpoint {}: {}", addr, e);
+        }
+    }
+    if args.respect_idle {
+        #[cfg(feature = "dbus")]
+        if let Err(e) = idle::spawn_monitor(session.clone(), sender.clone()) {
+            log::error!("Failed to monitor session state: {}", e);
+        }
+        #[cfg(not(feature = "dbus"))]
+        log::warn!("--respect-idle requires InertPad to be built with the `dbus` feature");
+    }
+    let precision = Arc::new(PrecisionState::new(args.precision_drag_mult));
+    if let Some(modifier) = args.precision_modifier {
+        if let Err(e) = precision::spawn_watcher(modifier, "precision", precision.modifier.clone())
+        {
+            log::error!("Failed to watch precision modifier: {}", e);
+        }
+    }
+    let suppress = Arc::new(ModifierState::default());
+    if let Some(modifier) = args.suppress_modifier {
+        if let Err(e) = precision::spawn_watcher(modifier, "suppress", suppress.clone()) {
+            log::error!("Failed to watch suppress modifier: {}", e);
+        }
+    }
+    let focus = Arc::new(FocusState::default());
+    let app_filter = AppFilter {
+        apps: args.inertia_apps.clone(),
+        exclude: args.inertia_exclude.clone(),
+    };
+    if !app_filter.is_empty() {
+        if std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland") {
+            log::warn!("Focus detection is unavailable on Wayland, --inertia-apps and --inertia-exclude are ignored");
+        } else {
+            #[cfg(feature = "x11")]
+            if let Err(e) = focus::spawn_tracker(app_filter, focus.clone()) {
+                log::error!("Failed to track the focused window: {}", e);
+            }
+            #[cfg(not(feature = "x11"))]
+            log::warn!("--inertia-apps and --inertia-exclude require InertPad to be built with the `x11` feature");
+        }
+    }
+    if args.tui {
+        #[cfg(feature = "tui")]
+        tui::spawn(params.clone(), control.clone());
+        #[cfg(not(feature = "tui"))]
+        log::warn!("--tui requires InertPad to be built with the `tui` feature");
+    }
+
+    if let Some(seconds) = args.keep_awake {
+        if args.on_flick.is_some() {
+            log::warn!("--keep-awake needs a virtual mouse, ignoring it with --on-flick");
+        } else {
+            spawn_keep_awake(sender.clone(), time::Duration::from_secs(seconds));
+        }
+    }
+    if args.verify_device {
+        // No input is needed, as verification exits before any glide
+        return run_virtual_mouse(
+            &args,
+            None,
+            receiver,
+            params,
+            &control,
+            &precision,
+            |_, _| {},
+        );
+    }
+    if args.test_pattern {
+        let interval = time::Duration::from_millis(args.test_pattern_interval_ms);
+        // Along the vertical axis only if the horizontal one is disabled
+        let velocity = match args.axes {
+            Axes::Y => (0.0, args.max_launch_speed),
+            _ => (args.max_launch_speed, 0.0),
+        };
+        log::info!(
+            "Emitting a test pattern every {:?} at {} units/s, input is ignored",
+            interval,
+            args.max_launch_speed
+        );
+        let sender = sender.clone();
+        return run_virtual_mouse(
+            &args,
+            None,
+            receiver,
+            params,
+            &control,
+            &precision,
+            move |_, _| spawn_test_pattern(sender, velocity, interval),
+        );
+    }
+    let source = args.source;
+    let source_name = match source {
+        Source::Touchpad => "touchpad",
+        Source::Wheel => "wheel device",
+    };
+    let device_query = args.device.clone().or_else(|| args.device_path.clone());
+    let found = match &device_query {
+        // Reported here, as reconnect attempts only log it at debug level
+        Some(query) => match Touchpad::open(query) {
+            Ok(touchpad) => Some(touchpad),
+            Err(e) => {
+                log::error!("No {} matches {}: {}", source_name, query, e);
+                return Failure::NoDevice.into();
+            }
+        },
+        None => find_source(source, args.match_strategy, args.select, None),
+    };
+    match found {
+        None => {
+            log::error!("No {} found!", source_name);
+            Failure::NoDevice.into()
+        }
+        Some(mut touchpad) => {
+            log::info!(
+                "Found {}: {} ({})",
+                source_name,
+                touchpad.device().name().unwrap_or_default(),
+                touchpad.path().display()
+            );
+            if let Some(name) = &device_block {
+                let opened = touchpad.device().name().unwrap_or_default();
+                if config::same_device(name, opened) {
+                    log::info!("Applied config block [device.{:?}]", name);
+                } else {
+                    log::warn!(
+                        "Applied config block [device.{:?}], but opened {:?}",
+                        name,
+                        opened
+                    );
+                }
+            }
+            prepare_touchpad(&mut touchpad, args.read_only);
+            let capture_params = params.clone();
+            let capture_options = args.capture_options();
+            let capture_control = control.clone();
+            let capture_probe = probe.clone();
+            let match_strategy = args.match_strategy;
+            let select = args.select;
+            let device_query = device_query.clone();
+            let read_only = args.read_only;
+            let no_reconnect = args.no_reconnect;
+            let frame_period = Emulator::frame_period(args.refresh_rate);
+            // The virtual mouse is owned by the emulation side, so restarting
+            // the capture never tears it down.
+            let spawn_capture = move |mut touchpad: Touchpad, vmouse_fd: Option<RawFd>| {
+                let vmouse_note = vmouse_fd
+                    .map(|fd| format!(" (virtual mouse fd {} stays open)", fd))
+                    .unwrap_or_default();
+                thread::spawn(move || loop {
+                    capture_control
+                        .capture_connected
+                        .store(true, Ordering::Relaxed);
+                    capture_control.set_device(DeviceInfo {
+                        name: touchpad.device().name().unwrap_or_default().to_owned(),
+                        path: touchpad.path().to_owned(),
+                        driver_version: touchpad.device().driver_version(),
+                    });
+                    let context = CaptureContext {
+                        params: &capture_params,
+                        options: &capture_options,
+                        session: &session,
+                        focus: &focus,
+                        suppress: &suppress,
+                        control: &capture_control,
+                        benchmark: benchmark.as_deref(),
+                        probe: capture_probe.as_deref(),
+                        speeds: &speeds,
+                    };
+                    let request = match source {
+                        Source::Touchpad => run_capture(&mut touchpad, &sender, context),
+                        Source::Wheel => {
+                            let result = wheel::run_capture(
+                                touchpad.device_mut(),
+                                &sender,
+                                context,
+                                frame_period,
+                            );
+                            if let Err(e) = result {
+                                log::error!("Failed to read wheel events: {}", e);
+                            }
+                            None
+                        }
+                    };
+                    if let Some(request) = request {
+                        let _ = sender.send(MomentumMessage::StopMovement);
+                        let reply = match inertpad::open_device(&request.query) {
+                            Ok((path, device)) => {
+                                let name = device.name().unwrap_or_default().to_owned();
+                                log::info!("Switching to {} ({})", name, path.display());
+                                touchpad = Touchpad::new(path, device);
+                                prepare_touchpad(&mut touchpad, read_only);
+                                format!("ok {}", name)
+                            }
+                            Err(e) => {
+                                log::warn!("Failed to switch to {}: {}", request.query, e);
+                                format!("error {}", e)
+                            }
+                        };
+                        let _ = request.reply.send(reply);
+                        continue;
+                    }
+                    capture_control
+                        .capture_connected
+                        .store(false, Ordering::Relaxed);
+                    let _ = sender.send(MomentumMessage::StopMovement);
+                    if no_reconnect {
+                        log::error!("Capture stopped, exiting as --no-reconnect is set");
+                        let (done, finished) = mpsc::channel();
+                        if sender.send(MomentumMessage::Shutdown(done)).is_ok() {
+                            let _ = finished.recv_timeout(SHUTDOWN_TIMEOUT);
+                        }
+                        std::process::exit(Failure::NoDevice as i32);
+                    }
+                    log::warn!(
+                        "Capture stopped, waiting for the {}{}",
+                        source_name,
+                        vmouse_note
+                    );
+                    touchpad =
+                        wait_for_source(source, match_strategy, select, device_query.as_deref());
+                    log::info!(
+                        "Found {}: {}, capture restarted",
+                        source_name,
+                        touchpad.device().name().unwrap_or_default()
+                    );
+                    prepare_touchpad(&mut touchpad, read_only);
+                });
+            };
+            if let Some(probe) = probe {
+                log::info!(
+                    "Probing the report rate for {:?}, use the touchpad as usual",
+                    PROBE_TIME
+                );
+                spawn_capture(touchpad, None);
+                thread::sleep(PROBE_TIME);
+                let probe = probe.lock().unwrap();
+                probe.log_summary();
+                let Some(rate) = probe.report_rate() else {
+                    log::error!("No touchpad reports seen, touch the touchpad while probing");
+                    return Failure::Other.into();
+                };
+                log::info!("The touchpad reports at {:.1} Hz", rate);
+                println!("--refresh-rate {:.0}", rate);
+                return ExitCode::SUCCESS;
+            }
+            if let Some(template) = &args.on_flick {
+                log::info!(
+                    "Running `{}` on flicks, no virtual mouse is created",
+                    template
+                );
+                spawn_capture(touchpad, None);
+                run_flick_commands(receiver, template);
+                return Failure::Other.into();
+            }
+            run_virtual_mouse(
+                &args,
+                Some(touchpad),
+                receiver,
+                params,
+                &control,
+                &precision,
+                |touchpad, vmouse_fd| {
+                    if let Some(touchpad) = touchpad {
+                        spawn_capture(touchpad, Some(vmouse_fd));
+                    }
+                },
+            )
+        }
+    }
+}
+
+/// Creates the virtual mouse and runs the emulation on the calling thread
+///
+/// `source` is the captured device, mimicked with `--clone-source` if it's
+/// a touchpad. `spawn_input` gets it back to start feeding the emulation
+/// once the virtual mouse exists.
+fn run_virtual_mouse(
+    args: &Args,
+    source: Option<Touchpad>,
+    receiver: mpsc::Receiver<MomentumMessage>,
+    params: Arc<LiveParams>,
+    control: &ControlState,
+    precision: &PrecisionState,
+    spawn_input: impl FnOnce(Option<Touchpad>, RawFd),
+) -> ExitCode {
+    let clone_source = source
+        .as_ref()
+        .filter(|_| args.source == Source::Touchpad)
+        .map(Touchpad::device);
+    let wheel_routing = (args.source == Source::Wheel).then_some(Routing {
+        x: InertiaSink::Scroll,
+        y: InertiaSink::Scroll,
+    });
+    let routes: Vec<_> = std::iter::once(args.routing())
+        .chain(
+            args.finger_modes
+                .iter()
+                .filter_map(|entry| entry.mode.routing()),
+        )
+        .chain(wheel_routing)
+        .collect();
+    if args.clone_source && clone_source.is_none() {
+        // A wheel device clone would be found as the wheel device itself on reconnect
+        log::warn!("--clone-source only applies to touchpads, ignoring it");
+    }
+    let custom_axis = args.rel_axis.map(|axis| {
+        log::info!(
+            "Delivering inertial movement on {:?} from the {:?} velocity",
+            axis,
+            args.rel_axis_component
+        );
+        CustomAxis {
+            axis: axis.into(),
+            component: args.rel_axis_component,
+        }
+    });
+    let mut sink = args.sink;
+    if sink == SinkKind::Wayland && (args.clone_source || custom_axis.is_some()) {
+        log::warn!("--clone-source and --rel-axis need a uinput device, ignoring --sink wayland");
+        sink = SinkKind::Uinput;
+    }
+    let create_vmouse = || {
+        if let Some(fd) = args.uinput_fd {
+            return VirtualMouse::inherited(fd, &routes, args.axes, args.scroll_step, custom_axis);
+        }
+        if sink == SinkKind::Wayland {
+            match VirtualMouse::wayland(&routes, args.axes, args.scroll_step) {
+                Ok(vmouse) => {
+                    log::info!("Emitting through the Wayland virtual pointer protocol");
+                    return Ok(vmouse);
+                }
+                Err(e) => log::warn!(
+                    "Failed to create a Wayland virtual pointer, falling back to uinput: {}",
+                    e
+                ),
+            }
+        }
+        if let Some(device) = clone_source.filter(|_| args.clone_source) {
+            VirtualMouse::clone_of(
+                device,
+                &args.vmouse_props,
+                &routes,
+                args.axes,
+                args.scroll_step,
+                custom_axis,
+            )
+        } else {
+            VirtualMouse::new(
+                args.vmouse_kind,
+                &args.vmouse_props,
+                &routes,
+                args.axes,
+                args.scroll_step,
+                custom_axis,
+            )
+        }
+    };
+    if args.startup_delay_ms > 0 {
+        log::info!(
+            "Waiting {} ms before creating the virtual mouse device",
+            args.startup_delay_ms
+        );
+        thread::sleep(time::Duration::from_millis(args.startup_delay_ms));
+    }
+    match VirtualMouse::new_with_retry(args.vmouse_retries, args.vmouse_retry_delay, create_vmouse)
+    {
+        Err(e) => {
+            log::error!("Failed to create virtual mouse device: {}", e);
+            Failure::Uinput.into()
+        }
+        Ok(mut vmouse) => {
+            let vmouse_fd = vmouse.device().as_raw_fd();
+            log::info!("Virtual mouse device is created (fd {})", vmouse_fd);
+            if args.stamp_events && !matches!(vmouse.device(), OutputDevice::Wayland(_)) {
+                log::warn!(
+                    "uinput replaces event timestamps with its own, --stamp-events has no effect"
+                );
+            }
+            let pointer_axes = [
+                evdev::RelativeAxisType::REL_X,
+                evdev::RelativeAxisType::REL_Y,
+            ];
+            if args.keep_awake.is_some()
+                && !pointer_axes
+                    .iter()
+                    .any(|&axis| vmouse.rel_axes().contains(axis))
+            {
+                log::warn!(
+                    "--keep-awake needs pointer axes, which --inertia-as and --axes leave out"
+                );
+            }
+            if args.verify_device {
+                return match vmouse.verify(args.vmouse_kind) {
+                    Ok(()) => ExitCode::SUCCESS,
+                    Err(e) => {
+                        log::error!("Virtual mouse device verification failed: {}", e);
+                        Failure::Uinput.into()
+                    }
+                };
+            }
+            for &kind in &args.mirror_sinks {
+                match vmouse.add_mirror(kind, &args.vmouse_props) {
+                    Ok(()) => log::info!("{:?} mirror device is created", kind),
+                    Err(e) => {
+                        log::error!("Failed to create {:?} mirror device: {}", kind, e);
+                        return Failure::Uinput.into();
+                    }
+                }
+            }
+            spawn_input(source, vmouse_fd);
+            Emulator::new(vmouse).run_emulation(
+                receiver,
+                params,
+                &args.emulation_options(),
+                control,
+                precision,
+            );
+            Failure::Other.into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evdev::{AbsoluteAxisType, EventType, InputEvent, Key, RelativeAxisType};
+    use fixture::Fixture;
+    use std::os::{fd::OwnedFd, unix::net::UnixStream};
+
+    impl Fixture {
+        /// Runs the emulation over a socket standing in for uinput while
+        /// `feed` sends its messages, until the last glide ends, returning
+        /// the emitted reports
+        ///
+        /// Once `feed` returns, the remaining frames run back to back, as
+        /// waiting for the next message returns right away.
+        fn emulate(&self, feed: impl FnOnce(&mpsc::Sender<MomentumMessage>)) -> Vec<Report> {
+            let (sender, receiver) = mpsc::channel();
+            let (output, mut input) = UnixStream::pair().unwrap();
+            let bytes = thread::scope(|scope| {
+                let reader = scope.spawn(move || {
+                    let mut bytes = Vec::new();
+                    input.read_to_end(&mut bytes).unwrap();
+                    bytes
+                });
+                scope.spawn(move || {
+                    let device = OutputDevice::Inherited(fs::File::from(OwnedFd::from(output)));
+                    let args = &self.args;
+                    let vmouse = VirtualMouse::from_device(
+                        device,
+                        &[args.routing()],
+                        args.axes,
+                        args.scroll_step,
+                        None,
+                    );
+                    Emulator::new(vmouse).run_emulation(
+                        receiver,
+                        self.params.clone(),
+                        &args.emulation_options(),
+                        &self.control,
+                        &self.precision,
+                    );
+                });
+                feed(&sender);
+                drop(sender);
+                reader.join().unwrap()
+            });
+            let mut reports = vec![Vec::new()];
+            for chunk in bytes.chunks_exact(mem::size_of::<libc::input_event>()) {
+                // SAFETY: the chunk holds a whole `input_event` as written
+                let event: libc::input_event =
+                    unsafe { std::ptr::read_unaligned(chunk.as_ptr().cast()) };
+                if event.type_ == EventType::SYNCHRONIZATION.0 {
+                    reports.push(Vec::new());
+                } else {
+                    reports.last_mut().unwrap().push(InputEvent::from(event));
+                }
+            }
+            reports.pop();
+            reports
+        }
+    }
+
+    /// Events of an emitted report, its SYN_REPORT left out
+    type Report = Vec<InputEvent>;
+
+    fn rel(report: &Report, axis: RelativeAxisType) -> i32 {
+        report
+            .iter()
+            .filter(|event| event.event_type() == EventType::RELATIVE && event.code() == axis.0)
+            .map(|event| event.value())
+            .sum()
+    }
+
+    /// Pointer movement of each report
+    fn moves(reports: &[Report]) -> Vec<(i32, i32)> {
+        reports
+            .iter()
+            .map(|report| {
+                (
+                    rel(report, RelativeAxisType::REL_X),
+                    rel(report, RelativeAxisType::REL_Y),
+                )
+            })
+            .filter(|&movement| movement != (0, 0))
+            .collect()
+    }
+
+    /// Pointer movement of all reports
+    fn distance(reports: &[Report]) -> (i32, i32) {
+        moves(reports)
+            .iter()
+            .fold((0, 0), |(x, y), (dx, dy)| (x + dx, y + dy))
+    }
+
+    fn launch(vx: f64, vy: f64) -> MomentumMessage {
+        MomentumMessage::StartMovement {
+            vx,
+            vy,
+            interval: None,
+            routing: None,
+            chained: false,
+            fingers: 0,
+        }
+    }
+
+    /// Feeds events to the capture state machine, returning the messages sent
+    fn play(
+        state: &mut CaptureState,
+        events: impl IntoIterator<Item = InputEvent>,
+    ) -> Vec<MomentumMessage> {
+        let mut sent = Vec::new();
+        state.handle_batch(events, &mut |message| sent.push(message));
+        sent
+    }
+
+    fn launches(messages: &[MomentumMessage]) -> Vec<(f64, f64)> {
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                MomentumMessage::StartMovement { vx, vy, .. } => Some((*vx, *vy)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Event stamped `ms` milliseconds into the test
+    fn event(ms: u64, type_: EventType, code: u16, value: i32) -> InputEvent {
+        fixture::event(time::Duration::from_millis(ms), type_, code, value)
+    }
+
+    fn key(ms: u64, key: Key, value: i32) -> InputEvent {
+        event(ms, EventType::KEY, key.code(), value)
+    }
+
+    fn abs(ms: u64, axis: AbsoluteAxisType, value: i32) -> InputEvent {
+        event(ms, EventType::ABSOLUTE, axis.0, value)
+    }
+
+    fn syn(ms: u64) -> InputEvent {
+        event(ms, EventType::SYNCHRONIZATION, 0, 0)
+    }
+
+    /// Single-finger contact, built up report by report
+    struct Touch {
+        ms: u64,
+        position: (i32, i32),
+        events: Vec<InputEvent>,
+    }
+
+    impl Touch {
+        /// Touches down with `tool`, the finger count key, at `position`
+        fn down(ms: u64, tool: Key, position: (i32, i32)) -> Self {
+            let events = vec![
+                key(ms, Key::BTN_TOUCH, 1),
+                key(ms, tool, 1),
+                abs(ms, AbsoluteAxisType::ABS_X, position.0),
+                abs(ms, AbsoluteAxisType::ABS_Y, position.1),
+                syn(ms),
+            ];
+            Self {
+                ms,
+                position,
+                events,
+            }
+        }
+
+        /// Moves by `step` in each of `reports` reports, `interval` ms apart
+        fn swipe(mut self, step: (i32, i32), reports: u32, interval: u64) -> Self {
+            for _ in 0..reports {
+                self.ms += interval;
+                self.position = (self.position.0 + step.0, self.position.1 + step.1);
+                self.events.extend([
+                    abs(self.ms, AbsoluteAxisType::ABS_X, self.position.0),
+                    abs(self.ms, AbsoluteAxisType::ABS_Y, self.position.1),
+                    syn(self.ms),
+                ]);
+            }
+            self
+        }
+
+        /// Loses the events of a move by `step`, as on a buffer overrun with
+        /// evdev's resync reporting the new position after SYN_DROPPED
+        fn drop_move(mut self, step: (i32, i32), interval: u64) -> Self {
+            self.ms += interval;
+            self.position = (self.position.0 + step.0, self.position.1 + step.1);
+            self.events.extend([
+                event(self.ms, EventType::SYNCHRONIZATION, 3, 0),
+                abs(self.ms, AbsoluteAxisType::ABS_X, self.position.0),
+                abs(self.ms, AbsoluteAxisType::ABS_Y, self.position.1),
+                syn(self.ms),
+            ]);
+            self
+        }
+
+        /// Reports key changes `after` ms past the last report
+        fn keys(mut self, after: u64, keys: &[(Key, i32)]) -> Self {
+            self.ms += after;
+            let ms = self.ms;
+            self.events
+                .extend(keys.iter().map(|&(code, value)| key(ms, code, value)));
+            self.events.push(syn(ms));
+            self
+        }
+
+        /// Lifts `tool` off `after` ms past the last report
+        fn lift(mut self, tool: Key, after: u64) -> Vec<InputEvent> {
+            self.ms += after;
+            self.events.extend([
+                key(self.ms, Key::BTN_TOUCH, 0),
+                key(self.ms, tool, 0),
+                syn(self.ms),
+            ]);
+            self.events
+        }
+    }
+
+    /// Fast single-finger flick starting at `ms`, lifted 60 ms later
+    fn flick(ms: u64) -> Vec<InputEvent> {
+        Touch::down(ms, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((100, 0), 5, 10)
+            .lift(Key::BTN_TOOL_FINGER, 10)
+    }
+
+    /// Two-finger touch released at `ms`
+    fn two_finger_release(ms: u64) -> Vec<InputEvent> {
+        Touch::down(ms - 100, Key::BTN_TOOL_DOUBLETAP, (1000, 1000))
+            .swipe((10, 10), 3, 10)
+            .lift(Key::BTN_TOOL_DOUBLETAP, 70)
+    }
+
+    #[test]
+    fn lift_off_within_multitouch_cooldown_is_suppressed() {
+        let fixture = Fixture::new(&["--multitouch-cooldown", "300"]);
+        let mut state = fixture.capture();
+        assert!(launches(&play(&mut state, two_finger_release(1000))).is_empty());
+        // Lifted 160 ms after the two fingers
+        assert!(launches(&play(&mut state, flick(1100))).is_empty());
+    }
+
+    #[test]
+    fn lift_off_past_multitouch_cooldown_launches() {
+        let fixture = Fixture::new(&["--multitouch-cooldown", "300"]);
+        let mut state = fixture.capture();
+        assert!(launches(&play(&mut state, two_finger_release(1000))).is_empty());
+        // Lifted 310 ms after the two fingers
+        assert_eq!(launches(&play(&mut state, flick(1250))).len(), 1);
+    }
+
+    #[test]
+    fn cooldown_taper_scales_the_launch() {
+        let flags = ["--multitouch-cooldown", "300", "--cooldown-taper-ms", "100"];
+        // A flick lifted off `ms` after the two fingers
+        let lifted_after = |ms: u64| {
+            let fixture = Fixture::new(&flags);
+            let mut state = fixture.capture();
+            play(&mut state, two_finger_release(1000));
+            launches(&play(&mut state, flick(940 + ms)))
+        };
+        // Suppressed until the taper starts 200 ms after the two fingers
+        assert!(lifted_after(190).is_empty());
+        // Half way into the taper, at half of the 10000 units/s flick
+        let tapered = lifted_after(250);
+        assert_eq!(tapered.len(), 1);
+        assert!((tapered[0].0 - 5000.0).abs() < 1.0);
+        let full = lifted_after(310);
+        assert!((full[0].0 - 10000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn flick_with_suppress_modifier_held_is_suppressed() {
+        let fixture = Fixture::new(&["--suppress-modifier", "ctrl"]);
+        let mut state = fixture.capture();
+        fixture.suppress.press();
+        assert!(launches(&play(&mut state, flick(1000))).is_empty());
+        // Released, the next flick launches again
+        fixture.suppress.release();
+        assert_eq!(launches(&play(&mut state, flick(2000))).len(), 1);
+    }
+
+    /// Whether the second of two flicks lifted `apart` ms apart chains onto
+    /// the first, with the flags
+    fn second_flick_chains(flags: &[&str], apart: u64) -> bool {
+        let fixture = Fixture::new(flags);
+        let mut state = fixture.capture();
+        play(&mut state, flick(1000));
+        let sent = play(&mut state, flick(1000 + apart));
+        match sent[..] {
+            [.., MomentumMessage::StartMovement { chained, .. }] => chained,
+            _ => panic!("second flick didn't launch"),
+        }
+    }
+
+    #[test]
+    fn flick_within_chain_window_chains() {
+        assert!(second_flick_chains(&["--chain-window-ms", "500"], 300));
+    }
+
+    #[test]
+    fn flick_past_chain_window_replaces_the_glide() {
+        assert!(!second_flick_chains(&["--chain-window-ms", "500"], 600));
+        // Without a window flicks never chain
+        assert!(!second_flick_chains(&[], 300));
+    }
+
+    /// Arming decision of the lift-off ending `events`, the last report
+    fn decision(state: &mut CaptureState, mut events: Vec<InputEvent>) -> LiftOff {
+        let lift = events.split_off(events.len() - 3);
+        play(state, events);
+        state.timestamp = lift[0].timestamp();
+        state.decide(&state.measure_flick())
+    }
+
+    #[test]
+    fn lift_off_decisions_name_the_reason() {
+        let decide = |flags: &[&str], setup: &dyn Fn(&Fixture), events: Vec<InputEvent>| {
+            let fixture = Fixture::new(flags);
+            setup(&fixture);
+            let range = AbsRange {
+                min: 0,
+                max: 4000,
+                resolution: 40,
+            };
+            let mut state =
+                CaptureState::new(fixture.context(), (Some(range), Some(range)), None, None);
+            decision(&mut state, events)
+        };
+        let idle = |_: &Fixture| {};
+        assert!(matches!(
+            decide(&[], &idle, flick(1000)),
+            LiftOff::Armed { speed } if (speed - 10000.0).abs() < 1.0
+        ));
+        let disabled = |fixture: &Fixture| fixture.control.enabled.store(false, Ordering::Relaxed);
+        assert!(matches!(
+            decide(&[], &disabled, flick(1000)),
+            LiftOff::Disabled
+        ));
+        let held = |fixture: &Fixture| fixture.suppress.press();
+        assert!(matches!(
+            decide(&["--suppress-modifier", "ctrl"], &held, flick(1000)),
+            LiftOff::ModifierHeld
+        ));
+        let dragging = Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .keys(10, &[(Key::BTN_LEFT, 1)])
+            .swipe((100, 0), 5, 10)
+            .lift(Key::BTN_TOOL_FINGER, 10);
+        assert!(matches!(decide(&[], &idle, dragging), LiftOff::ButtonHeld));
+        assert!(matches!(
+            decide(
+                &["--multitouch-cooldown", "0"],
+                &idle,
+                one_two_one_fingers()
+            ),
+            LiftOff::TooManyFingers { fingers: 2 }
+        ));
+        // Touched down 1000 units, a quarter of the touchpad, from its edges
+        assert!(matches!(
+            decide(&["--edge-margin", "0.3"], &idle, flick(1000)),
+            LiftOff::EdgeSwipe
+        ));
+        assert!(matches!(
+            decide(&["--min-contact-ms", "100"], &idle, flick(1000)),
+            LiftOff::ShortContact { contact } if contact.as_millis() == 60
+        ));
+        let mut cooled = two_finger_release(1000);
+        cooled.extend(flick(1100));
+        assert!(matches!(
+            decide(&["--multitouch-cooldown", "300"], &idle, cooled),
+            LiftOff::Cooldown {
+                cause: Cooldown::Multitouch,
+                elapsed
+            } if elapsed.as_millis() == 160
+        ));
+        let slow = Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((1, 0), 5, 10)
+            .lift(Key::BTN_TOOL_FINGER, 10);
+        assert!(matches!(
+            decide(&[], &idle, slow),
+            LiftOff::BelowThreshold { speed, .. } if (speed - 100.0).abs() < 1.0
+        ));
+    }
+
+    #[test]
+    fn short_contact_does_not_launch() {
+        let fixture = Fixture::new(&["--min-contact-ms", "80"]);
+        let mut state = fixture.capture();
+        // A 10000 units/s flick-tap, down for 40 ms
+        let tap = Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((100, 0), 3, 10)
+            .lift(Key::BTN_TOOL_FINGER, 10);
+        assert!(launches(&play(&mut state, tap)).is_empty());
+        // The same flick held down for 90 ms
+        let held = Touch::down(2000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((100, 0), 8, 10)
+            .lift(Key::BTN_TOOL_FINGER, 10);
+        assert_eq!(launches(&play(&mut state, held)).len(), 1);
+    }
+
+    #[test]
+    fn rotation_maps_x_launch_to_y() {
+        let launched = |flags: &[&str]| {
+            let fixture = Fixture::new(flags);
+            let mut state = fixture.capture();
+            launches(&play(&mut state, flick(1000)))[0]
+        };
+        let (vx, vy) = launched(&["--rotate-deg", "90"]);
+        assert!(vx.abs() < 1e-6);
+        assert!((vy - 10000.0).abs() < 1.0);
+        let (vx, vy) = launched(&["--rotate-deg", "-90"]);
+        assert!(vx.abs() < 1e-6);
+        assert!((vy + 10000.0).abs() < 1.0);
+        assert_eq!(launched(&["--rotate-deg", "0"]), launched(&[]));
+    }
+
+    /// Launch of a 1000 Hz swipe over `reports` reports, alternating
+    /// between 6 and 14 units a report for an average of 10000 units/s
+    fn noisy_launch(flags: &[&str], reports: u32) -> f64 {
+        let fixture = Fixture::new(flags);
+        let mut state = fixture.capture();
+        let touch = (0..reports).fold(
+            Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000)),
+            |touch, report| touch.swipe((if report % 2 == 0 { 6 } else { 14 }, 0), 1, 1),
+        );
+        let events = touch.lift(Key::BTN_TOOL_FINGER, 1);
+        launches(&play(&mut state, events))[0].0
+    }
+
+    #[test]
+    fn report_smoothing_stabilizes_noisy_launches() {
+        // Unsmoothed, the launch is whichever of the deltas came last
+        assert_eq!(noisy_launch(&[], 40), 14000.0);
+        assert_eq!(noisy_launch(&[], 41), 6000.0);
+        let smoothed: Vec<_> = (40..44)
+            .map(|reports| noisy_launch(&["--report-smoothing", "0.3"], reports))
+            .collect();
+        let (low, high) = smoothed
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(low, high), &v| {
+                (low.min(v), high.max(v))
+            });
+        assert!(low > 8500.0 && high < 10500.0);
+        assert!(high - low < 300.0);
+    }
+
+    #[test]
+    fn glide_frame_limit_is_bounded() {
+        let period = time::Duration::from_millis(2);
+        assert_eq!(
+            Emulator::glide_frame_limit(period, time::Duration::ZERO),
+            Emulator::MAX_GLIDE_FRAMES
+        );
+        assert_eq!(
+            Emulator::glide_frame_limit(period, time::Duration::from_millis(100)),
+            100
+        );
+        assert_eq!(
+            Emulator::glide_frame_limit(period, time::Duration::from_secs(3600)),
+            Emulator::MAX_GLIDE_FRAMES
+        );
+    }
+
+    #[test]
+    fn zero_drag_glide_ends() {
+        let fixture = Fixture::new(&[
+            "--drag",
+            "0",
+            "--refresh-rate",
+            "500",
+            "--max-glide-ms",
+            "100",
+        ]);
+        let reports = fixture.emulate(|sender| sender.send(launch(2000.0, 0.0)).unwrap());
+        let moves = moves(&reports);
+        assert!(!moves.is_empty());
+        // Never decelerating, the glide moves 15 px every frame, until twice
+        // the 50 frames of the max glide duration
+        assert!(moves.iter().all(|&movement| movement == (15, 0)));
+        assert!(moves.len() <= 100);
+    }
+
+    /// Reports of a never decelerating glide shut down 50 ms in, after the
+    /// shutdown was answered
+    fn shut_down_glide(flags: &[&str]) -> Vec<Report> {
+        let flags = [&["--drag", "0", "--refresh-rate", "500"], flags].concat();
+        Fixture::new(&flags).emulate(|sender| {
+            sender.send(launch(2000.0, 0.0)).unwrap();
+            thread::sleep(time::Duration::from_millis(50));
+            let (done, answered) = mpsc::channel();
+            sender.send(MomentumMessage::Shutdown(done)).unwrap();
+            answered
+                .recv_timeout(time::Duration::from_secs(1))
+                .expect("shutdown wasn't answered");
+        })
+    }
+
+    #[test]
+    fn shutdown_mid_glide_ends_with_an_empty_report() {
+        let reports = shut_down_glide(&[]);
+        let (last, glide) = reports.split_last().unwrap();
+        assert!(moves(glide).len() > 1);
+        assert!(moves(glide).iter().all(|&movement| movement == (15, 0)));
+        assert!(last.is_empty());
+    }
+
+    #[test]
+    fn shutdown_drops_the_motion_in_flight() {
+        // The 15 px frames pile up short of the minimum emitted movement
+        let reports = shut_down_glide(&["--min-emit-px", "100000"]);
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].is_empty());
+        // Movement deferred past the frame cap isn't flushed either
+        let reports = shut_down_glide(&["--max-frame-delta", "5"]);
+        let (last, glide) = reports.split_last().unwrap();
+        assert!(moves(glide).iter().all(|&movement| movement == (5, 0)));
+        assert!(last.is_empty());
+    }
+
+    #[test]
+    fn runaway_glide_enters_safe_mode() {
+        let fixture = Fixture::new(&[
+            "--drag",
+            "0",
+            "--safe-mode-threshold",
+            "100",
+            "--safe-mode-pause",
+        ]);
+        let flick = |sender: &mpsc::Sender<_>| sender.send(launch(2000.0, 0.0)).unwrap();
+        // Never decelerating, stopped by the 7th 15 px frame past 100 px
+        let moves = moves(&fixture.emulate(flick));
+        assert_eq!(moves.len(), 7);
+        assert!(fixture.control.safe_mode.load(Ordering::Relaxed));
+        // Paused, further flicks are ignored
+        assert!(fixture.emulate(flick).is_empty());
+    }
+
+    #[test]
+    fn glide_within_safe_mode_threshold_runs_its_course() {
+        let fixture = Fixture::new(&["--safe-mode-threshold", "1000", "--safe-mode-pause"]);
+        let reports = fixture.emulate(|sender| sender.send(launch(3000.0, 0.0)).unwrap());
+        assert_eq!(distance(&reports), (149, 0));
+        assert!(!fixture.control.safe_mode.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn non_finite_launch_is_dropped() {
+        let fixture = Fixture::new(&[]);
+        let reports = fixture.emulate(|sender| {
+            sender.send(launch(f64::NAN, 0.0)).unwrap();
+            sender.send(launch(3000.0, 0.0)).unwrap();
+        });
+        // The finite flick after it glides as usual
+        assert_eq!(distance(&reports), (149, 0));
+        assert!(!fixture.control.safe_mode.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn non_finite_movement_stops_the_glide() {
+        // Finite as a velocity, but overflowing as a movement
+        let fixture = Fixture::new(&["--speed-factor", "10"]);
+        let reports = fixture.emulate(|sender| sender.send(launch(1e308, 0.0)).unwrap());
+        // Dropped on the first frame, rather than gliding on at infinity
+        assert!(moves(&reports).is_empty());
+    }
+
+    #[test]
+    fn strict_enters_safe_mode_on_non_finite_values() {
+        let fixture = Fixture::new(&["--strict"]);
+        let reports = fixture.emulate(|sender| {
+            sender.send(launch(f64::INFINITY, 0.0)).unwrap();
+            sender.send(launch(3000.0, 0.0)).unwrap();
+        });
+        assert!(fixture.control.safe_mode.load(Ordering::Relaxed));
+        assert!(moves(&reports).is_empty());
+    }
+
+    #[test]
+    fn virtual_mouse_advertises_its_capabilities() {
+        use RelativeAxisType as Rel;
+        // Creating a device needs write access to uinput, as on CI machines
+        // set up for it
+        if fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/uinput")
+            .is_err()
+        {
+            eprintln!("/dev/uinput isn't writable, skipping");
+            return;
+        }
+        let modes: [(&[&str], &[Rel]); 3] = [
+            (&[], &[Rel::REL_X, Rel::REL_Y]),
+            (
+                &["--inertia-as", "scroll"],
+                &[
+                    Rel::REL_WHEEL,
+                    Rel::REL_WHEEL_HI_RES,
+                    Rel::REL_HWHEEL,
+                    Rel::REL_HWHEEL_HI_RES,
+                ],
+            ),
+            (
+                &["--inertia-as", "scroll", "--scroll-step", "120"],
+                &[Rel::REL_WHEEL, Rel::REL_HWHEEL],
+            ),
+        ];
+        for (flags, axes) in modes {
+            let args = Fixture::new(flags).args;
+            let mut vmouse = VirtualMouse::new(
+                args.vmouse_kind,
+                &args.vmouse_props,
+                &[args.routing()],
+                args.axes,
+                args.scroll_step,
+                None,
+            )
+            .unwrap();
+            let expected: evdev::AttributeSet<_> = axes.iter().copied().collect();
+            assert_eq!(
+                vmouse.rel_axes().iter().collect::<Vec<_>>(),
+                expected.iter().collect::<Vec<_>>(),
+                "{:?}",
+                flags
+            );
+            // Re-opened as the compositor sees it, with the name, IDs and keys
+            vmouse.verify(args.vmouse_kind).unwrap();
+        }
+    }
+
+    /// Distance of a 3000 units/s flick with the rounding, where the frame
+    /// deltas add up to 150 px
+    fn rounded_distance(rounding: &str) -> i32 {
+        let fixture = Fixture::new(&["--rounding", rounding]);
+        distance(&fixture.emulate(|sender| sender.send(launch(3000.0, 0.0)).unwrap())).0
+    }
+
+    /// Distance and frame count of a 3000 units/s flick with the drag flags
+    fn drag_glide(drag: &[&str]) -> (i32, usize) {
+        let reports =
+            Fixture::new(drag).emulate(|sender| sender.send(launch(3000.0, 0.0)).unwrap());
+        (distance(&reports).0, moves(&reports).len())
+    }
+
+    #[test]
+    fn drag_curve_changes_glide_length() {
+        let constant = drag_glide(&["--drag", "0.15"]);
+        assert_eq!(drag_glide(&["--drag-curve", "0.15:0.15"]), constant);
+        // The glide travels speed * speed_factor / drag, 150 px at 0.15
+        let (heavy, heavy_frames) = drag_glide(&["--drag", "0.3"]);
+        let (light, light_frames) = drag_glide(&["--drag", "0.05"]);
+        assert!((70..=75).contains(&heavy));
+        assert!((440..=450).contains(&light));
+        // A curve glides further than its heavier drag and less far than
+        // its lighter one
+        let (snappy, snappy_frames) = drag_glide(&["--drag-curve", "0.3:0.05"]);
+        let (braking, braking_frames) = drag_glide(&["--drag-curve", "0.05:0.3"]);
+        for (distance, frames) in [(snappy, snappy_frames), (braking, braking_frames)] {
+            assert!(heavy < distance && distance < light);
+            assert!(heavy_frames < frames && frames < light_frames);
+        }
+        // A snappy start sheds most of the speed early, then coasts on past
+        // the constant drag's glide, which a braking end cuts short
+        assert!(snappy_frames > constant.1);
+        assert!(braking_frames < constant.1);
+        assert!(braking > snappy);
+    }
+
+    #[test]
+    fn resistance_moves_against_the_flick() {
+        let fixture = Fixture::new(&["--resistance"]);
+        let reports = fixture.emulate(|sender| sender.send(launch(3000.0, -2000.0)).unwrap());
+        let moves = moves(&reports);
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|&(x, y)| x <= 0 && y >= 0));
+        // A nudge, well short of the 75 px of the flick's glide at the drag
+        // of the counter-motion
+        let (x, y) = distance(&reports);
+        assert!(x < 0 && y > 0);
+        assert!(x > -10 && y < 10);
+    }
+
+    #[test]
+    fn max_frame_delta_preserves_distance() {
+        let flick = |sender: &mpsc::Sender<_>| sender.send(launch(6000.0, 8000.0)).unwrap();
+        let free = Fixture::new(&[]).emulate(flick);
+        let capped = Fixture::new(&["--max-frame-delta", "20"]).emulate(flick);
+        let length = |(x, y): (i32, i32)| f64::from(x).hypot(f64::from(y));
+        // The first frame alone would move 75 px
+        assert!(moves(&free)
+            .into_iter()
+            .any(|movement| length(movement) > 20.0));
+        assert!(moves(&capped)
+            .into_iter()
+            .all(|movement| length(movement) <= 20.0));
+        // Spread over more of the fast frames
+        let fast = |reports: &[Report]| {
+            moves(reports)
+                .into_iter()
+                .filter(|&movement| length(movement) > 10.0)
+                .count()
+        };
+        assert!(fast(&capped) > fast(&free));
+        let (free_x, free_y) = distance(&free);
+        let (capped_x, capped_y) = distance(&capped);
+        assert!((free_x - capped_x).abs() <= 1 && (free_y - capped_y).abs() <= 1);
+    }
+
+    #[test]
+    fn snap_to_octant_rounds_to_nearest_45_degrees() {
+        let snapped = |degrees: f64| {
+            let (sin, cos) = degrees.to_radians().sin_cos();
+            let (x, y) = Emulator::snap_to_octant(2000.0 * cos, 2000.0 * sin);
+            assert!((x.hypot(y) - 2000.0).abs() < 1e-9);
+            y.atan2(x).to_degrees()
+        };
+        assert!(snapped(20.0).abs() < 1e-9);
+        assert!((snapped(30.0) - 45.0).abs() < 1e-9);
+        assert!((snapped(-160.0) + 180.0).abs() < 1e-9);
+        assert!((snapped(100.0) - 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn park_speed_ends_the_glide_early() {
+        let flick = |sender: &mpsc::Sender<_>| sender.send(launch(3000.0, 0.0)).unwrap();
+        let full = moves(&Fixture::new(&[]).emulate(flick));
+        let parked = moves(&Fixture::new(&["--park-speed", "1000"]).emulate(flick));
+        // 3000 units/s keeps 0.85 of the speed a frame, dropping below
+        // 1000 units/s on the 8th frame, which emits nothing
+        assert_eq!(parked.len(), 7);
+        assert_eq!(parked[..], full[..7]);
+        // Every frame emitted moves more than the 7.5 px of the park speed
+        assert!(parked.iter().all(|&(x, _)| x >= 8));
+        assert!(full.len() > parked.len());
+    }
+
+    /// First frame and distance of a rightward glide superseded by
+    /// `second` with `--blend-flicks` at `weight`
+    fn blended_glide(weight: &str, second: (f64, f64)) -> (Option<(i32, i32)>, (i32, i32)) {
+        let reports = Fixture::new(&["--blend-flicks", weight]).emulate(|sender| {
+            // Both arrive before the first frame, which keeps all of the
+            // first glide's velocity to blend
+            sender.send(launch(3000.0, 0.0)).unwrap();
+            sender.send(launch(second.0, second.1)).unwrap();
+        });
+        (moves(&reports).first().copied(), distance(&reports))
+    }
+
+    #[test]
+    fn blend_flicks_adds_the_glide_velocity() {
+        let up = (0.0, 3000.0);
+        // A frame moves 22.5 px at 3000 units/s
+        assert_eq!(blended_glide("0", up), (Some((0, 22)), (0, 149)));
+        assert_eq!(blended_glide("0.5", up), (Some((11, 22)), (74, 149)));
+        assert_eq!(blended_glide("1", up), (Some((22, 22)), (149, 149)));
+        let right = (3000.0, 0.0);
+        assert_eq!(blended_glide("0.5", right), (Some((33, 0)), (224, 0)));
+        assert_eq!(blended_glide("1", right), (Some((45, 0)), (299, 0)));
+        // An opposite flick cancels out the glide
+        let left = (-3000.0, 0.0);
+        assert_eq!(blended_glide("0.5", left), (Some((-11, 0)), (-74, 0)));
+        assert_eq!(blended_glide("1", left), (None, (0, 0)));
+    }
+
+    #[test]
+    fn predicted_latency_leads_the_first_frame() {
+        let glide = |latency: &str| {
+            let fixture = Fixture::new(&["--refresh-rate", "100", "--predict-latency-ms", latency]);
+            moves(&fixture.emulate(|sender| sender.send(launch(3000.0, 0.0)).unwrap()))
+        };
+        let plain = glide("0");
+        assert_eq!(plain[0], (22, 0));
+        // 20 ms are two 10 ms frames of 22.5 px ahead
+        let predicted = glide("20");
+        assert_eq!(predicted[0], (67, 0));
+        assert_eq!(predicted[1..], plain[1..]);
+        // Capped at three frames ahead
+        assert_eq!(glide("1000")[0], (90, 0));
+    }
+
+    #[test]
+    fn scroll_accel_ramps_up_with_rapid_flicks() {
+        let mut accel = ScrollAccel::new(4.0);
+        let start = time::Instant::now();
+        let at = |ms| start + time::Duration::from_millis(ms);
+        assert_eq!(accel.flick(at(0)), 1.0);
+        // Right after the first, nothing of its boost has worn off
+        assert_eq!(accel.flick(at(0)), 1.5);
+        assert_eq!(accel.flick(at(0)), 2.25);
+        assert_eq!(accel.flick(at(0)), 3.375);
+        assert_eq!(accel.flick(at(0)), 4.0);
+        assert_eq!(accel.flick(at(0)), 4.0);
+    }
+
+    #[test]
+    fn scroll_accel_decays_between_flicks() {
+        let mut accel = ScrollAccel::new(4.0);
+        let start = time::Instant::now();
+        let at = |ms| start + time::Duration::from_millis(ms);
+        accel.flick(at(0));
+        accel.flick(at(0));
+        accel.flick(at(0));
+        // A half-life later half of the 1.25 beyond 1 is left, then stepped
+        assert!((accel.flick(at(400)) - 1.625 * 1.5).abs() < 1e-9);
+        // Less is left after a longer pause
+        let mut quick = ScrollAccel { ..accel };
+        let boost = accel.flick(at(1100));
+        assert!(boost > 1.5 && boost < quick.flick(at(500)));
+        // A pause past the window starts over
+        assert_eq!(accel.flick(at(2000)), 1.0);
+    }
+
+    #[test]
+    fn rounding_modes_emit_distance() {
+        let trunc = rounded_distance("trunc");
+        let round = rounded_distance("round");
+        let stochastic = rounded_distance("stochastic");
+        let accumulate = rounded_distance("accumulate");
+        // Truncation loses a fraction every frame
+        assert!(trunc < 140);
+        assert!((145..=150).contains(&round));
+        assert!((140..=160).contains(&stochastic));
+        assert!((149..=150).contains(&accumulate));
+    }
+
+    #[test]
+    fn flush_remainder_emits_the_analytic_distance() {
+        let flick = |sender: &mpsc::Sender<_>| sender.send(launch(3000.0, -1800.0)).unwrap();
+        for rounding in ["trunc", "round", "accumulate"] {
+            let fixture = Fixture::new(&["--rounding", rounding, "--flush-remainder"]);
+            let (x, y) = distance(&fixture.emulate(flick));
+            // speed * speed_factor / drag on each axis
+            assert!((x - 150).abs() <= 1, "{}: {}", rounding, x);
+            assert!((y + 90).abs() <= 1, "{}: {}", rounding, y);
+        }
+        // Truncating without the flush falls well short
+        let (x, _) = distance(&Fixture::new(&["--rounding", "trunc"]).emulate(flick));
+        assert!(x < 149);
+    }
+
+    /// One-finger swipe joined by a second finger for a while
+    fn one_two_one_fingers() -> Vec<InputEvent> {
+        Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((100, 0), 3, 10)
+            .keys(
+                10,
+                &[(Key::BTN_TOOL_FINGER, 0), (Key::BTN_TOOL_DOUBLETAP, 1)],
+            )
+            .swipe((100, 0), 2, 10)
+            .keys(
+                10,
+                &[(Key::BTN_TOOL_DOUBLETAP, 0), (Key::BTN_TOOL_FINGER, 1)],
+            )
+            .swipe((100, 0), 5, 10)
+            .lift(Key::BTN_TOOL_FINGER, 10)
+    }
+
+    #[test]
+    fn second_finger_during_swipe_disarms_pointer_inertia() {
+        let fixture = Fixture::new(&["--multitouch-cooldown", "0"]);
+        let mut state = fixture.capture();
+        assert!(launches(&play(&mut state, one_two_one_fingers())).is_empty());
+    }
+
+    #[test]
+    fn pointer_max_fingers_allows_second_finger() {
+        let fixture = Fixture::new(&["--multitouch-cooldown", "0", "--pointer-max-fingers", "2"]);
+        let mut state = fixture.capture();
+        let mut events = one_two_one_fingers();
+        let lift = events.split_off(events.len() - 3);
+        play(&mut state, events);
+        assert_eq!(launches(&play(&mut state, lift)).len(), 1);
+    }
+
+    /// Sum of a relative axis over all reports
+    fn axis_total(reports: &[Report], axis: RelativeAxisType) -> i32 {
+        reports.iter().map(|report| rel(report, axis)).sum()
+    }
+
+    #[test]
+    fn scroll_step_emits_a_click_per_step() {
+        let flick = |sender: &mpsc::Sender<_>| sender.send(launch(0.0, 3000.0)).unwrap();
+        let scroll = ["--inertia-as", "scroll", "--scroll-speed-factor", "0.12"];
+        let smooth = Fixture::new(&scroll).emulate(flick);
+        // The frame deltas add up to 2400 high-resolution units
+        let hi_res = axis_total(&smooth, RelativeAxisType::REL_WHEEL_HI_RES);
+        assert!((2395..=2400).contains(&hi_res));
+        let stepped =
+            Fixture::new(&[&scroll[..], &["--scroll-step", "60"]].concat()).emulate(flick);
+        assert_eq!(
+            axis_total(&stepped, RelativeAxisType::REL_WHEEL),
+            hi_res / 60
+        );
+        assert_eq!(axis_total(&stepped, RelativeAxisType::REL_WHEEL_HI_RES), 0);
+    }
+
+    #[test]
+    fn scroll_reversal_drops_the_glide_velocity() {
+        let scroll = Fixture::new(&["--inertia-as", "scroll", "--blend-flicks", "1"]);
+        let wheel = |reports: Vec<Report>| -> Vec<i32> {
+            reports
+                .iter()
+                .map(|report| rel(report, RelativeAxisType::REL_WHEEL_HI_RES))
+                .collect()
+        };
+        let down = wheel(scroll.emulate(|sender| sender.send(launch(0.0, 3000.0)).unwrap()));
+        // A down flick superseding an up glide, whose velocity would
+        // otherwise be blended in
+        let reversed = wheel(scroll.emulate(|sender| {
+            sender.send(launch(0.0, -3000.0)).unwrap();
+            sender.send(launch(0.0, 3000.0)).unwrap();
+        }));
+        assert_eq!(reversed, down);
+        assert!(!reversed.is_empty());
+        // Nothing of the up glide is left
+        assert!(
+            reversed.iter().all(|&delta| delta >= 0) || reversed.iter().all(|&delta| delta <= 0)
+        );
+    }
+
+    #[test]
+    fn overscroll_decelerates_faster_past_the_distance() {
+        let wheel = |flags: &[&str]| -> Vec<i32> {
+            let scroll = [
+                &["--inertia-as", "scroll", "--scroll-speed-factor", "0.12"],
+                flags,
+            ]
+            .concat();
+            Fixture::new(&scroll)
+                .emulate(|sender| sender.send(launch(0.0, 3000.0)).unwrap())
+                .iter()
+                .map(|report| rel(report, RelativeAxisType::REL_WHEEL_HI_RES).abs())
+                .filter(|&delta| delta != 0)
+                .collect()
+        };
+        let free = wheel(&[]);
+        let damped = wheel(&["--scroll-overscroll-distance", "1200"]);
+        // The same up to the distance, then short of the free glide's 2400
+        let within = free
+            .iter()
+            .scan(0, |scrolled, &delta| {
+                *scrolled += delta;
+                Some(*scrolled)
+            })
+            .take_while(|&scrolled| scrolled <= 1200)
+            .count();
+        assert!(within > 0);
+        assert_eq!(damped[..within], free[..within]);
+        let total = |deltas: &[i32]| deltas.iter().sum::<i32>();
+        assert!(total(&damped) > 1200 && total(&damped) < total(&free) - 100);
+        assert!(damped.len() < free.len());
+    }
+
+    #[test]
+    fn movement_across_a_time_gap_is_not_measured() {
+        let fixture = Fixture::new(&[]);
+        let mut state = fixture.capture();
+        // 3000 units over 600 ms would be a 5000 units/s flick
+        let events = Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((10, 0), 2, 10)
+            .swipe((3000, 0), 1, 600)
+            .lift(Key::BTN_TOOL_FINGER, 10);
+        assert!(launches(&play(&mut state, events)).is_empty());
+    }
+
+    #[test]
+    fn swipe_after_a_time_gap_measures_from_the_gap() {
+        let fixture = Fixture::new(&[]);
+        let mut state = fixture.capture();
+        let events = Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((10, 0), 2, 10)
+            .swipe((3000, 0), 1, 600)
+            .swipe((100, 0), 3, 10)
+            .lift(Key::BTN_TOOL_FINGER, 10);
+        let launches = launches(&play(&mut state, events));
+        assert_eq!(launches.len(), 1);
+        // 100 units per 10 ms report, with nothing of the gap in it
+        assert!((launches[0].0 - 10000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn movement_across_dropped_events_is_not_measured() {
+        let fixture = Fixture::new(&[]);
+        let mut state = fixture.capture();
+        let events = Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((10, 0), 2, 10)
+            .drop_move((3000, 0), 10)
+            .lift(Key::BTN_TOOL_FINGER, 10);
+        assert!(launches(&play(&mut state, events)).is_empty());
+    }
+
+    #[test]
+    fn swipe_after_dropped_events_measures_from_the_resync() {
+        let fixture = Fixture::new(&[]);
+        let mut state = fixture.capture();
+        let events = Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((10, 0), 2, 10)
+            .drop_move((3000, 0), 10)
+            .swipe((100, 0), 3, 10)
+            .lift(Key::BTN_TOOL_FINGER, 10);
+        let launches = launches(&play(&mut state, events));
+        assert_eq!(launches.len(), 1);
+        // 100 units per 10 ms report, the 3000 unit jump left out
+        assert!((launches[0].0 - 10000.0).abs() < 1.0);
+    }
+
+    /// Launches of a flick delivered in batches, split before the events
+    /// at `splits`
+    fn batched_flick(splits: impl Iterator<Item = usize>) -> Vec<(f64, f64)> {
+        let fixture = Fixture::new(&[]);
+        let mut state = fixture.capture();
+        let mut rest = flick(1000);
+        let mut batches: Vec<_> = splits
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .map(|split| rest.split_off(split))
+            .collect();
+        batches.push(rest);
+        batches
+            .into_iter()
+            .rev()
+            .flat_map(|batch| launches(&play(&mut state, batch)))
+            .collect()
+    }
+
+    #[test]
+    fn reports_are_measured_regardless_of_batches() {
+        let single = batched_flick(std::iter::empty());
+        assert_eq!(single.len(), 1);
+        assert!((single[0].0 - 10000.0).abs() < 1.0);
+        // The touch-down takes 5 events, each report after it 3
+        let len = flick(1000).len();
+        // Each report in a batch of its own
+        assert_eq!(batched_flick((5..len).step_by(3)), single);
+        // Reports split across batches, between their axes
+        assert_eq!(batched_flick((6..len).step_by(3)), single);
+    }
+
+    #[test]
+    fn lift_off_measures_movement_of_its_report() {
+        let fixture = Fixture::new(&[]);
+        let mut state = fixture.capture();
+        let mut events = Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+            .swipe((100, 0), 4, 10)
+            .lift(Key::BTN_TOOL_FINGER, 10);
+        // The last move comes with the lift-off, after its keys
+        let at = events.len() - 1;
+        events.insert(at, abs(1050, AbsoluteAxisType::ABS_X, 1700));
+        let launches = launches(&play(&mut state, events));
+        assert_eq!(launches.len(), 1);
+        // 300 units in the last 10 ms, rather than the 100 of the reports
+        // before it
+        assert!((launches[0].0 - 30000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn touch_down_reported_by_slots_stops_the_glide() {
+        let fixture = Fixture::new(&[]);
+        let slots = Slots {
+            contact: true,
+            ..Slots::default()
+        };
+        let mut state = CaptureState::new(fixture.context(), (None, None), None, Some(slots));
+        let sent = play(
+            &mut state,
+            [
+                abs(1000, AbsoluteAxisType::ABS_MT_SLOT, 0),
+                abs(1000, AbsoluteAxisType::ABS_MT_TRACKING_ID, 7),
+                abs(1000, AbsoluteAxisType::ABS_MT_POSITION_X, 1000),
+                abs(1000, AbsoluteAxisType::ABS_MT_POSITION_Y, 1000),
+                syn(1000),
+            ],
+        );
+        assert!(matches!(sent[..], [MomentumMessage::StopMovement]));
+    }
+
+    /// Scripted swipe of the golden corpus, replayed through the capture
+    /// with `flags` and then through the emulation with them
+    struct Recording {
+        name: &'static str,
+        flags: &'static [&'static str],
+        swipe: fn() -> Vec<InputEvent>,
+    }
+
+    /// Swipes whose emitted motion is locked against the traces of
+    /// `tests/golden`, one `<name>.txt` per swipe
+    ///
+    /// When a change alters the feel on purpose, the traces are regenerated
+    /// with `BLESS=1 cargo test golden` and the diff reviewed.
+    const CORPUS: &[Recording] = &[
+        Recording {
+            name: "flick",
+            flags: &[],
+            swipe: || flick(1000),
+        },
+        Recording {
+            name: "diagonal",
+            flags: &[],
+            swipe: || {
+                Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 3000))
+                    .swipe((60, -40), 8, 8)
+                    .lift(Key::BTN_TOOL_FINGER, 8)
+            },
+        },
+        Recording {
+            name: "accelerating",
+            flags: &[],
+            swipe: || {
+                Touch::down(1000, Key::BTN_TOOL_FINGER, (500, 1000))
+                    .swipe((10, 0), 5, 10)
+                    .swipe((40, 5), 3, 10)
+                    .swipe((120, 15), 3, 10)
+                    .lift(Key::BTN_TOOL_FINGER, 10)
+            },
+        },
+        Recording {
+            name: "low-drag",
+            flags: &["--drag", "0.05", "--speed-factor", "0.01"],
+            swipe: || {
+                Touch::down(1000, Key::BTN_TOOL_FINGER, (3000, 1000))
+                    .swipe((-80, 20), 6, 10)
+                    .lift(Key::BTN_TOOL_FINGER, 10)
+            },
+        },
+        Recording {
+            // Dragging with the button held, which doesn't glide
+            name: "button-drag",
+            flags: &[],
+            swipe: || {
+                Touch::down(1000, Key::BTN_TOOL_FINGER, (1000, 1000))
+                    .keys(10, &[(Key::BTN_LEFT, 1)])
+                    .swipe((100, 0), 5, 10)
+                    .lift(Key::BTN_TOOL_FINGER, 10)
+            },
+        },
+    ];
+
+    /// Largest difference of a frame's movement from its golden one, per
+    /// axis, in pixels
+    const GOLDEN_TOLERANCE: i32 = 1;
+
+    /// Emitted movement of a corpus swipe
+    fn replay(recording: &Recording) -> Vec<(i32, i32)> {
+        let fixture = Fixture::new(recording.flags);
+        let mut state = fixture.capture();
+        let sent = play(&mut state, (recording.swipe)());
+        drop(state);
+        moves(&fixture.emulate(|sender| {
+            for message in sent {
+                sender.send(message).unwrap();
+            }
+        }))
+    }
+
+    fn golden_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name)
+            .with_extension("txt")
+    }
+
+    /// Trace of one `dx dy` line per emitted frame
+    fn parse_trace(text: &str) -> Vec<(i32, i32)> {
+        text.lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (x, y) = line.split_once(' ').expect("a frame is `dx dy`");
+                (x.parse().unwrap(), y.parse().unwrap())
+            })
+            .collect()
+    }
+
+    fn write_trace(path: &Path, moves: &[(i32, i32)]) {
+        let mut text = String::from("# dx dy of each emitted frame\n");
+        for (x, y) in moves {
+            text.push_str(&format!("{} {}\n", x, y));
+        }
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn golden_corpus_replays_within_tolerance() {
+        let bless = std::env::var_os("BLESS").is_some();
+        for recording in CORPUS {
+            let moves = replay(recording);
+            let path = golden_path(recording.name);
+            if bless {
+                write_trace(&path, &moves);
+                continue;
+            }
+            let text = fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("{}: {}, regenerate with BLESS=1", path.display(), e));
+            let golden = parse_trace(&text);
+            assert_eq!(
+                moves.len(),
+                golden.len(),
+                "{}: {} frames instead of the golden {}",
+                recording.name,
+                moves.len(),
+                golden.len()
+            );
+            for (frame, (movement, expected)) in moves.iter().zip(&golden).enumerate() {
+                assert!(
+                    (movement.0 - expected.0).abs() <= GOLDEN_TOLERANCE
+                        && (movement.1 - expected.1).abs() <= GOLDEN_TOLERANCE,
+                    "{}: frame {} moved {:?} instead of the golden {:?}",
+                    recording.name,
+                    frame,
+                    movement,
+                    expected
+                );
             }
         }
     }
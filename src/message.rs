@@ -0,0 +1,39 @@
+//! Messages driving the emulation of inertial movement
+
+use crate::Routing;
+use std::{sync::mpsc, time};
+
+/// Messages sent from touchpad capture to movement emulation
+#[derive(Debug)]
+pub enum MomentumMessage {
+    /// Starts inertial movement
+    StartMovement {
+        /// Horizontal lift-off velocity in raw touchpad units per second, the
+        /// same units `speed_threshold` is compared against. Any scaling into
+        /// output units is done by the emulation side.
+        vx: f64,
+        /// Vertical lift-off velocity, in the same units as `vx`
+        vy: f64,
+        /// Average input report interval of the swipe, if it was measured
+        /// reliably
+        interval: Option<time::Duration>,
+        /// Routing of the swipe's finger mode, if it replaces the configured one
+        routing: Option<Routing>,
+        /// Whether it chains onto the glide it interrupted, adding that
+        /// glide's momentum
+        chained: bool,
+        /// Most fingers of the swipe, 0 for launches not made by a swipe
+        fingers: u8,
+    },
+    /// Stops inertial movement immediately
+    StopMovement,
+    /// Quickly decays inertial movement, when a finger grabs the glide
+    FingerGrab,
+    /// Brings inertial movement to a stop within the soft catch time
+    SoftCatch,
+    /// Drops any movement in flight and leaves the virtual mouse with
+    /// a final empty report, answered once done
+    Shutdown(mpsc::Sender<()>),
+    /// Nudges the pointer back and forth with `--keep-awake`
+    KeepAwake,
+}
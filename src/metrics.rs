@@ -0,0 +1,209 @@
+//! Prometheus metrics served over HTTP for `--metrics-addr`
+
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread, time,
+};
+
+use crate::control::ControlState;
+
+/// Upper bounds of the launch speed buckets, in touchpad units per second
+const LAUNCH_SPEED_BUCKETS: [f64; 8] = [
+    1000.0, 2000.0, 3000.0, 5000.0, 10000.0, 20000.0, 50000.0, 100000.0,
+];
+
+/// Upper bounds of the glide distance buckets, in pixels or wheel units
+const GLIDE_DISTANCE_BUCKETS: [f64; 9] = [
+    10.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0,
+];
+
+/// Time a client has to send its request
+const REQUEST_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+#[derive(Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    /// Sample counts per bucket, not cumulative, the last one is unbounded
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+        }
+    }
+
+    fn record(&mut self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|&upper| value <= upper)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += value;
+    }
+
+    fn write(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (i, count) in self.counts.iter().enumerate() {
+            cumulative += count;
+            match self.bounds.get(i) {
+                Some(upper) => {
+                    let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, upper, cumulative);
+                }
+                None => {
+                    let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, cumulative);
+                }
+            }
+        }
+        let _ = writeln!(out, "{}_sum {}", name, self.sum);
+        let _ = writeln!(out, "{}_count {}", name, cumulative);
+    }
+}
+
+/// Counters, gauges and histograms of the running daemon
+#[derive(Debug)]
+pub struct Metrics {
+    flicks_armed: AtomicU64,
+    flicks_cooldown: AtomicU64,
+    glides_completed: AtomicU64,
+    /// Current glide speed as `f64` bits, zero between glides
+    speed: AtomicU64,
+    launch_speed: Mutex<Histogram>,
+    glide_distance: Mutex<Histogram>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            flicks_armed: AtomicU64::new(0),
+            flicks_cooldown: AtomicU64::new(0),
+            glides_completed: AtomicU64::new(0),
+            speed: AtomicU64::new(0),
+            launch_speed: Mutex::new(Histogram::new(&LAUNCH_SPEED_BUCKETS)),
+            glide_distance: Mutex::new(Histogram::new(&GLIDE_DISTANCE_BUCKETS)),
+        }
+    }
+
+    /// Counts a lift-off that launched inertial movement
+    pub fn flick_armed(&self) {
+        self.flicks_armed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a lift-off suppressed by one of the cooldowns
+    pub fn flick_cooldown(&self) {
+        self.flicks_cooldown.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the launch speed of a new glide
+    pub fn glide_started(&self, launch_speed: f64) {
+        self.launch_speed.lock().unwrap().record(launch_speed);
+    }
+
+    /// Records a glide that decelerated to a stop on its own
+    pub fn glide_completed(&self, distance: f64) {
+        self.glides_completed.fetch_add(1, Ordering::Relaxed);
+        self.glide_distance.lock().unwrap().record(distance);
+    }
+
+    /// Updates the current glide speed, in touchpad units per second
+    pub fn set_speed(&self, speed: f64) {
+        self.speed.store(speed.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "inertpad_flicks_armed_total",
+                "Lift-offs that launched inertial movement",
+                &self.flicks_armed,
+            ),
+            (
+                "inertpad_flicks_suppressed_cooldown_total",
+                "Lift-offs suppressed by a cooldown",
+                &self.flicks_cooldown,
+            ),
+            (
+                "inertpad_glides_completed_total",
+                "Glides that decelerated to a stop on their own",
+                &self.glides_completed,
+            ),
+        ];
+        for (name, help, counter) in counters {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+        }
+        let _ = writeln!(
+            out,
+            "# HELP inertpad_glide_speed Current glide speed in touchpad units per second"
+        );
+        let _ = writeln!(out, "# TYPE inertpad_glide_speed gauge");
+        let speed = f64::from_bits(self.speed.load(Ordering::Relaxed));
+        let _ = writeln!(out, "inertpad_glide_speed {}", speed);
+        self.launch_speed.lock().unwrap().write(
+            &mut out,
+            "inertpad_launch_speed",
+            "Glide launch speed in touchpad units per second",
+        );
+        self.glide_distance.lock().unwrap().write(
+            &mut out,
+            "inertpad_glide_distance",
+            "Distance of completed glides in pixels or high-resolution wheel units",
+        );
+        out
+    }
+}
+
+/// Binds the metrics endpoint and serves it on a background thread
+pub fn spawn(addr: SocketAddr, state: Arc<ControlState>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    log::info!("Serving metrics on http://{}/metrics", addr);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| serve(stream, &state.metrics));
+            if let Err(e) = result {
+                log::debug!("Metrics request failed: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn serve(stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Skip the headers, nothing in them matters here
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let (status, body) = match request.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+        ["GET", "/metrics"] => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", "Not found, try /metrics\n".to_owned()),
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
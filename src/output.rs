@@ -0,0 +1,714 @@
+//! Virtual devices delivering inertial movement as relative motion and scroll
+
+use crate::GestureKind;
+use anyhow::Result;
+use evdev::uinput;
+use std::{
+    fs, io, mem,
+    os::unix::{
+        ffi::OsStrExt,
+        io::{AsRawFd, FromRawFd, RawFd},
+    },
+    path::Path,
+    thread, time,
+};
+
+/// Axes inertial movement is allowed along
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axes {
+    /// Horizontal movement only
+    X,
+    /// Vertical movement only
+    Y,
+    /// Both horizontal and vertical movement
+    Both,
+}
+
+impl Axes {
+    /// Zeroes velocity components along disallowed axes
+    pub fn filter(self, vx: f64, vy: f64) -> (f64, f64) {
+        match self {
+            Axes::X => (vx, 0.0),
+            Axes::Y => (0.0, vy),
+            Axes::Both => (vx, vy),
+        }
+    }
+}
+
+/// Where the virtual mouse delivers its reports
+pub enum OutputDevice {
+    /// Device created through /dev/uinput
+    Uinput(uinput::VirtualDevice),
+    /// uinput device created by someone else, or any file taking the same
+    /// reports
+    Inherited(fs::File),
+    /// Wayland virtual pointer
+    Wayland(crate::wayland::VirtualPointer),
+}
+
+impl OutputDevice {
+    /// Delivers a report, its SYN_REPORT included
+    pub fn emit(&mut self, events: &[evdev::InputEvent]) -> io::Result<()> {
+        match self {
+            OutputDevice::Uinput(device) => write_report(device.as_raw_fd(), events),
+            OutputDevice::Inherited(file) => write_report(file.as_raw_fd(), events),
+            OutputDevice::Wayland(pointer) => pointer.emit(events),
+        }
+    }
+
+    pub fn as_raw_fd(&self) -> RawFd {
+        match self {
+            OutputDevice::Uinput(device) => device.as_raw_fd(),
+            OutputDevice::Inherited(file) => file.as_raw_fd(),
+            OutputDevice::Wayland(pointer) => pointer.as_raw_fd(),
+        }
+    }
+}
+
+/// Event stamped with the given time, rather than the current one
+pub fn event_at(
+    time: time::SystemTime,
+    type_: evdev::EventType,
+    code: u16,
+    value: i32,
+) -> evdev::InputEvent {
+    let since_epoch = time.duration_since(time::UNIX_EPOCH).unwrap_or_default();
+    evdev::InputEvent::from(libc::input_event {
+        time: libc::timeval {
+            tv_sec: since_epoch.as_secs() as libc::time_t,
+            tv_usec: libc::suseconds_t::from(since_epoch.subsec_micros()),
+        },
+        type_: type_.0,
+        code,
+        value,
+    })
+}
+
+/// `_IOR('U', 45, unsigned int)`, reading the uinput interface version
+const UI_GET_VERSION: libc::c_ulong = 0x8004_552d;
+
+/// Writes a report, its SYN_REPORT included, to a uinput device in a single
+/// write
+///
+/// `VirtualDevice::emit` appends another SYN_REPORT in a write of its own.
+fn write_report(fd: RawFd, events: &[evdev::InputEvent]) -> io::Result<()> {
+    let len = mem::size_of_val(events);
+    // SAFETY: `InputEvent` is a transparent wrapper of the plain
+    // `input_event`, and the slice is valid for its length
+    let written = unsafe { libc::write(fd, events.as_ptr().cast(), len) };
+    match written {
+        -1 => Err(io::Error::last_os_error()),
+        n if n as usize == len => Ok(()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::WriteZero,
+            "uinput accepted only part of the report",
+        )),
+    }
+}
+
+/// Device class of a created virtual mouse
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VirtualMouseKind {
+    /// Plain relative pointer device
+    Mouse,
+    /// Pointer device with touchpad property bits and axes
+    Touchpad,
+}
+
+/// Input property for `--vmouse-props`
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceProp {
+    /// INPUT_PROP_POINTER, needs a pointer on the screen
+    Pointer,
+    /// INPUT_PROP_DIRECT, like a touchscreen
+    Direct,
+    /// INPUT_PROP_BUTTONPAD, buttons under the pad
+    Buttonpad,
+    /// INPUT_PROP_POINTING_STICK, like a TrackPoint
+    PointingStick,
+}
+
+impl From<DeviceProp> for evdev::PropType {
+    fn from(prop: DeviceProp) -> Self {
+        match prop {
+            DeviceProp::Pointer => Self::POINTER,
+            DeviceProp::Direct => Self::DIRECT,
+            DeviceProp::Buttonpad => Self::BUTTONPAD,
+            DeviceProp::PointingStick => Self::POINTING_STICK,
+        }
+    }
+}
+
+/// Component of a two-dimensional velocity
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Component {
+    /// Horizontal
+    X,
+    /// Vertical
+    Y,
+}
+
+/// Relative axis receiving one component of inertial movement
+#[derive(Clone, Copy, Debug)]
+pub struct CustomAxis {
+    pub axis: evdev::RelativeAxisType,
+    pub component: Component,
+}
+
+/// Output receiving a component of inertial movement
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InertiaSink {
+    /// Relative pointer movement
+    Pointer,
+    /// High-resolution wheel scrolling
+    Scroll,
+}
+
+/// Sinks receiving the horizontal and vertical components of inertial movement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Routing {
+    pub x: InertiaSink,
+    pub y: InertiaSink,
+}
+
+impl From<InertiaSink> for GestureKind {
+    fn from(sink: InertiaSink) -> Self {
+        match sink {
+            InertiaSink::Pointer => GestureKind::Pointer,
+            InertiaSink::Scroll => GestureKind::Scroll,
+        }
+    }
+}
+
+/// Emulates mouse device (via uinput) which performs inertial pointer movement
+///
+/// [`VirtualMouse::emit_motion`] delivers the movement of a frame to the
+/// outputs of the current [`Routing`], in a single report.
+pub struct VirtualMouse {
+    device: OutputDevice,
+    /// Routing of the emitted motion, which a glide's finger mode may replace
+    routing: Routing,
+    /// Devices of `--mirror-sinks` receiving the same movement
+    mirrors: Vec<uinput::VirtualDevice>,
+    /// Relative axes registered on the device
+    rel_axes: evdev::AttributeSet<evdev::RelativeAxisType>,
+    /// Accumulated high-resolution wheel units not yet reported as whole clicks
+    wheel_remainder: (i32, i32),
+    /// High-resolution units per emitted click in discrete scroll mode
+    scroll_step: Option<i32>,
+    /// Axis of `--rel-axis` replacing the routed outputs
+    custom_axis: Option<CustomAxis>,
+    /// Frame time stamped on emitted events with `--stamp-events`
+    stamp: Option<time::SystemTime>,
+}
+
+impl VirtualMouse {
+    /// High-resolution wheel units per one legacy wheel click
+    pub const WHEEL_CLICK: i32 = 120;
+
+    /// Name of the virtual mouse device
+    pub const NAME: &'static str = "InertPad Virtual Mouse";
+
+    /// USB vendor and product IDs of the virtual mouse device
+    pub const INPUT_ID: (u16, u16) = (0x1234, 0x5678);
+
+    /// Time udev has to create the device node before verifying it
+    const NODE_TIMEOUT: time::Duration = time::Duration::from_secs(2);
+
+    pub fn new(
+        kind: VirtualMouseKind,
+        props: &[DeviceProp],
+        routes: &[Routing],
+        axes: Axes,
+        scroll_step: Option<i32>,
+        custom_axis: Option<CustomAxis>,
+    ) -> Result<Self> {
+        let device = Self::build(
+            kind,
+            props,
+            Self::NAME,
+            &Self::relative_axes(routes, axes, scroll_step, custom_axis),
+        )?;
+        Ok(Self::from_device(
+            OutputDevice::Uinput(device),
+            routes,
+            axes,
+            scroll_step,
+            custom_axis,
+        ))
+    }
+
+    /// Takes over a uinput device created elsewhere and passed down as `fd`
+    ///
+    /// The descriptor is only owned once it is known to be a writable uinput
+    /// device, so that a failed attempt can be retried.
+    pub fn inherited(
+        fd: RawFd,
+        routes: &[Routing],
+        axes: Axes,
+        scroll_step: Option<i32>,
+        custom_axis: Option<CustomAxis>,
+    ) -> Result<Self> {
+        // SAFETY: plain syscall without pointer arguments
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+        if flags == -1 {
+            let e = io::Error::last_os_error();
+            anyhow::bail!("file descriptor {} is not usable: {}", fd, e);
+        }
+        if flags & libc::O_ACCMODE == libc::O_RDONLY {
+            anyhow::bail!("file descriptor {} is not open for writing", fd);
+        }
+        let mut version: libc::c_uint = 0;
+        // SAFETY: UI_GET_VERSION writes a single `c_uint` to `version`
+        if unsafe { libc::ioctl(fd, UI_GET_VERSION as _, &mut version) } == -1 {
+            anyhow::bail!("file descriptor {} is not a uinput device", fd);
+        }
+        log::debug!("Inherited uinput device, uinput version {:#x}", version);
+        // SAFETY: the descriptor was handed over to this process, and
+        // nothing else in it owns the descriptor
+        let file = unsafe { fs::File::from_raw_fd(fd) };
+        Ok(Self::from_device(
+            OutputDevice::Inherited(file),
+            routes,
+            axes,
+            scroll_step,
+            custom_axis,
+        ))
+    }
+
+    /// Creates a Wayland virtual pointer instead of a uinput device
+    pub fn wayland(routes: &[Routing], axes: Axes, scroll_step: Option<i32>) -> Result<Self> {
+        let pointer = crate::wayland::VirtualPointer::connect()?;
+        Ok(Self::from_device(
+            OutputDevice::Wayland(pointer),
+            routes,
+            axes,
+            scroll_step,
+            None,
+        ))
+    }
+
+    /// Creates an extra virtual device of the given class receiving the
+    /// same movement, for comparing how the compositor treats each
+    pub fn add_mirror(&mut self, kind: VirtualMouseKind, props: &[DeviceProp]) -> Result<()> {
+        let name = format!("{} ({:?} mirror)", Self::NAME, kind);
+        let device = Self::build(kind, props, &name, &self.rel_axes)?;
+        self.mirrors.push(device);
+        Ok(())
+    }
+
+    /// Opens uinput, explaining the common setup problems behind a failure
+    fn open_uinput<'a>() -> Result<uinput::VirtualDeviceBuilder<'a>> {
+        const UINPUT_PATH: &str = "/dev/uinput";
+        uinput::VirtualDeviceBuilder::new().map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound if !Path::new(UINPUT_PATH).exists() => anyhow::anyhow!(
+                "{} does not exist, the uinput kernel module is probably not loaded. \
+                 Load it with `modprobe uinput`, and to load it at boot, \
+                 add `uinput` to a file in /etc/modules-load.d/",
+                UINPUT_PATH
+            ),
+            io::ErrorKind::PermissionDenied => anyhow::anyhow!(
+                "no permission to open {}: {}. Run InertPad as root, \
+                 or grant access to {} with a udev rule",
+                UINPUT_PATH,
+                e,
+                UINPUT_PATH
+            ),
+            _ => anyhow::anyhow!("failed to open {}: {}", UINPUT_PATH, e),
+        })
+    }
+
+    fn build(
+        kind: VirtualMouseKind,
+        props: &[DeviceProp],
+        name: &str,
+        rel_axes: &evdev::AttributeSetRef<evdev::RelativeAxisType>,
+    ) -> Result<uinput::VirtualDevice> {
+        use evdev::{
+            AbsInfo, AbsoluteAxisType, AttributeSet, BusType, InputId, Key, PropType,
+            UinputAbsSetup,
+        };
+        let mut properties: AttributeSet<PropType> =
+            props.iter().map(|&prop| PropType::from(prop)).collect();
+        let mut builder = Self::open_uinput()?
+            .name(name)
+            .input_id(InputId::new(
+                BusType::BUS_USB,
+                Self::INPUT_ID.0,
+                Self::INPUT_ID.1,
+                0,
+            ))
+            .with_relative_axes(rel_axes)?;
+        builder = match kind {
+            VirtualMouseKind::Mouse => {
+                builder.with_keys(&[Key::BTN_LEFT].into_iter().collect::<AttributeSet<_>>())?
+            }
+            VirtualMouseKind::Touchpad => {
+                // Property bits and axes udev uses to tag a device as ID_INPUT_TOUCHPAD
+                let absinfo = AbsInfo::new(0, 0, 4096, 0, 0, 32);
+                builder
+                    .with_keys(
+                        &[Key::BTN_LEFT, Key::BTN_TOOL_FINGER, Key::BTN_TOUCH]
+                            .into_iter()
+                            .collect::<AttributeSet<_>>(),
+                    )?
+                    .with_absolute_axis(&UinputAbsSetup::new(AbsoluteAxisType::ABS_X, absinfo))?
+                    .with_absolute_axis(&UinputAbsSetup::new(AbsoluteAxisType::ABS_Y, absinfo))?
+            }
+        };
+        if kind == VirtualMouseKind::Touchpad {
+            properties.insert(PropType::POINTER);
+        }
+        Ok(builder.with_properties(&properties)?.build()?)
+    }
+
+    /// Creates a virtual device mimicking the source device,
+    /// with relative axes added for inertial movement
+    pub fn clone_of(
+        source: &evdev::Device,
+        props: &[DeviceProp],
+        routes: &[Routing],
+        axes: Axes,
+        scroll_step: Option<i32>,
+        custom_axis: Option<CustomAxis>,
+    ) -> Result<Self> {
+        use evdev::{AbsInfo, AttributeSet, PropType, UinputAbsSetup};
+        let mut properties: AttributeSet<PropType> = source.properties().iter().collect();
+        for &prop in props {
+            properties.insert(prop.into());
+        }
+        let mut builder = Self::open_uinput()?
+            .name(source.name().unwrap_or(Self::NAME))
+            .input_id(source.input_id())
+            .with_properties(&properties)?
+            .with_relative_axes(&Self::relative_axes(routes, axes, scroll_step, custom_axis))?;
+        if let Some(keys) = source.supported_keys() {
+            builder = builder.with_keys(keys)?;
+        }
+        if let Some(axes) = source.supported_absolute_axes() {
+            let state = source.get_abs_state()?;
+            for axis in axes.iter() {
+                let info = &state[axis.0 as usize];
+                let absinfo = AbsInfo::new(
+                    info.value,
+                    info.minimum,
+                    info.maximum,
+                    info.fuzz,
+                    info.flat,
+                    info.resolution,
+                );
+                builder = builder.with_absolute_axis(&UinputAbsSetup::new(axis, absinfo))?;
+            }
+        }
+        Ok(Self::from_device(
+            OutputDevice::Uinput(builder.build()?),
+            routes,
+            axes,
+            scroll_step,
+            custom_axis,
+        ))
+    }
+
+    /// The first of `routes` is the configured one, the others are those
+    /// of finger modes
+    pub fn from_device(
+        device: OutputDevice,
+        routes: &[Routing],
+        axes: Axes,
+        scroll_step: Option<i32>,
+        custom_axis: Option<CustomAxis>,
+    ) -> Self {
+        Self {
+            device,
+            routing: routes[0],
+            mirrors: Vec::new(),
+            rel_axes: Self::relative_axes(routes, axes, scroll_step, custom_axis),
+            wheel_remainder: (0, 0),
+            scroll_step,
+            custom_axis,
+            stamp: None,
+        }
+    }
+
+    /// Reopens the created device through evdev and checks that it
+    /// advertises what `build` registered for `kind`
+    pub fn verify(&mut self, kind: VirtualMouseKind) -> Result<()> {
+        use evdev::{AbsoluteAxisType, Key};
+        let OutputDevice::Uinput(device) = &mut self.device else {
+            anyhow::bail!("only a created uinput device can be verified");
+        };
+        let deadline = time::Instant::now() + Self::NODE_TIMEOUT;
+        let found = loop {
+            let node = device
+                .enumerate_dev_nodes_blocking()?
+                .filter_map(|node| node.ok())
+                .find(|node| {
+                    node.file_name()
+                        .is_some_and(|name| name.as_bytes().starts_with(b"event"))
+                });
+            match node.map(|node| (evdev::Device::open(&node), node)) {
+                Some((Ok(found), node)) => {
+                    log::info!("Verifying virtual mouse device {}", node.display());
+                    break found;
+                }
+                _ if time::Instant::now() < deadline => {
+                    thread::sleep(time::Duration::from_millis(100));
+                }
+                Some((Err(e), node)) => {
+                    anyhow::bail!("failed to open {}: {}", node.display(), e)
+                }
+                None => anyhow::bail!("no device node appeared within {:?}", Self::NODE_TIMEOUT),
+            }
+        };
+        let mut problems = Vec::new();
+        if found.name() != Some(Self::NAME) {
+            problems.push(format!("name is {:?}", found.name().unwrap_or_default()));
+        }
+        let id = found.input_id();
+        if (id.vendor(), id.product()) != Self::INPUT_ID {
+            problems.push(format!("IDs are {:04x}:{:04x}", id.vendor(), id.product()));
+        }
+        let axes = found.supported_relative_axes();
+        for axis in self.rel_axes.iter() {
+            if !axes.is_some_and(|axes| axes.contains(axis)) {
+                problems.push(format!("{:?} is missing", axis));
+            }
+        }
+        let mut keys = vec![Key::BTN_LEFT];
+        let mut abs_axes = vec![];
+        if kind == VirtualMouseKind::Touchpad {
+            keys.extend([Key::BTN_TOOL_FINGER, Key::BTN_TOUCH]);
+            abs_axes.extend([AbsoluteAxisType::ABS_X, AbsoluteAxisType::ABS_Y]);
+        }
+        for key in keys {
+            if !found
+                .supported_keys()
+                .is_some_and(|keys| keys.contains(key))
+            {
+                problems.push(format!("{:?} is missing", key));
+            }
+        }
+        for axis in abs_axes {
+            let supported = found.supported_absolute_axes();
+            if !supported.is_some_and(|axes| axes.contains(axis)) {
+                problems.push(format!("{:?} is missing", axis));
+            }
+        }
+        if !problems.is_empty() {
+            anyhow::bail!("{}", problems.join(", "));
+        }
+        let axes: Vec<_> = self.rel_axes.iter().collect();
+        log::info!("Virtual mouse device advertises {:?} and its keys", axes);
+        Ok(())
+    }
+
+    /// Relative axes needed to emit inertial movement with any of the given
+    /// routes and axes
+    ///
+    /// Discrete scroll mode (`scroll_step` set) leaves out the high-resolution
+    /// wheel axes, since consumers ignore legacy clicks when those are present.
+    /// A custom axis replaces all the others.
+    fn relative_axes(
+        routes: &[Routing],
+        axes: Axes,
+        scroll_step: Option<i32>,
+        custom_axis: Option<CustomAxis>,
+    ) -> evdev::AttributeSet<evdev::RelativeAxisType> {
+        let mut set = evdev::AttributeSet::new();
+        if let Some(custom) = custom_axis {
+            set.insert(custom.axis);
+            return set;
+        }
+        for &routing in routes {
+            for axis in Self::route_axes(routing, axes, scroll_step) {
+                set.insert(axis);
+            }
+        }
+        set
+    }
+
+    fn route_axes(
+        routing: Routing,
+        axes: Axes,
+        scroll_step: Option<i32>,
+    ) -> Vec<evdev::RelativeAxisType> {
+        use evdev::RelativeAxisType;
+        let horizontal: &[_] = match routing.x {
+            InertiaSink::Pointer => &[RelativeAxisType::REL_X],
+            InertiaSink::Scroll if scroll_step.is_some() => &[RelativeAxisType::REL_HWHEEL],
+            InertiaSink::Scroll => &[
+                RelativeAxisType::REL_HWHEEL,
+                RelativeAxisType::REL_HWHEEL_HI_RES,
+            ],
+        };
+        let vertical: &[_] = match routing.y {
+            InertiaSink::Pointer => &[RelativeAxisType::REL_Y],
+            InertiaSink::Scroll if scroll_step.is_some() => &[RelativeAxisType::REL_WHEEL],
+            InertiaSink::Scroll => &[
+                RelativeAxisType::REL_WHEEL,
+                RelativeAxisType::REL_WHEEL_HI_RES,
+            ],
+        };
+        let mut route_axes = Vec::new();
+        if axes != Axes::Y {
+            route_axes.extend_from_slice(horizontal);
+        }
+        if axes != Axes::X {
+            route_axes.extend_from_slice(vertical);
+        }
+        route_axes
+    }
+
+    /// Emits values on registered relative axes, followed by SYN_REPORT
+    pub fn emit_relative(&mut self, values: &[(evdev::RelativeAxisType, i32)]) -> io::Result<()> {
+        use evdev::{EventType, InputEvent, Synchronization};
+        let stamp = self.stamp;
+        let event = |type_: EventType, code, value| match stamp {
+            Some(stamp) => event_at(stamp, type_, code, value),
+            None => InputEvent::new(type_, code, value),
+        };
+        let syn = event(EventType::SYNCHRONIZATION, Synchronization::SYN_REPORT.0, 0);
+        let mut events = [syn; 8];
+        let mut len = 0;
+        for &(axis, value) in values {
+            if self.rel_axes.contains(axis) && len < events.len() - 1 {
+                events[len] = event(EventType::RELATIVE, axis.0, value);
+                len += 1;
+            }
+        }
+        for mirror in &mut self.mirrors {
+            write_report(mirror.as_raw_fd(), &events[..=len])?;
+        }
+        self.device.emit(&events[..=len])
+    }
+
+    /// Creates the virtual mouse, retrying with exponential backoff on failure
+    pub fn new_with_retry(
+        attempts: u32,
+        delay: u64,
+        mut create: impl FnMut() -> Result<Self>,
+    ) -> Result<Self> {
+        let attempts = attempts.max(1);
+        let mut delay = time::Duration::from_millis(delay);
+        let mut attempt = 1;
+        loop {
+            match create() {
+                Ok(vmouse) => return Ok(vmouse),
+                Err(e) if attempt < attempts => {
+                    log::warn!(
+                        "Failed to create virtual mouse device (attempt {}/{}): {}. Retrying in {:?}",
+                        attempt,
+                        attempts,
+                        e,
+                        delay
+                    );
+                    thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Converts scroll amounts in high-resolution wheel units into wheel
+    /// axis values, following touchpad "natural" scrolling direction.
+    fn scroll_values(&mut self, x: i32, y: i32) -> [(evdev::RelativeAxisType, i32); 4] {
+        use evdev::RelativeAxisType;
+        let (hwheel, wheel) = (-x, y);
+        let (rem_h, rem_v) = (
+            self.wheel_remainder.0 + hwheel,
+            self.wheel_remainder.1 + wheel,
+        );
+        let step = self.scroll_step.unwrap_or(Self::WHEEL_CLICK);
+        let (clicks_h, clicks_v) = (rem_h / step, rem_v / step);
+        self.wheel_remainder = (rem_h - clicks_h * step, rem_v - clicks_v * step);
+        [
+            (RelativeAxisType::REL_HWHEEL_HI_RES, hwheel),
+            (RelativeAxisType::REL_WHEEL_HI_RES, wheel),
+            (RelativeAxisType::REL_HWHEEL, clicks_h),
+            (RelativeAxisType::REL_WHEEL, clicks_v),
+        ]
+    }
+
+    /// Moves the pointer by a pixel and back, for `--keep-awake`
+    ///
+    /// Returns `false` if the device has no pointer axis to move along.
+    pub fn keep_awake(&mut self) -> io::Result<bool> {
+        use evdev::RelativeAxisType;
+        let axis = [RelativeAxisType::REL_X, RelativeAxisType::REL_Y]
+            .into_iter()
+            .find(|&axis| self.rel_axes.contains(axis));
+        let Some(axis) = axis else {
+            return Ok(false);
+        };
+        self.emit_relative(&[(axis, 1)])?;
+        self.emit_relative(&[(axis, -1)])?;
+        Ok(true)
+    }
+
+    /// Delivers inertial movement to the sinks configured per axis,
+    /// in a single report
+    pub fn emit_motion(&mut self, x: i32, y: i32) -> io::Result<()> {
+        use evdev::RelativeAxisType;
+        if let Some(custom) = self.custom_axis {
+            let value = match custom.component {
+                Component::X => x,
+                Component::Y => y,
+            };
+            return self.emit_relative(&[(custom.axis, value)]);
+        }
+        let split = |sink, value| match sink {
+            InertiaSink::Pointer => (value, 0),
+            InertiaSink::Scroll => (0, value),
+        };
+        let (pointer_x, scroll_x) = split(self.routing.x, x);
+        let (pointer_y, scroll_y) = split(self.routing.y, y);
+        let [hwheel_hi_res, wheel_hi_res, hwheel, wheel] = self.scroll_values(scroll_x, scroll_y);
+        self.emit_relative(&[
+            (RelativeAxisType::REL_X, pointer_x),
+            (RelativeAxisType::REL_Y, pointer_y),
+            hwheel_hi_res,
+            wheel_hi_res,
+            hwheel,
+            wheel,
+        ])
+    }
+
+    /// Where the reports are delivered
+    pub fn device(&self) -> &OutputDevice {
+        &self.device
+    }
+
+    /// Relative axes registered on the device
+    pub fn rel_axes(&self) -> &evdev::AttributeSetRef<evdev::RelativeAxisType> {
+        &self.rel_axes
+    }
+
+    /// Routing of the emitted motion
+    pub fn routing(&self) -> Routing {
+        self.routing
+    }
+
+    /// Replaces the routing of the emitted motion, by one of the routes the
+    /// device was created for
+    pub fn set_routing(&mut self, routing: Routing) {
+        self.routing = routing;
+    }
+
+    /// Time stamped on the emitted events, if not the current one
+    pub fn stamp(&self) -> Option<time::SystemTime> {
+        self.stamp
+    }
+
+    /// Stamps the emitted events with `stamp`, or with the current time
+    /// if `None`
+    pub fn set_stamp(&mut self, stamp: Option<time::SystemTime>) {
+        self.stamp = stamp;
+    }
+
+    /// Drops the high-resolution wheel units not reported as whole clicks yet
+    pub fn reset_wheel_remainder(&mut self) {
+        self.wheel_remainder = (0, 0);
+    }
+}
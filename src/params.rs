@@ -0,0 +1,270 @@
+use arc_swap::ArcSwap;
+use std::sync::Arc;
+
+/// Kind of inertial movement, which selects the parameter set
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GestureKind {
+    Pointer,
+    Scroll,
+}
+
+/// Drag coefficient changing over the course of a glide
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DragCurve {
+    /// Drag at launch
+    pub start: f64,
+    /// Drag as the glide comes to a stop
+    pub end: f64,
+}
+
+impl DragCurve {
+    /// Drag at `progress`, from 0.0 at launch to 1.0 at a standstill
+    pub fn at(&self, progress: f64) -> f64 {
+        self.start + (self.end - self.start) * progress.clamp(0.0, 1.0)
+    }
+}
+
+impl std::str::FromStr for DragCurve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .split(':')
+            .map(|part| part.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid number: {}", e))?;
+        let [start, end] = parts[..] else {
+            return Err("expected START:END".to_owned());
+        };
+        if !(0.0..=1.0).contains(&start) || !(0.0..=1.0).contains(&end) {
+            return Err("drag must be between 0.0 and 1.0".to_owned());
+        }
+        Ok(Self { start, end })
+    }
+}
+
+/// Snapshot of the tunable inertia parameters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Params {
+    /// Inertia drag coefficient (between 0.0 and 1.0)
+    pub drag: f64,
+    /// Scales velocity from raw touchpad units to virtual mouse units
+    pub speed_factor: f64,
+    /// Minimum touchpad pointer speed required to trigger inertial movement
+    pub speed_threshold: f64,
+    /// Exponent mapping lift-off speed to initial inertia speed (1.0 is linear)
+    pub response_curve: f64,
+    /// Floor of the launch speed of an armed flick, 0.0 to disable
+    pub min_launch_speed: f64,
+    /// Extra damping (between 0.0 and 1.0) applied in the tail of a glide
+    pub settle_damp: f64,
+    /// Width of the band around the speed threshold where launch velocity
+    /// fades in smoothly instead of a hard cutoff, 0.0 for a hard threshold
+    pub soft_threshold: f64,
+    /// Lower speed threshold applied while the user keeps flicking, for hysteresis
+    pub speed_threshold_low: Option<f64>,
+    /// Drag varying with the glide's speed, overrides `drag` and `scroll_drag`
+    pub drag_curve: Option<DragCurve>,
+    /// Scroll-specific drag, `drag` if unset
+    pub scroll_drag: Option<f64>,
+    /// Horizontal drag, `drag` if unset
+    pub drag_x: Option<f64>,
+    /// Vertical drag, `drag` if unset
+    pub drag_y: Option<f64>,
+    /// Scroll-specific speed factor, `speed_factor` if unset
+    pub scroll_speed_factor: Option<f64>,
+    /// Horizontal speed factor, `speed_factor` if unset
+    pub speed_factor_x: Option<f64>,
+    /// Vertical speed factor, `speed_factor` if unset
+    pub speed_factor_y: Option<f64>,
+    /// Scroll-specific speed threshold, `speed_threshold` if unset
+    pub scroll_speed_threshold: Option<f64>,
+}
+
+impl Params {
+    /// Drag coefficient for the gesture kind
+    pub fn drag(&self, kind: GestureKind) -> f64 {
+        match kind {
+            GestureKind::Pointer => self.drag,
+            GestureKind::Scroll => self.scroll_drag.unwrap_or(self.drag),
+        }
+    }
+
+    /// Drag coefficient for the gesture kind on the horizontal or vertical axis
+    ///
+    /// The scroll-specific drag is the most specific, then the axis one.
+    pub fn axis_drag(&self, kind: GestureKind, vertical: bool) -> f64 {
+        let axis = if vertical { self.drag_y } else { self.drag_x };
+        match kind {
+            GestureKind::Pointer => axis.unwrap_or(self.drag),
+            GestureKind::Scroll => self.scroll_drag.or(axis).unwrap_or(self.drag),
+        }
+    }
+
+    /// Drag coefficient for a frame of a glide on the horizontal or vertical axis
+    ///
+    /// `progress` is how much of the launch speed has been lost, from 0.0
+    /// at launch to 1.0 at a standstill. Without a drag curve it's ignored.
+    pub fn glide_drag(&self, kind: GestureKind, vertical: bool, progress: f64) -> f64 {
+        match self.drag_curve {
+            Some(curve) => curve.at(progress),
+            None => self.axis_drag(kind, vertical),
+        }
+    }
+
+    /// Speed factor for the gesture kind
+    pub fn speed_factor(&self, kind: GestureKind) -> f64 {
+        match kind {
+            GestureKind::Pointer => self.speed_factor,
+            GestureKind::Scroll => self.scroll_speed_factor.unwrap_or(self.speed_factor),
+        }
+    }
+
+    /// Speed factor for the gesture kind on the horizontal or vertical axis
+    ///
+    /// The scroll-specific factor is the most specific, then the axis one.
+    pub fn axis_speed_factor(&self, kind: GestureKind, vertical: bool) -> f64 {
+        let axis = if vertical {
+            self.speed_factor_y
+        } else {
+            self.speed_factor_x
+        };
+        match kind {
+            GestureKind::Pointer => axis.unwrap_or(self.speed_factor),
+            GestureKind::Scroll => self
+                .scroll_speed_factor
+                .or(axis)
+                .unwrap_or(self.speed_factor),
+        }
+    }
+
+    /// Approximate distance in output units of a glide launched at
+    /// `launch_speed`
+    ///
+    /// Every frame emits `speed * speed_factor` and keeps `1 - drag` of the
+    /// speed, so the whole glide travels `speed * speed_factor / drag`, with
+    /// the speed taken after the response curve.
+    pub fn glide_distance(&self, kind: GestureKind, launch_speed: f64) -> f64 {
+        let (speed, _) = self.launch_velocity(kind, launch_speed, 0.0);
+        speed * self.speed_factor(kind) / self.drag(kind)
+    }
+
+    /// Speed factor making a glide launched at `launch_speed` travel
+    /// `distance`, the inverse of [`Params::glide_distance`]
+    pub fn speed_factor_for_distance(
+        &self,
+        kind: GestureKind,
+        launch_speed: f64,
+        distance: f64,
+    ) -> f64 {
+        let (speed, _) = self.launch_velocity(kind, launch_speed, 0.0);
+        distance * self.drag(kind) / speed
+    }
+
+    /// Speed threshold for the gesture kind
+    pub fn speed_threshold(&self, kind: GestureKind) -> f64 {
+        match kind {
+            GestureKind::Pointer => self.speed_threshold,
+            GestureKind::Scroll => self.scroll_speed_threshold.unwrap_or(self.speed_threshold),
+        }
+    }
+
+    /// Speed threshold for arming inertia after a lift-off
+    ///
+    /// Right after an armed flick the lower "keep" threshold is used instead,
+    /// so repeated flicks near the threshold behave consistently.
+    pub fn arm_threshold(&self, kind: GestureKind, recently_armed: bool) -> f64 {
+        let threshold = self.speed_threshold(kind);
+        match self.speed_threshold_low {
+            Some(low) if recently_armed => low.min(threshold),
+            _ => threshold,
+        }
+    }
+
+    /// Launch velocity scale (between 0.0 and 1.0) for a lift-off speed
+    ///
+    /// With a soft threshold the scale rises smoothly from zero at
+    /// `threshold - soft_threshold / 2` to one at `threshold + soft_threshold / 2`.
+    pub fn launch_scale(&self, speed: f64, threshold: f64) -> f64 {
+        if self.soft_threshold <= 0.0 {
+            return if speed < threshold { 0.0 } else { 1.0 };
+        }
+        let t = ((speed - threshold) / self.soft_threshold + 0.5).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    /// Applies the response curve to the lift-off velocity, preserving direction
+    ///
+    /// The speed is mapped as `speed_threshold * (speed / speed_threshold) ^ response_curve`,
+    /// so flicks right at the threshold are unaffected, while gentler and harder
+    /// flicks are scaled down and up respectively when the exponent is above 1.0.
+    /// The result is then raised to at least `min_launch_speed`.
+    pub fn launch_velocity(&self, kind: GestureKind, vx: f64, vy: f64) -> (f64, f64) {
+        let speed = vx.hypot(vy);
+        if speed == 0.0 {
+            return (vx, vy);
+        }
+        let pivot = self.speed_threshold(kind).max(1.0);
+        let curved = match self.response_curve {
+            1.0 => speed,
+            curve => pivot * (speed / pivot).powf(curve),
+        };
+        let scale = curved.max(self.min_launch_speed) / speed;
+        (vx * scale, vy * scale)
+    }
+}
+
+/// Parameters shared between the running threads and the tuning path
+///
+/// Readers get a consistent snapshot without locking, writers swap in
+/// a whole new snapshot at once.
+#[derive(Debug)]
+pub struct LiveParams(ArcSwap<Params>);
+
+impl LiveParams {
+    pub fn new(params: Params) -> Self {
+        Self(ArcSwap::from_pointee(params))
+    }
+
+    /// Returns the current snapshot
+    pub fn load(&self) -> Params {
+        **self.0.load()
+    }
+
+    /// Replaces the current snapshot
+    pub fn store(&self, params: Params) {
+        self.0.store(Arc::new(params));
+    }
+
+    /// Replaces the current snapshot with a modified copy of it
+    pub fn update(&self, f: impl Fn(&mut Params)) {
+        self.0.rcu(|current| {
+            let mut params = **current;
+            f(&mut params);
+            params
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_curve_interpolates_from_start_to_end() {
+        let curve: DragCurve = "0.3:0.05".parse().unwrap();
+        assert_eq!(curve.at(0.0), 0.3);
+        assert!((curve.at(0.5) - 0.175).abs() < 1e-9);
+        assert!((curve.at(1.0) - 0.05).abs() < 1e-9);
+        // Progress past a standstill keeps the end drag
+        assert_eq!(curve.at(2.0), curve.at(1.0));
+    }
+
+    #[test]
+    fn drag_curve_rejects_malformed_values() {
+        assert!("0.3".parse::<DragCurve>().is_err());
+        assert!("0.3:0.2:0.1".parse::<DragCurve>().is_err());
+        assert!("0.3:fast".parse::<DragCurve>().is_err());
+        assert!("0.3:1.5".parse::<DragCurve>().is_err());
+    }
+}
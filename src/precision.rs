@@ -0,0 +1,155 @@
+//! Keyboard modifiers held to change inertia: precision mode with heavier
+//! drag, and suppression of inertia altogether
+
+use clap::ValueEnum;
+use evdev::{InputEventKind, Key};
+use inertpad::DevicePoller;
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Modifier {
+    /// Either Ctrl key (KEY_LEFTCTRL, KEY_RIGHTCTRL)
+    Ctrl,
+    /// Either Shift key (KEY_LEFTSHIFT, KEY_RIGHTSHIFT)
+    Shift,
+    /// Either Alt key (KEY_LEFTALT, KEY_RIGHTALT)
+    Alt,
+    /// Either Super key (KEY_LEFTMETA, KEY_RIGHTMETA)
+    Super,
+}
+
+impl Modifier {
+    fn keys(self) -> [Key; 2] {
+        match self {
+            Modifier::Ctrl => [Key::KEY_LEFTCTRL, Key::KEY_RIGHTCTRL],
+            Modifier::Shift => [Key::KEY_LEFTSHIFT, Key::KEY_RIGHTSHIFT],
+            Modifier::Alt => [Key::KEY_LEFTALT, Key::KEY_RIGHTALT],
+            Modifier::Super => [Key::KEY_LEFTMETA, Key::KEY_RIGHTMETA],
+        }
+    }
+}
+
+/// Whether a modifier is held on any keyboard
+#[derive(Debug, Default)]
+pub struct ModifierState {
+    /// Number of keyboards currently holding the modifier
+    holders: AtomicUsize,
+}
+
+impl ModifierState {
+    pub fn is_held(&self) -> bool {
+        self.holders.load(Ordering::Relaxed) > 0
+    }
+
+    /// Records a keyboard starting to hold the modifier
+    pub fn press(&self) {
+        self.holders.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a keyboard no longer holding the modifier
+    pub fn release(&self) {
+        self.holders.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Whether the precision modifier is held on any keyboard
+#[derive(Debug)]
+pub struct PrecisionState {
+    pub modifier: Arc<ModifierState>,
+    drag_mult: f64,
+}
+
+impl PrecisionState {
+    pub fn new(drag_mult: f64) -> Self {
+        Self {
+            modifier: Arc::default(),
+            drag_mult,
+        }
+    }
+
+    /// Returns `drag`, multiplied while the modifier is held
+    pub fn apply(&self, drag: f64) -> f64 {
+        if self.modifier.is_held() {
+            drag * self.drag_mult
+        } else {
+            drag
+        }
+    }
+}
+
+/// Starts a background thread reporting the modifier of every keyboard.
+/// Keyboards are only read, never grabbed, so typing is unaffected.
+///
+/// `purpose` names the modifier in log messages, e.g. "precision".
+pub fn spawn_watcher(
+    modifier: Modifier,
+    purpose: &'static str,
+    state: Arc<ModifierState>,
+) -> io::Result<()> {
+    let keys = modifier.keys();
+    let mut poller = DevicePoller::new();
+    for (path, device) in evdev::enumerate() {
+        let has_modifier = device
+            .supported_keys()
+            .is_some_and(|supported| keys.iter().any(|&key| supported.contains(key)));
+        if !has_modifier {
+            continue;
+        }
+        log::info!(
+            "Watching {} modifier on {} ({})",
+            purpose,
+            device.name().unwrap_or_default(),
+            path.display()
+        );
+        poller.add(device)?;
+    }
+    if poller.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no keyboard with the {:?} modifier found", modifier),
+        ));
+    }
+    // Per keyboard and key state, so that releasing one of the pair
+    // keeps the other held
+    let mut pressed: Vec<[bool; 2]> = Vec::new();
+    thread::spawn(move || {
+        let result = poller.run(|index, event| {
+            let InputEventKind::Key(key) = event.kind() else {
+                return;
+            };
+            let Some(i) = keys.iter().position(|&k| k == key) else {
+                return;
+            };
+            if pressed.len() <= index {
+                pressed.resize(index + 1, [false; 2]);
+            }
+            let was_held = pressed[index].contains(&true);
+            // Ignore autorepeat (value 2)
+            match event.value() {
+                0 => pressed[index][i] = false,
+                1 => pressed[index][i] = true,
+                _ => {}
+            }
+            let held = pressed[index].contains(&true);
+            if held && !was_held {
+                log::debug!("{} modifier held", purpose);
+                state.press();
+            } else if !held && was_held {
+                log::debug!("{} modifier released", purpose);
+                state.release();
+            }
+        });
+        match result {
+            Ok(()) => log::warn!("No keyboards left to watch for the {} modifier", purpose),
+            Err(e) => log::warn!("Stopped watching {} modifier: {}", purpose, e),
+        }
+    });
+    Ok(())
+}
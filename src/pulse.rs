@@ -0,0 +1,71 @@
+//! Force-feedback pulse at the end of glides, for `--end-pulse`
+
+use evdev::{FFEffect, FFEffectData, FFEffectKind, FFEffectType, FFReplay, FFTrigger};
+use std::sync::Mutex;
+
+/// Duration of the pulse in milliseconds
+const LENGTH_MS: u16 = 30;
+
+/// Strength of the pulse on the light motor, out of `u16::MAX`
+const MAGNITUDE: u16 = 0x6000;
+
+/// Short rumble uploaded to a force-feedback device
+#[derive(Debug)]
+pub struct EndPulse {
+    effect: Mutex<FFEffect>,
+}
+
+impl EndPulse {
+    /// Uploads the pulse to the first device supporting rumble, returning
+    /// `None` if there is none or it rejects the effect
+    pub fn open() -> Option<Self> {
+        let (path, mut device) = evdev::enumerate().find(|(_, device)| {
+            let own = device
+                .name()
+                .is_some_and(|name| name.starts_with("InertPad"));
+            let rumble = device
+                .supported_ff()
+                .is_some_and(|ff| ff.contains(FFEffectType::FF_RUMBLE));
+            !own && rumble
+        })?;
+        let data = FFEffectData {
+            direction: 0,
+            trigger: FFTrigger::default(),
+            replay: FFReplay {
+                length: LENGTH_MS,
+                delay: 0,
+            },
+            kind: FFEffectKind::Rumble {
+                strong_magnitude: 0,
+                weak_magnitude: MAGNITUDE,
+            },
+        };
+        match device.upload_ff_effect(data) {
+            Ok(effect) => {
+                log::info!(
+                    "Glide end pulse on {} ({})",
+                    device.name().unwrap_or_default(),
+                    path.display()
+                );
+                Some(Self {
+                    effect: Mutex::new(effect),
+                })
+            }
+            Err(e) => {
+                log::warn!(
+                    "Failed to upload the glide end pulse to {}: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Plays the pulse once
+    pub fn play(&self) {
+        if let Err(e) = self.effect.lock().unwrap().play(1) {
+            log::debug!("Failed to play the glide end pulse: {}", e);
+        }
+    }
+}
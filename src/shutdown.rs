@@ -0,0 +1,31 @@
+//! Orderly exit on SIGINT and SIGTERM
+
+use std::{io, mem, ptr, thread};
+
+/// Runs `handler` and exits once SIGINT or SIGTERM arrives
+///
+/// Blocks both signals in the calling thread and waits for them on a
+/// dedicated one, so it must be called before spawning any other thread.
+/// The exit status is 128 plus the signal number, as if killed by it.
+pub fn on_termination(handler: impl FnOnce(i32) + Send + 'static) -> io::Result<()> {
+    // SAFETY: `set` is initialized by `sigemptyset` before use
+    let set = unsafe {
+        let mut set: libc::sigset_t = mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, libc::SIGINT);
+        libc::sigaddset(&mut set, libc::SIGTERM);
+        match libc::pthread_sigmask(libc::SIG_BLOCK, &set, ptr::null_mut()) {
+            0 => set,
+            e => return Err(io::Error::from_raw_os_error(e)),
+        }
+    };
+    thread::spawn(move || {
+        let mut signal = 0;
+        // SAFETY: `set` is a valid signal set blocked in every thread
+        unsafe { libc::sigwait(&set, &mut signal) };
+        log::info!("Received signal {}, shutting down", signal);
+        handler(signal);
+        std::process::exit(128 + signal);
+    });
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! Non-blocking event sources for embedding in event loops
+
+use evdev::{Device, InputEvent};
+use std::{
+    io,
+    os::unix::io::{AsRawFd, RawFd},
+    path::{Path, PathBuf},
+};
+
+/// Touchpad opened in non-blocking mode
+///
+/// Register [`AsRawFd::as_raw_fd`] with a reactor (mio, tokio, `poll(2)`)
+/// and call [`TouchpadSource::poll_events`] when it becomes readable.
+pub struct TouchpadSource {
+    device: Device,
+    path: PathBuf,
+}
+
+impl TouchpadSource {
+    /// Opens the device node and switches it to non-blocking mode
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let device = Device::open(&path)?;
+        set_nonblocking(device.as_raw_fd())?;
+        Ok(Self { device, path })
+    }
+
+    /// Device node path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Underlying evdev device, e.g. to query capabilities
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Returns the events that are ready, without blocking
+    ///
+    /// An empty result means no events are pending.
+    pub fn poll_events(&mut self) -> io::Result<Vec<InputEvent>> {
+        match self.device.fetch_events() {
+            Ok(events) => Ok(events.collect()),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl AsRawFd for TouchpadSource {
+    fn as_raw_fd(&self) -> RawFd {
+        self.device.as_raw_fd()
+    }
+}
+
+/// Several non-blocking devices read from a single thread with `poll(2)`
+///
+/// More scalable than a thread per device, e.g. for watching every keyboard.
+/// Devices that fail, typically because they were unplugged, are dropped.
+#[derive(Default)]
+pub struct DevicePoller {
+    devices: Vec<Device>,
+}
+
+impl DevicePoller {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    /// Switches the device to non-blocking mode and adds it to the set,
+    /// returning its index for [`DevicePoller::run`]
+    pub fn add(&mut self, device: Device) -> io::Result<usize> {
+        set_nonblocking(device.as_raw_fd())?;
+        self.devices.push(device);
+        Ok(self.devices.len() - 1)
+    }
+
+    /// Passes events of every device to `handler` along with the device
+    /// index, until no devices are left
+    pub fn run(self, mut handler: impl FnMut(usize, InputEvent)) -> io::Result<()> {
+        let mut devices: Vec<_> = self.devices.into_iter().enumerate().collect();
+        while !devices.is_empty() {
+            let mut pollfds: Vec<_> = devices
+                .iter()
+                .map(|(_, device)| libc::pollfd {
+                    fd: device.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                })
+                .collect();
+            // SAFETY: `pollfds` is a valid, exclusively borrowed array of its length
+            if unsafe { libc::poll(pollfds.as_mut_ptr(), pollfds.len() as libc::nfds_t, -1) } == -1
+            {
+                match io::Error::last_os_error() {
+                    e if e.kind() == io::ErrorKind::Interrupted => continue,
+                    e => return Err(e),
+                }
+            }
+            let mut failed = Vec::new();
+            for (pollfd, (index, device)) in pollfds.iter().zip(&mut devices) {
+                if pollfd.revents == 0 {
+                    continue;
+                }
+                let result = device
+                    .fetch_events()
+                    .map(|events| events.for_each(|event| handler(*index, event)));
+                match result {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => {
+                        log::warn!(
+                            "Stopped reading {}: {}",
+                            device.name().unwrap_or_default(),
+                            e
+                        );
+                        failed.push(*index);
+                    }
+                }
+            }
+            devices.retain(|(index, _)| !failed.contains(index));
+        }
+        Ok(())
+    }
+}
+
+fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    // SAFETY: `fd` is an open descriptor owned by the device
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: as above, only the status flags are changed
+    if unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
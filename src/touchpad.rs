@@ -0,0 +1,92 @@
+//! Touchpad device held open for capture
+
+use crate::{open_device, selection_score, Capabilities, MatchStrategy, Selection};
+use evdev::Device;
+use std::{
+    fs, io,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+/// Touchpad opened in blocking mode, along with its device node path
+///
+/// Unlike [`crate::TouchpadSource`], the node can be reopened in place,
+/// e.g. after the device stopped delivering events.
+pub struct Touchpad {
+    device: Device,
+    path: PathBuf,
+}
+
+impl Touchpad {
+    /// Wraps a device opened from `path`
+    pub fn new(path: PathBuf, device: Device) -> Self {
+        Self { device, path }
+    }
+
+    /// Opens the first or best device satisfying the match strategy
+    pub fn find(strategy: MatchStrategy, selection: Selection) -> Option<Self> {
+        let mut candidates =
+            evdev::enumerate().filter(|(_, device)| strategy.matches(&Capabilities::of(device)));
+        if selection == Selection::First {
+            return candidates
+                .next()
+                .map(|(path, device)| Self { device, path });
+        }
+        let mut best: Option<(u32, Self)> = None;
+        for (path, device) in candidates {
+            let score = selection_score(&device);
+            log::info!(
+                "Touchpad candidate {} ({}): score {}",
+                device.name().unwrap_or_default(),
+                path.display(),
+                score
+            );
+            if best.as_ref().is_none_or(|(top, _)| score > *top) {
+                best = Some((score, Self { device, path }));
+            }
+        }
+        best.map(|(_, touchpad)| touchpad)
+    }
+
+    /// Opens the device a path, glob or name refers to, as [`open_device`]
+    /// resolves it
+    pub fn open(query: &str) -> io::Result<Self> {
+        let (path, device) = open_device(query)?;
+        Ok(Self { device, path })
+    }
+
+    /// Device node path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Underlying evdev device
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Underlying evdev device, e.g. to fetch its events
+    pub fn device_mut(&mut self) -> &mut Device {
+        &mut self.device
+    }
+
+    /// Reopens the device node
+    pub fn reopen(&mut self, read_only: bool) -> io::Result<()> {
+        self.device = Device::open(&self.path)?;
+        if read_only {
+            self.drop_write_access()?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the read-write descriptor evdev opens by a read-only one
+    /// of the same node
+    pub fn drop_write_access(&mut self) -> io::Result<()> {
+        let file = fs::File::open(&self.path)?;
+        // SAFETY: both descriptors are open and owned by live values
+        if unsafe { libc::dup2(file.as_raw_fd(), self.device.as_raw_fd()) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
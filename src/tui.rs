@@ -0,0 +1,100 @@
+//! Interactive terminal UI for live parameter tuning
+
+use crate::control::ControlState;
+use inertpad::{LiveParams, Params};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    text::Line,
+    widgets::{Block, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::{
+    io,
+    sync::{atomic::Ordering, Arc},
+    thread, time,
+};
+
+/// Interval between readout refreshes while no key is pressed
+const REFRESH_PERIOD: time::Duration = time::Duration::from_millis(100);
+
+/// Drag change per key press
+const DRAG_STEP: f64 = 0.01;
+
+/// Relative change of speed factor and threshold per key press
+const SCALE_STEP: f64 = 1.1;
+
+/// Takes over the terminal on a background thread
+///
+/// Quitting the UI restores the terminal and exits the process.
+pub fn spawn(params: Arc<LiveParams>, control: Arc<ControlState>) {
+    thread::spawn(move || {
+        let terminal = ratatui::init();
+        let result = run(terminal, &params, &control);
+        ratatui::restore();
+        if let Err(e) = result {
+            log::error!("Terminal UI failed: {}", e);
+        }
+        std::process::exit(0);
+    });
+}
+
+fn run(
+    mut terminal: DefaultTerminal,
+    params: &LiveParams,
+    control: &ControlState,
+) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, &params.load(), control))?;
+        if !event::poll(REFRESH_PERIOD)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('d') => params.update(|p| p.drag = (p.drag - DRAG_STEP).max(0.0)),
+            KeyCode::Char('D') => params.update(|p| p.drag = (p.drag + DRAG_STEP).min(1.0)),
+            KeyCode::Char('f') => params.update(|p| p.speed_factor /= SCALE_STEP),
+            KeyCode::Char('F') => params.update(|p| p.speed_factor *= SCALE_STEP),
+            KeyCode::Char('t') => params.update(|p| p.speed_threshold /= SCALE_STEP),
+            KeyCode::Char('T') => params.update(|p| p.speed_threshold *= SCALE_STEP),
+            KeyCode::Char('r') => control.reset_params(),
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, params: &Params, control: &ControlState) {
+    let glide = *control.last_glide.lock().unwrap();
+    let capture = if control.capture_connected.load(Ordering::Relaxed) {
+        "connected"
+    } else {
+        "reconnecting"
+    };
+    let lines = vec![
+        Line::from(format!("Drag             {:>10.3}   [d/D]", params.drag)),
+        Line::from(format!(
+            "Speed factor     {:>10.5}   [f/F]",
+            params.speed_factor
+        )),
+        Line::from(format!(
+            "Speed threshold  {:>10.0}   [t/T]",
+            params.speed_threshold
+        )),
+        Line::from(""),
+        Line::from(format!(
+            "Last launch speed    {:.0} units/s",
+            glide.launch_speed
+        )),
+        Line::from(format!("Last glide distance  {:.0}", glide.distance)),
+        Line::from(format!("Capture              {}", capture)),
+        Line::from(""),
+        Line::from("lowercase: decrease, uppercase: increase, r: reset, q: quit"),
+    ];
+    let block = Block::bordered().title(" InertPad ");
+    frame.render_widget(Paragraph::new(lines).block(block), frame.area());
+}
@@ -0,0 +1,367 @@
+//! Unprivileged output through the wlroots virtual pointer protocol
+//!
+//! Speaks just enough of the Wayland wire protocol to create a
+//! `zwlr_virtual_pointer_v1` and send it motion and scroll, so that no
+//! client library is needed.
+
+use evdev::{EventType, InputEvent, RelativeAxisType};
+use std::{
+    io::{self, Read, Write},
+    os::unix::{
+        io::{AsRawFd, RawFd},
+        net::UnixStream,
+    },
+    path::PathBuf,
+    time,
+};
+
+const MANAGER_INTERFACE: &str = "zwlr_virtual_pointer_manager_v1";
+const SEAT_INTERFACE: &str = "wl_seat";
+
+/// Object ids, allocated by the client in order
+const DISPLAY: u32 = 1;
+const REGISTRY: u32 = 2;
+const FIRST_CALLBACK: u32 = 3;
+const MANAGER: u32 = 4;
+const SEAT: u32 = 5;
+const POINTER: u32 = 6;
+const SECOND_CALLBACK: u32 = 7;
+
+/// `zwlr_virtual_pointer_v1` requests
+const MOTION: u16 = 0;
+const AXIS: u16 = 3;
+const FRAME: u16 = 4;
+const AXIS_SOURCE: u16 = 5;
+const AXIS_DISCRETE: u16 = 7;
+
+/// `wl_pointer.axis` and `wl_pointer.axis_source` values
+const VERTICAL_SCROLL: u32 = 0;
+const HORIZONTAL_SCROLL: u32 = 1;
+const SOURCE_WHEEL: u32 = 0;
+
+/// Scroll distance of one wheel click, as libinput reports it
+const CLICK_DISTANCE: f64 = 15.0;
+
+/// High-resolution wheel units per click
+const WHEEL_CLICK: f64 = 120.0;
+
+/// Virtual pointer of the compositor the process runs under
+#[derive(Debug)]
+pub struct VirtualPointer {
+    stream: UnixStream,
+    /// Base of the event times
+    started: time::SystemTime,
+    /// Requests of the frame being sent, reused to avoid allocating
+    request: Vec<u8>,
+}
+
+impl VirtualPointer {
+    /// Connects to `$WAYLAND_DISPLAY` and creates a virtual pointer on the
+    /// first seat
+    ///
+    /// Fails with `Unsupported` if the compositor lacks the protocol.
+    pub fn connect() -> io::Result<Self> {
+        let mut stream = UnixStream::connect(socket_path()?)?;
+        let mut request = Vec::new();
+        Message::new(&mut request, DISPLAY, 1)
+            .uint(REGISTRY)
+            .finish();
+        Message::new(&mut request, DISPLAY, 0)
+            .uint(FIRST_CALLBACK)
+            .finish();
+        stream.write_all(&request)?;
+        let mut manager = None;
+        let mut seat = None;
+        roundtrip(&mut stream, FIRST_CALLBACK, |object, opcode, body| {
+            if object != REGISTRY || opcode != 0 {
+                return Ok(());
+            }
+            let mut body = Reader(body);
+            let (name, interface, version) = (body.uint()?, body.string()?, body.uint()?);
+            match interface.as_str() {
+                MANAGER_INTERFACE => manager = manager.or(Some(name)),
+                SEAT_INTERFACE => seat = seat.or(Some((name, version))),
+                _ => {}
+            }
+            Ok(())
+        })?;
+        let unsupported = |what| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("compositor doesn't offer {}", what),
+            )
+        };
+        let manager = manager.ok_or_else(|| unsupported(MANAGER_INTERFACE))?;
+        let (seat, seat_version) = seat.ok_or_else(|| unsupported(SEAT_INTERFACE))?;
+        request.clear();
+        bind(&mut request, manager, MANAGER_INTERFACE, 1, MANAGER);
+        bind(
+            &mut request,
+            seat,
+            SEAT_INTERFACE,
+            seat_version.min(1),
+            SEAT,
+        );
+        Message::new(&mut request, MANAGER, 0)
+            .uint(SEAT)
+            .uint(POINTER)
+            .finish();
+        // Protocol errors in the requests above arrive before the callback
+        Message::new(&mut request, DISPLAY, 0)
+            .uint(SECOND_CALLBACK)
+            .finish();
+        stream.write_all(&request)?;
+        roundtrip(&mut stream, SECOND_CALLBACK, |_, _, _| Ok(()))?;
+        Ok(Self {
+            stream,
+            started: time::SystemTime::now(),
+            request,
+        })
+    }
+
+    /// Sends a report of relative events as motion and scroll in one frame
+    ///
+    /// Axes other than pointer motion and wheels are dropped. The frame is
+    /// timed by the events' timestamp if they have one, or else by the
+    /// current time.
+    pub fn emit(&mut self, events: &[InputEvent]) -> io::Result<()> {
+        let mut motion = (0, 0);
+        let mut hi_res = (0, 0);
+        let mut clicks = (0, 0);
+        for event in events
+            .iter()
+            .filter(|e| e.event_type() == EventType::RELATIVE)
+        {
+            match RelativeAxisType(event.code()) {
+                RelativeAxisType::REL_X => motion.0 += event.value(),
+                RelativeAxisType::REL_Y => motion.1 += event.value(),
+                RelativeAxisType::REL_HWHEEL_HI_RES => hi_res.0 += event.value(),
+                RelativeAxisType::REL_WHEEL_HI_RES => hi_res.1 += event.value(),
+                RelativeAxisType::REL_HWHEEL => clicks.0 += event.value(),
+                RelativeAxisType::REL_WHEEL => clicks.1 += event.value(),
+                _ => {}
+            }
+        }
+        self.drain_events();
+        let stamp = events
+            .first()
+            .map(InputEvent::timestamp)
+            .filter(|&stamp| stamp != time::UNIX_EPOCH)
+            .unwrap_or_else(time::SystemTime::now);
+        // Wraps around after 49 days, as Wayland timestamps do
+        let time = stamp
+            .duration_since(self.started)
+            .unwrap_or_default()
+            .as_millis() as u32;
+        let request = &mut self.request;
+        request.clear();
+        if motion != (0, 0) {
+            Message::new(request, POINTER, MOTION)
+                .uint(time)
+                .fixed(f64::from(motion.0))
+                .fixed(f64::from(motion.1))
+                .finish();
+        }
+        // Wheel values grow upwards, Wayland scroll values downwards
+        let scroll = [
+            (HORIZONTAL_SCROLL, hi_res.0, clicks.0),
+            (VERTICAL_SCROLL, -hi_res.1, -clicks.1),
+        ];
+        if scroll
+            .iter()
+            .any(|&(_, hi_res, clicks)| hi_res != 0 || clicks != 0)
+        {
+            Message::new(request, POINTER, AXIS_SOURCE)
+                .uint(SOURCE_WHEEL)
+                .finish();
+        }
+        for (axis, hi_res, clicks) in scroll {
+            // Without the high-resolution axes, in discrete scroll mode,
+            // only the clicks are there
+            let distance = if hi_res != 0 {
+                f64::from(hi_res) / WHEEL_CLICK * CLICK_DISTANCE
+            } else {
+                f64::from(clicks) * CLICK_DISTANCE
+            };
+            if clicks != 0 {
+                Message::new(request, POINTER, AXIS_DISCRETE)
+                    .uint(time)
+                    .uint(axis)
+                    .fixed(distance)
+                    .int(clicks)
+                    .finish();
+            } else if hi_res != 0 {
+                Message::new(request, POINTER, AXIS)
+                    .uint(time)
+                    .uint(axis)
+                    .fixed(distance)
+                    .finish();
+            }
+        }
+        Message::new(request, POINTER, FRAME).finish();
+        self.stream.write_all(&self.request)
+    }
+
+    /// Discards pending events, such as seat updates, so that they don't
+    /// fill up the socket. None of them matter once the pointer exists.
+    fn drain_events(&self) {
+        let mut buffer = [0u8; 512];
+        loop {
+            // SAFETY: the buffer is valid for its length
+            let read = unsafe {
+                libc::recv(
+                    self.stream.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                    libc::MSG_DONTWAIT,
+                )
+            };
+            if read <= 0 {
+                break;
+            }
+        }
+    }
+}
+
+impl AsRawFd for VirtualPointer {
+    fn as_raw_fd(&self) -> RawFd {
+        self.stream.as_raw_fd()
+    }
+}
+
+fn socket_path() -> io::Result<PathBuf> {
+    let display = std::env::var_os("WAYLAND_DISPLAY").unwrap_or_else(|| "wayland-0".into());
+    let display = PathBuf::from(display);
+    if display.is_absolute() {
+        return Ok(display);
+    }
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "XDG_RUNTIME_DIR is not set"))?;
+    Ok(PathBuf::from(runtime_dir).join(display))
+}
+
+/// `wl_registry.bind` of a global to a new object id
+fn bind(request: &mut Vec<u8>, name: u32, interface: &str, version: u32, id: u32) {
+    Message::new(request, REGISTRY, 0)
+        .uint(name)
+        .string(interface)
+        .uint(version)
+        .uint(id)
+        .finish();
+}
+
+/// Reads events until the `wl_callback.done` of `callback`, passing the
+/// others to `handle` as (object, opcode, body)
+///
+/// A `wl_display.error` fails the roundtrip.
+fn roundtrip(
+    stream: &mut UnixStream,
+    callback: u32,
+    mut handle: impl FnMut(u32, u16, &[u8]) -> io::Result<()>,
+) -> io::Result<()> {
+    loop {
+        let mut header = [0u8; 8];
+        stream.read_exact(&mut header)?;
+        let mut header = Reader(&header);
+        let (object, word) = (header.uint()?, header.uint()?);
+        let (size, opcode) = ((word >> 16) as usize, (word & 0xffff) as u16);
+        let mut body = vec![0u8; size.saturating_sub(8)];
+        stream.read_exact(&mut body)?;
+        match (object, opcode) {
+            (DISPLAY, 0) => {
+                let mut body = Reader(&body);
+                let (_, code, message) = (body.uint()?, body.uint()?, body.string()?);
+                return Err(io::Error::other(format!(
+                    "Wayland protocol error {}: {}",
+                    code, message
+                )));
+            }
+            (id, 0) if id == callback => return Ok(()),
+            _ => handle(object, opcode, &body)?,
+        }
+    }
+}
+
+/// Request being serialized in the native-endian wire format, appended
+/// to a buffer of requests
+struct Message<'a> {
+    buffer: &'a mut Vec<u8>,
+    /// Offset of the request's header in the buffer
+    start: usize,
+    opcode: u16,
+}
+
+impl<'a> Message<'a> {
+    /// Starts the request with its header, completed by `finish`
+    fn new(buffer: &'a mut Vec<u8>, object: u32, opcode: u16) -> Self {
+        let start = buffer.len();
+        buffer.extend(object.to_ne_bytes());
+        buffer.extend(0u32.to_ne_bytes());
+        Self {
+            buffer,
+            start,
+            opcode,
+        }
+    }
+
+    fn uint(self, value: u32) -> Self {
+        self.buffer.extend(value.to_ne_bytes());
+        self
+    }
+
+    fn int(self, value: i32) -> Self {
+        self.buffer.extend(value.to_ne_bytes());
+        self
+    }
+
+    /// 24.8 fixed-point number
+    fn fixed(self, value: f64) -> Self {
+        self.int((value * 256.0).round() as i32)
+    }
+
+    /// Length including the NUL terminator, then the padded bytes
+    fn string(self, value: &str) -> Self {
+        let this = self.uint(value.len() as u32 + 1);
+        this.buffer.extend(value.as_bytes());
+        this.buffer.push(0);
+        // The buffer holds whole words, so its length tells the padding
+        while !this.buffer.len().is_multiple_of(4) {
+            this.buffer.push(0);
+        }
+        this
+    }
+
+    /// Fills in the size of the request in its header
+    fn finish(self) {
+        let size = (self.buffer.len() - self.start) as u32;
+        let word = (size << 16 | u32::from(self.opcode)).to_ne_bytes();
+        self.buffer[self.start + 4..self.start + 8].copy_from_slice(&word);
+    }
+}
+
+/// Cursor over the arguments of an event
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn uint(&mut self) -> io::Result<u32> {
+        let truncated = || io::Error::new(io::ErrorKind::InvalidData, "truncated Wayland event");
+        let (bytes, rest) = self.0.split_first_chunk::<4>().ok_or_else(truncated)?;
+        self.0 = rest;
+        Ok(u32::from_ne_bytes(*bytes))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.uint()? as usize;
+        let padded = len.div_ceil(4) * 4;
+        if padded > self.0.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated Wayland string",
+            ));
+        }
+        let (bytes, rest) = self.0.split_at(padded);
+        self.0 = rest;
+        let text = &bytes[..len.saturating_sub(1)];
+        Ok(String::from_utf8_lossy(text).into_owned())
+    }
+}
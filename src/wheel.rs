@@ -0,0 +1,157 @@
+//! Kinetic scroll tails for devices that scroll through native wheel events
+
+use crate::{wait_readable, CaptureContext, InertiaSink, MomentumMessage, Routing};
+use evdev::{Device, InputEventKind, RelativeAxisType, Synchronization};
+use inertpad::GestureKind;
+use std::{
+    io,
+    os::unix::io::AsRawFd,
+    path::PathBuf,
+    sync::{atomic::Ordering, mpsc},
+    time,
+};
+
+/// Wheel silence after which scrolling counts as finished
+const SCROLL_END: time::Duration = time::Duration::from_millis(80);
+
+/// Weight of the newest report in the smoothed scroll velocity
+const SMOOTHING: f64 = 0.5;
+
+/// Slowest scrolling continued with a kinetic tail, in high-resolution
+/// wheel units per second
+const MIN_TAIL_SPEED: f64 = 480.0;
+
+/// High-resolution wheel units per legacy wheel click
+const WHEEL_CLICK: i32 = 120;
+
+/// Finds the first device scrolling through wheel events without moving
+/// a pointer, such as the scroll node of some touchpads
+///
+/// InertPad's own virtual devices are skipped.
+pub fn find() -> Option<(PathBuf, Device)> {
+    evdev::enumerate().find(|(_, device)| {
+        let own = device
+            .name()
+            .is_some_and(|name| name.starts_with("InertPad Virtual Mouse"));
+        let axes = device.supported_relative_axes();
+        let wheel = axes.is_some_and(|axes| {
+            axes.contains(RelativeAxisType::REL_WHEEL)
+                || axes.contains(RelativeAxisType::REL_HWHEEL)
+        });
+        let pointer = axes.is_some_and(|axes| axes.contains(RelativeAxisType::REL_X));
+        !own && wheel && !pointer
+    })
+}
+
+/// Follows native wheel events and launches a scroll glide once they stop
+///
+/// The glide continues at the speed scrolling had when it stopped, and
+/// a new scroll stops it.
+pub fn run_capture(
+    device: &mut Device,
+    sender: &mpsc::Sender<MomentumMessage>,
+    context: CaptureContext,
+    frame_period: time::Duration,
+) -> io::Result<()> {
+    let axes = device.supported_relative_axes();
+    // Wheels with high-resolution axes report the legacy ones alongside
+    let hi_res = (
+        axes.is_some_and(|axes| axes.contains(RelativeAxisType::REL_HWHEEL_HI_RES)),
+        axes.is_some_and(|axes| axes.contains(RelativeAxisType::REL_WHEEL_HI_RES)),
+    );
+    let mut scrolling = false;
+    // Smoothed horizontal and vertical velocity in high-resolution units per second
+    let mut velocity = (0f64, 0f64);
+    let mut delta = (0i32, 0i32);
+    let mut last_report: Option<time::SystemTime> = None;
+    loop {
+        if scrolling && !wait_readable(device.as_raw_fd(), SCROLL_END)? {
+            scrolling = false;
+            launch(sender, context, velocity, frame_period);
+            continue;
+        }
+        for event in device.fetch_events()? {
+            match (event.kind(), hi_res) {
+                (InputEventKind::RelAxis(RelativeAxisType::REL_HWHEEL_HI_RES), (true, _)) => {
+                    delta.0 += event.value();
+                }
+                (InputEventKind::RelAxis(RelativeAxisType::REL_HWHEEL), (false, _)) => {
+                    delta.0 += event.value() * WHEEL_CLICK;
+                }
+                (InputEventKind::RelAxis(RelativeAxisType::REL_WHEEL_HI_RES), (_, true)) => {
+                    delta.1 += event.value();
+                }
+                (InputEventKind::RelAxis(RelativeAxisType::REL_WHEEL), (_, false)) => {
+                    delta.1 += event.value() * WHEEL_CLICK;
+                }
+                (InputEventKind::Synchronization(Synchronization::SYN_REPORT), _)
+                    if delta != (0, 0) =>
+                {
+                    let timestamp = event.timestamp();
+                    if !scrolling {
+                        // Scrolling again takes over from any tail in progress
+                        let _ = sender.send(MomentumMessage::StopMovement);
+                        scrolling = true;
+                        velocity = (0.0, 0.0);
+                        last_report = None;
+                    }
+                    let interval = last_report
+                        .and_then(|last| timestamp.duration_since(last).ok())
+                        .filter(|interval| !interval.is_zero());
+                    if let Some(interval) = interval {
+                        let seconds = interval.as_secs_f64();
+                        let current = (f64::from(delta.0) / seconds, f64::from(delta.1) / seconds);
+                        velocity = (
+                            velocity.0 + (current.0 - velocity.0) * SMOOTHING,
+                            velocity.1 + (current.1 - velocity.1) * SMOOTHING,
+                        );
+                    }
+                    log::trace!("Wheel: delta = {:?}, velocity = {:.0?}", delta, velocity);
+                    last_report = Some(timestamp);
+                    delta = (0, 0);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Starts the kinetic tail of a finished scroll, if it was fast enough
+fn launch(
+    sender: &mpsc::Sender<MomentumMessage>,
+    context: CaptureContext,
+    (h, v): (f64, f64),
+    frame_period: time::Duration,
+) {
+    let speed = h.hypot(v);
+    if speed < MIN_TAIL_SPEED {
+        log::debug!("Wheel: scroll ended at {:.0} units/s, no tail", speed);
+        return;
+    }
+    let disabled = !context.control.enabled.load(Ordering::Relaxed);
+    if disabled
+        || context.session.is_inactive()
+        || context.focus.is_excluded()
+        || context.suppress.is_held()
+    {
+        log::debug!("Wheel: scroll ended, tail suppressed");
+        return;
+    }
+    log::debug!("Wheel: scroll ended at {:.0} units/s, starting tail", speed);
+    context.control.metrics.flick_armed();
+    // The emulation emits `velocity * speed_factor` per frame, which has to
+    // match the per-frame scroll amount, in the emitted wheel directions
+    let params = context.params.load();
+    let seconds = frame_period.as_secs_f64();
+    let _ = sender.send(MomentumMessage::StartMovement {
+        vx: -h * seconds / params.axis_speed_factor(GestureKind::Scroll, false),
+        vy: v * seconds / params.axis_speed_factor(GestureKind::Scroll, true),
+        interval: None,
+        routing: Some(Routing {
+            x: InertiaSink::Scroll,
+            y: InertiaSink::Scroll,
+        }),
+        chained: false,
+        fingers: 0,
+    });
+}